@@ -0,0 +1,139 @@
+use crate::geometry::{Point, Rect, Vector};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    Left,
+    CenterX,
+    Right,
+    Top,
+    CenterY,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spacing {
+    // outermost elements stay put, the space between neighbours is shared out evenly
+    EqualGap,
+    // outermost elements stay put, centers are spread evenly
+    EqualCenter,
+    // the first element stays put and the rest follow at this gap
+    Fixed(f64),
+}
+
+impl Axis {
+    pub(crate) fn cross(&self) -> Axis {
+        match self {
+            Axis::Horizontal => Axis::Vertical,
+            Axis::Vertical => Axis::Horizontal,
+        }
+    }
+
+    pub(crate) fn start(&self, bounds: &Rect) -> f64 {
+        match self {
+            Axis::Horizontal => bounds.left(),
+            Axis::Vertical => bounds.top(),
+        }
+    }
+
+    pub(crate) fn size(&self, bounds: &Rect) -> f64 {
+        match self {
+            Axis::Horizontal => bounds.width,
+            Axis::Vertical => bounds.height,
+        }
+    }
+
+    pub(crate) fn center(&self, bounds: &Rect) -> f64 {
+        self.start(bounds) + self.size(bounds) / 2.0
+    }
+
+    pub(crate) fn offset(&self, amount: f64) -> Vector {
+        match self {
+            Axis::Horizontal => Vector::new(amount, 0.0),
+            Axis::Vertical => Vector::new(0.0, amount),
+        }
+    }
+
+    pub(crate) fn end(&self, bounds: &Rect) -> f64 {
+        self.start(bounds) + self.size(bounds)
+    }
+
+    // a point given its coordinate along this axis and across it
+    pub(crate) fn point(&self, along: f64, across: f64) -> Point {
+        match self {
+            Axis::Horizontal => Point::new(along, across),
+            Axis::Vertical => Point::new(across, along),
+        }
+    }
+}
+
+// the move each element needs to line up with the combined bounds, in input order
+pub fn align_offsets(bounds: &[Rect], alignment: Alignment) -> Vec<Vector> {
+    let Some(selection) = bounds.iter().copied().reduce(|all, other| all.union(&other)) else {
+        return Vec::new();
+    };
+    bounds
+        .iter()
+        .map(|rect| match alignment {
+            Alignment::Left => Vector::new(selection.left() - rect.left(), 0.0),
+            Alignment::CenterX => Vector::new(selection.center().x - rect.center().x, 0.0),
+            Alignment::Right => Vector::new(selection.right() - rect.right(), 0.0),
+            Alignment::Top => Vector::new(0.0, selection.top() - rect.top()),
+            Alignment::CenterY => Vector::new(0.0, selection.center().y - rect.center().y),
+            Alignment::Bottom => Vector::new(0.0, selection.bottom() - rect.bottom()),
+        })
+        .collect()
+}
+
+// the move each element needs to be spread along the axis, in input order
+pub fn distribute_offsets(bounds: &[Rect], axis: Axis, spacing: Spacing) -> Vec<Vector> {
+    let mut offsets = vec![Vector::new(0.0, 0.0); bounds.len()];
+    let minimum = if matches!(spacing, Spacing::Fixed(_)) { 2 } else { 3 };
+    if bounds.len() < minimum {
+        return offsets;
+    }
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    let key = |index: &usize| match spacing {
+        Spacing::EqualCenter => axis.center(&bounds[*index]),
+        _ => axis.start(&bounds[*index]),
+    };
+    order.sort_by(|a, b| key(a).total_cmp(&key(b)).then(a.cmp(b)));
+
+    let first = &bounds[order[0]];
+    let last = &bounds[order[order.len() - 1]];
+    let steps = (order.len() - 1) as f64;
+    match spacing {
+        Spacing::EqualCenter => {
+            let step = (axis.center(last) - axis.center(first)) / steps;
+            for (position, index) in order.iter().enumerate() {
+                let target = axis.center(first) + step * position as f64;
+                offsets[*index] = axis.offset(target - axis.center(&bounds[*index]));
+            }
+        }
+        Spacing::EqualGap | Spacing::Fixed(_) => {
+            let gap = match spacing {
+                Spacing::Fixed(gap) => gap,
+                _ => {
+                    let span = axis.end(last) - axis.start(first);
+                    let occupied: f64 = order.iter().map(|index| axis.size(&bounds[*index])).sum();
+                    (span - occupied) / steps
+                }
+            };
+            let mut cursor = axis.start(first);
+            for index in &order {
+                offsets[*index] = axis.offset(cursor - axis.start(&bounds[*index]));
+                cursor += axis.size(&bounds[*index]) + gap;
+            }
+        }
+    }
+    offsets
+}
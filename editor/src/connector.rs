@@ -0,0 +1,298 @@
+use crate::elements::Element;
+use crate::geometry::{
+    closest_on_polygon_edge, flatten_bezier, point_in_polygon, polygon_edges, segment_intersection, Affine, Point,
+    Rect, Vector,
+};
+use crate::routing::{Heading, Routing};
+use serde::{Deserialize, Serialize};
+
+const CURVE_STEPS: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Anchor {
+    // midpoint of one side of the target's unrotated box
+    Side { side: Side },
+    // fraction of the target's unrotated box, (0, 0) top-left to (1, 1) bottom-right
+    Fixed { x: f64, y: f64 },
+    // where the line towards the other end leaves the target's outline
+    NearestEdge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub element_id: u64,
+    pub anchor: Anchor,
+}
+
+impl Binding {
+    pub fn new(element_id: u64, anchor: Anchor) -> Self {
+        Self { element_id, anchor }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Marker {
+    #[default]
+    None,
+    Triangle,
+    OpenTriangle,
+    Circle,
+    Diamond,
+    Bar,
+    CrowsFoot,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectorEnd {
+    Start,
+    End,
+}
+
+impl Element {
+    pub fn is_connector(&self) -> bool {
+        matches!(self, Element::Line(_) | Element::Arrow(_))
+    }
+
+    // labels ride on their container, so they are neither binding targets nor routing obstacles
+    pub fn can_bind(&self) -> bool {
+        matches!(
+            self,
+            Element::Rectangle(_)
+                | Element::Ellipse(_)
+                | Element::Diamond(_)
+                | Element::Image(_)
+                | Element::Polygon(_)
+                | Element::RegularPolygon(_)
+                | Element::Star(_)
+        ) || matches!(self, Element::Text(text) if text.container_id.is_none())
+    }
+
+    pub fn endpoints(&self) -> Option<(Point, Point)> {
+        match self {
+            Element::Line(line) => Some((line.start, line.end)),
+            Element::Arrow(arrow) => Some((arrow.start, arrow.end)),
+            _ => None,
+        }
+    }
+
+    pub fn set_endpoints(&mut self, start: Point, end: Point) -> bool {
+        let (current_start, current_end) = match self {
+            Element::Line(line) => (&mut line.start, &mut line.end),
+            Element::Arrow(arrow) => (&mut arrow.start, &mut arrow.end),
+            _ => return false,
+        };
+        if *current_start == start && *current_end == end {
+            return false;
+        }
+        *current_start = start;
+        *current_end = end;
+        true
+    }
+
+    pub fn routing(&self) -> Option<Routing> {
+        match self {
+            Element::Line(line) => Some(line.routing),
+            Element::Arrow(arrow) => Some(arrow.routing),
+            _ => None,
+        }
+    }
+
+    pub fn waypoints(&self) -> &[Point] {
+        match self {
+            Element::Line(line) => &line.waypoints,
+            Element::Arrow(arrow) => &arrow.waypoints,
+            _ => &[],
+        }
+    }
+
+    pub fn set_waypoints(&mut self, waypoints: Vec<Point>) -> bool {
+        let current = match self {
+            Element::Line(line) => &mut line.waypoints,
+            Element::Arrow(arrow) => &mut arrow.waypoints,
+            _ => return false,
+        };
+        if *current == waypoints {
+            return false;
+        }
+        *current = waypoints;
+        true
+    }
+
+    pub fn control_points(&self) -> &[Point] {
+        match self {
+            Element::Line(line) => &line.control_points,
+            Element::Arrow(arrow) => &arrow.control_points,
+            _ => &[],
+        }
+    }
+
+    pub fn control_points_mut(&mut self) -> Option<&mut Vec<Point>> {
+        match self {
+            Element::Line(line) => Some(&mut line.control_points),
+            Element::Arrow(arrow) => Some(&mut arrow.control_points),
+            _ => None,
+        }
+    }
+
+    // the drawn polyline: a flattened curve when there are control points,
+    // otherwise start, any routed waypoints, then end
+    pub fn connector_points(&self) -> Vec<Point> {
+        let Some((start, end)) = self.endpoints() else {
+            return Vec::new();
+        };
+        let controls = self.control_points();
+        if !controls.is_empty() {
+            return flatten_bezier(start, controls, end, CURVE_STEPS);
+        }
+        let mut points = vec![start];
+        points.extend_from_slice(self.waypoints());
+        points.push(end);
+        points
+    }
+
+    // the stored rotation, about the center of the drawn polyline
+    pub fn connector_transform(&self) -> Affine {
+        let angle = match self {
+            Element::Line(line) => line.rotation_angle,
+            Element::Arrow(arrow) => arrow.rotation_angle,
+            _ => return Affine::IDENTITY,
+        };
+        if angle == 0.0 {
+            return Affine::IDENTITY;
+        }
+        match Rect::from_points(self.connector_points()) {
+            Some(bounds) => Affine::rotate_around(bounds.center(), angle),
+            None => Affine::IDENTITY,
+        }
+    }
+
+    pub fn marker(&self, end: ConnectorEnd) -> Marker {
+        let (start_marker, end_marker) = match self {
+            Element::Line(line) => (line.start_marker, line.end_marker),
+            Element::Arrow(arrow) => (arrow.start_marker, arrow.end_marker),
+            _ => return Marker::None,
+        };
+        match end {
+            ConnectorEnd::Start => start_marker,
+            ConnectorEnd::End => end_marker,
+        }
+    }
+
+    pub fn marker_size(&self) -> Option<f64> {
+        match self {
+            Element::Line(line) => Some(line.marker_size),
+            Element::Arrow(arrow) => Some(arrow.marker_size),
+            _ => None,
+        }
+    }
+
+    pub fn binding(&self, end: ConnectorEnd) -> Option<&Binding> {
+        let (start_binding, end_binding) = match self {
+            Element::Line(line) => (&line.start_binding, &line.end_binding),
+            Element::Arrow(arrow) => (&arrow.start_binding, &arrow.end_binding),
+            _ => return None,
+        };
+        match end {
+            ConnectorEnd::Start => start_binding.as_ref(),
+            ConnectorEnd::End => end_binding.as_ref(),
+        }
+    }
+
+    pub fn set_binding(&mut self, end: ConnectorEnd, binding: Option<Binding>) -> bool {
+        let slot = match (self, end) {
+            (Element::Line(line), ConnectorEnd::Start) => &mut line.start_binding,
+            (Element::Line(line), ConnectorEnd::End) => &mut line.end_binding,
+            (Element::Arrow(arrow), ConnectorEnd::Start) => &mut arrow.start_binding,
+            (Element::Arrow(arrow), ConnectorEnd::End) => &mut arrow.end_binding,
+            _ => return false,
+        };
+        if *slot == binding {
+            return false;
+        }
+        *slot = binding;
+        true
+    }
+
+    pub fn is_bound_to(&self, id: u64) -> bool {
+        [ConnectorEnd::Start, ConnectorEnd::End]
+            .into_iter()
+            .any(|end| self.binding(end).is_some_and(|binding| binding.element_id == id))
+    }
+
+    pub fn resolve_anchor(&self, anchor: &Anchor, toward: Point) -> Option<Point> {
+        let (frame, _) = self.local_frame()?;
+        let transform = self.transform();
+        match anchor {
+            Anchor::Side { side } => {
+                let center = frame.center();
+                let local = match side {
+                    Side::Top => Point::new(center.x, frame.top()),
+                    Side::Right => Point::new(frame.right(), center.y),
+                    Side::Bottom => Point::new(center.x, frame.bottom()),
+                    Side::Left => Point::new(frame.left(), center.y),
+                };
+                Some(transform.apply(local))
+            }
+            Anchor::Fixed { x, y } => Some(transform.apply(Point::new(
+                frame.x + frame.width * x,
+                frame.y + frame.height * y,
+            ))),
+            Anchor::NearestEdge => {
+                let outline = self
+                    .outline()
+                    .unwrap_or_else(|| frame.corners().iter().map(|corner| transform.apply(*corner)).collect());
+                let center = transform.apply(frame.center());
+                if point_in_polygon(toward, &outline) || toward == center {
+                    return closest_on_polygon_edge(toward, &outline);
+                }
+                polygon_edges(&outline)
+                    .filter_map(|(start, end)| segment_intersection(center, toward, start, end))
+                    .min_by(|a, b| toward.distance(*a).total_cmp(&toward.distance(*b)))
+                    .or_else(|| closest_on_polygon_edge(toward, &outline))
+            }
+        }
+    }
+
+    pub fn anchor_center(&self) -> Option<Point> {
+        self.local_frame().map(|(frame, _)| self.transform().apply(frame.center()))
+    }
+
+    // the axis-aligned direction a connector leaves this element when attached at `point`
+    pub fn exit_heading(&self, anchor: &Anchor, point: Point) -> Option<Heading> {
+        let (frame, _) = self.local_frame()?;
+        let (dx, dy) = match anchor {
+            Anchor::Side { side } => match side {
+                Side::Top => (0.0, -1.0),
+                Side::Right => (1.0, 0.0),
+                Side::Bottom => (0.0, 1.0),
+                Side::Left => (-1.0, 0.0),
+            },
+            _ => {
+                let local = self.transform().inverse()?.apply(point);
+                let center = frame.center();
+                let half_width = (frame.width / 2.0).max(f64::EPSILON);
+                let half_height = (frame.height / 2.0).max(f64::EPSILON);
+                let (nx, ny) = ((local.x - center.x) / half_width, (local.y - center.y) / half_height);
+                if nx.abs() >= ny.abs() {
+                    (nx.signum(), 0.0)
+                } else {
+                    (0.0, ny.signum())
+                }
+            }
+        };
+        let direction = self.transform().apply_vector(Vector::new(dx, dy));
+        Some(Heading::from_vector(direction.x, direction.y))
+    }
+}
@@ -1,9 +1,27 @@
-use crate::elements::{Arrow, Diamond, Ellipse, Group, Image, Line, Path, Rectangle, Text};
-use crate::geometry::Point;
+use crate::align::{align_offsets, distribute_offsets, Alignment, Axis, Spacing};
+use crate::connector::{Binding, ConnectorEnd, Marker};
+use crate::routing::{route_elbow, Heading, RouteEnd, Routing, ROUTE_MARGIN};
+use crate::elements::{
+    Arrow, BlendMode, Diamond, Element, ElementKind, ElementPatch, Ellipse, Frame, Group, Image, Layer, Line,
+    Path, Polygon, Rectangle, RegularPolygon, Star, Text,
+};
+use crate::geometry::{Affine, Point, Rect, Vector, MAX_BEZIER_CONTROL_POINTS};
+use crate::fill::Fill;
+use crate::rich_text::{ListKind, TextStyle, TextStylePatch};
+use crate::rough::{sketch, Sketch};
+use crate::stroke::{process_path, simulate_pressure, PathOptions};
+use crate::snap::{snap_bounds, Snap, SnapHandle, SnapOptions};
+use crate::spatial::{SelectionMode, SpatialIndex};
+use crate::style::{Color, DashPattern, FontWeight, TextAlign};
+use crate::text_layout::{FallbackMetrics, FontMetrics, TextLayout, TextSizing, VerticalAlign};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+const ROUTE_SEARCH_DISTANCE: f64 = ROUTE_MARGIN * 10.0;
+const SNAP_SEARCH_DISTANCE: f64 = 1024.0;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct DocumentSnapshot {
+struct SerializedDocument {
     rectangles: Vec<Rectangle>,
     ellipses: Vec<Ellipse>,
     lines: Vec<Line>,
@@ -13,79 +31,180 @@ struct DocumentSnapshot {
     images: Vec<Image>,
     texts: Vec<Text>,
     groups: Vec<Group>,
+    #[serde(default)]
+    polygons: Vec<Polygon>,
+    #[serde(default)]
+    regular_polygons: Vec<RegularPolygon>,
+    #[serde(default)]
+    stars: Vec<Star>,
+    #[serde(default)]
+    frames: Vec<Frame>,
+    #[serde(default)]
+    layers: Vec<Layer>,
     next_id: u64,
 }
 
+impl SerializedDocument {
+    fn into_elements(self) -> BTreeMap<u64, Element> {
+        let elements = self
+            .rectangles
+            .into_iter()
+            .map(Element::Rectangle)
+            .chain(self.ellipses.into_iter().map(Element::Ellipse))
+            .chain(self.lines.into_iter().map(Element::Line))
+            .chain(self.arrows.into_iter().map(Element::Arrow))
+            .chain(self.diamonds.into_iter().map(Element::Diamond))
+            .chain(self.paths.into_iter().map(Element::Path))
+            .chain(self.images.into_iter().map(Element::Image))
+            .chain(self.texts.into_iter().map(Element::Text))
+            .chain(self.groups.into_iter().map(Element::Group))
+            .chain(self.polygons.into_iter().map(Element::Polygon))
+            .chain(self.regular_polygons.into_iter().map(Element::RegularPolygon))
+            .chain(self.stars.into_iter().map(Element::Star))
+            .chain(self.frames.into_iter().map(Element::Frame))
+            .chain(self.layers.into_iter().map(Element::Layer));
+        elements.map(|element| (element.id(), element)).collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ElementChange {
+    id: u64,
+    before: Option<Element>,
+    after: Option<Element>,
+}
+
+#[derive(Clone, Debug)]
+struct HistoryEntry {
+    label: Option<String>,
+    changes: Vec<ElementChange>,
+    next_id_before: u64,
+    next_id_after: u64,
+}
+
 pub struct Document {
-    rectangles: Vec<Rectangle>,
-    ellipses: Vec<Ellipse>,
-    lines: Vec<Line>,
-    arrows: Vec<Arrow>,
-    diamonds: Vec<Diamond>,
-    paths: Vec<Path>,
-    images: Vec<Image>,
-    texts: Vec<Text>,
-    groups: Vec<Group>,
+    elements: BTreeMap<u64, Element>,
     next_id: u64,
-    history: Vec<DocumentSnapshot>,
+    history: Vec<HistoryEntry>,
     history_index: usize,
     max_history: usize,
+    pending: BTreeMap<u64, Option<Element>>,
+    committed_next_id: u64,
+    transaction: Option<Transaction>,
+    spatial: SpatialIndex,
+    // reverse lookups kept up to date alongside the spatial index: the layer, frame and group
+    // holding each element, the label of each container and the connectors bound to each shape
+    layer_members: Relations,
+    frame_members: Relations,
+    group_members: Relations,
+    labels: Relations,
+    bindings: Relations,
+    // measures text for layout; every client may plug in its own
+    metrics: Box<dyn FontMetrics>,
+}
+
+// one kind of reference between elements, indexed from both ends
+#[derive(Default)]
+struct Relations {
+    // what each element refers to
+    targets: HashMap<u64, Vec<u64>>,
+    // who refers to each element
+    referrers: HashMap<u64, Vec<u64>>,
+}
+
+impl Relations {
+    fn set(&mut self, id: u64, targets: Vec<u64>) {
+        if self.targets.get(&id).map_or(targets.is_empty(), |old| *old == targets) {
+            return;
+        }
+        for target in self.targets.remove(&id).unwrap_or_default() {
+            if let Some(referrers) = self.referrers.get_mut(&target) {
+                referrers.retain(|referrer| *referrer != id);
+                if referrers.is_empty() {
+                    self.referrers.remove(&target);
+                }
+            }
+        }
+        for target in &targets {
+            let referrers = self.referrers.entry(*target).or_default();
+            if !referrers.contains(&id) {
+                referrers.push(id);
+            }
+        }
+        if !targets.is_empty() {
+            self.targets.insert(id, targets);
+        }
+    }
+
+    fn referrers(&self, id: u64) -> &[u64] {
+        self.referrers.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    fn clear(&mut self) {
+        self.targets.clear();
+        self.referrers.clear();
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Transaction {
+    label: String,
+    depth: usize,
+    // set by a nested rollback; the outermost level then reverts everything
+    poisoned: bool,
 }
 
 impl Document {
     pub fn new() -> Self {
-        let mut doc = Self {
-            rectangles: Vec::new(),
-            ellipses: Vec::new(),
-            lines: Vec::new(),
-            arrows: Vec::new(),
-            diamonds: Vec::new(),
-            paths: Vec::new(),
-            images: Vec::new(),
-            texts: Vec::new(),
-            groups: Vec::new(),
+        Self {
+            elements: BTreeMap::new(),
             next_id: 0,
             history: Vec::new(),
             history_index: 0,
             max_history: 100,
-        };
-        doc.save_snapshot();
-        doc
+            pending: BTreeMap::new(),
+            committed_next_id: 0,
+            transaction: None,
+            spatial: SpatialIndex::default(),
+            layer_members: Relations::default(),
+            frame_members: Relations::default(),
+            group_members: Relations::default(),
+            labels: Relations::default(),
+            bindings: Relations::default(),
+            metrics: Box::new(FallbackMetrics),
+        }
     }
 
     pub fn save_snapshot(&mut self) {
-        let snapshot = DocumentSnapshot {
-            rectangles: self.rectangles.clone(),
-            ellipses: self.ellipses.clone(),
-            lines: self.lines.clone(),
-            arrows: self.arrows.clone(),
-            diamonds: self.diamonds.clone(),
-            paths: self.paths.clone(),
-            images: self.images.clone(),
-            texts: self.texts.clone(),
-            groups: self.groups.clone(),
-            next_id: self.next_id,
-        };
+        if self.transaction.is_none() {
+            self.commit_pending(None);
+        }
+    }
 
-        if self.history_index > 0 {
-            let last_snapshot = &self.history[self.history_index - 1];
-            if last_snapshot.rectangles == snapshot.rectangles
-                && last_snapshot.ellipses == snapshot.ellipses
-                && last_snapshot.lines == snapshot.lines
-                && last_snapshot.arrows == snapshot.arrows
-                && last_snapshot.diamonds == snapshot.diamonds
-                && last_snapshot.paths == snapshot.paths
-                && last_snapshot.images == snapshot.images
-                && last_snapshot.texts == snapshot.texts
-                && last_snapshot.groups == snapshot.groups
-                && last_snapshot.next_id == snapshot.next_id
-            {
-                return;
-            }
+    fn commit_pending(&mut self, label: Option<String>) {
+        let pending = std::mem::take(&mut self.pending);
+        let changes: Vec<ElementChange> = pending
+            .into_iter()
+            .filter_map(|(id, before)| {
+                let after = self.elements.get(&id).cloned();
+                (before != after).then_some(ElementChange { id, before, after })
+            })
+            .collect();
+
+        if changes.is_empty() && self.next_id == self.committed_next_id {
+            return;
         }
 
+        let entry = HistoryEntry {
+            label,
+            changes,
+            next_id_before: self.committed_next_id,
+            next_id_after: self.next_id,
+        };
+        self.committed_next_id = self.next_id;
+
         self.history.truncate(self.history_index);
-        self.history.push(snapshot);
+        self.history.push(entry);
         self.history_index = self.history.len();
 
         if self.history.len() > self.max_history {
@@ -94,41 +213,153 @@ impl Document {
         }
     }
 
-    fn restore_snapshot(&mut self, snapshot: &DocumentSnapshot) {
-        self.rectangles = snapshot.rectangles.clone();
-        self.ellipses = snapshot.ellipses.clone();
-        self.lines = snapshot.lines.clone();
-        self.arrows = snapshot.arrows.clone();
-        self.diamonds = snapshot.diamonds.clone();
-        self.paths = snapshot.paths.clone();
-        self.images = snapshot.images.clone();
-        self.texts = snapshot.texts.clone();
-        self.groups = snapshot.groups.clone();
-        self.next_id = snapshot.next_id;
+    fn revert_pending(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        let ids: Vec<u64> = pending.keys().copied().collect();
+        for (id, before) in pending {
+            match before {
+                Some(element) => {
+                    self.elements.insert(id, element);
+                }
+                None => {
+                    self.elements.remove(&id);
+                }
+            }
+            self.reindex(id);
+        }
+        self.next_id = self.committed_next_id;
+        self.refresh_layout(&ids);
+    }
+
+    fn apply_history_entry(&mut self, index: usize, forward: bool) {
+        let entry = &self.history[index];
+        for change in &entry.changes {
+            let state = if forward { &change.after } else { &change.before };
+            match state {
+                Some(element) => {
+                    self.elements.insert(change.id, element.clone());
+                }
+                None => {
+                    self.elements.remove(&change.id);
+                }
+            }
+        }
+        self.next_id = if forward {
+            entry.next_id_after
+        } else {
+            entry.next_id_before
+        };
+        self.committed_next_id = self.next_id;
+        let ids: Vec<u64> = entry.changes.iter().map(|change| change.id).collect();
+        for id in &ids {
+            self.reindex(*id);
+        }
+        self.refresh_layout(&ids);
+    }
+
+    pub fn begin_transaction(&mut self, label: &str) {
+        match &mut self.transaction {
+            Some(transaction) => transaction.depth += 1,
+            None => {
+                self.commit_pending(None);
+                self.transaction = Some(Transaction {
+                    label: label.to_string(),
+                    depth: 1,
+                    poisoned: false,
+                });
+            }
+        }
+    }
+
+    // false once any level has rolled back, in which case the outermost commit reverts instead
+    pub fn commit_transaction(&mut self) -> bool {
+        let Some(transaction) = &mut self.transaction else {
+            return false;
+        };
+        transaction.depth -= 1;
+        let poisoned = transaction.poisoned;
+        if transaction.depth == 0 {
+            let label = transaction.label.clone();
+            self.transaction = None;
+            if poisoned {
+                self.revert_pending();
+            } else {
+                self.commit_pending(Some(label));
+            }
+        }
+        !poisoned
+    }
+
+    // a nested rollback only marks the transaction; nothing is reverted until the outermost level
+    // ends. Returns false when there is nothing left to roll back
+    pub fn rollback_transaction(&mut self) -> bool {
+        let Some(transaction) = &mut self.transaction else {
+            return false;
+        };
+        transaction.depth -= 1;
+        let already_poisoned = std::mem::replace(&mut transaction.poisoned, true);
+        if transaction.depth == 0 {
+            self.transaction = None;
+            self.revert_pending();
+        }
+        !already_poisoned
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    pub fn transact<T, E, F>(&mut self, label: &str, apply: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Self) -> Result<T, E>,
+    {
+        self.begin_transaction(label);
+        match apply(self) {
+            Ok(value) => {
+                self.commit_transaction();
+                Ok(value)
+            }
+            Err(error) => {
+                self.rollback_transaction();
+                Err(error)
+            }
+        }
+    }
+
+    pub fn undo_label(&self) -> Option<&str> {
+        self.history_index
+            .checked_sub(1)
+            .and_then(|index| self.history[index].label.as_deref())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.history
+            .get(self.history_index)
+            .and_then(|entry| entry.label.as_deref())
     }
 
     pub fn undo(&mut self) -> bool {
-        if self.history_index <= 1 {
+        if self.history_index == 0 || self.transaction.is_some() {
             return false;
         }
+        self.revert_pending();
         self.history_index -= 1;
-        let snapshot = self.history[self.history_index - 1].clone();
-        self.restore_snapshot(&snapshot);
+        self.apply_history_entry(self.history_index, false);
         true
     }
 
     pub fn redo(&mut self) -> bool {
-        if self.history_index >= self.history.len() {
+        if self.history_index >= self.history.len() || self.transaction.is_some() {
             return false;
         }
-        let snapshot = self.history[self.history_index].clone();
-        self.restore_snapshot(&snapshot);
+        self.revert_pending();
+        self.apply_history_entry(self.history_index, true);
         self.history_index += 1;
         true
     }
 
     pub fn can_undo(&self) -> bool {
-        self.history_index > 1
+        self.history_index > 0
     }
 
     pub fn can_redo(&self) -> bool {
@@ -136,386 +367,1217 @@ impl Document {
     }
 
     pub fn history_index(&self) -> usize {
-        self.history_index
+        self.history_index + 1
     }
 
-    pub fn add_rectangle(&mut self, position: Point, width: f64, height: f64) -> u64 {
-        let id = self.add_rectangle_without_snapshot(position, width, height);
-        self.save_snapshot();
-        id
+    fn track(&mut self, id: u64) -> bool {
+        if self.pending.contains_key(&id) {
+            return false;
+        }
+        self.pending.insert(id, self.elements.get(&id).cloned());
+        true
     }
 
-    pub fn add_rectangle_without_snapshot(
-        &mut self,
-        position: Point,
-        width: f64,
-        height: f64,
-    ) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut rect = Rectangle::new(id, position, width, height);
-        rect.z_index = self.get_max_z_index() + 1;
-        self.rectangles.push(rect);
+    fn element_mut(&mut self, id: u64) -> Option<&mut Element> {
+        if !self.elements.contains_key(&id) {
+            return None;
+        }
+        self.track(id);
+        self.elements.get_mut(&id)
+    }
+
+    fn insert_element(&mut self, element: Element) -> u64 {
+        let id = element.id();
+        self.track(id);
+        self.elements.insert(id, element);
+        self.reindex(id);
+        let bounds = self.obstacle_bounds(id);
+        self.obstacle_changed(None, bounds);
         id
     }
 
-    pub fn get_rectangles(&self) -> &[Rectangle] {
-        &self.rectangles
+    fn remove_element(&mut self, id: u64) -> Option<Element> {
+        if !self.elements.contains_key(&id) {
+            return None;
+        }
+        self.track(id);
+        let bounds = self.obstacle_bounds(id);
+        self.spatial.remove(id);
+        let removed = self.elements.remove(&id);
+        self.reindex_relations(id);
+        self.unbind_connectors_from(id);
+        self.obstacle_changed(bounds, None);
+        removed
     }
 
-    pub fn move_rectangle(&mut self, id: u64, new_position: Point, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter().find(|r| r.id == id) {
-            if rect.position != new_position {
-                if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-                    rect.position = new_position;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
+    pub fn bind_connector(
+        &mut self,
+        id: u64,
+        end: ConnectorEnd,
+        binding: Option<Binding>,
+    ) -> bool {
+        if let Some(binding) = &binding {
+            let valid_target = binding.element_id != id
+                && self
+                    .elements
+                    .get(&binding.element_id)
+                    .is_some_and(Element::can_bind);
+            if !valid_target {
+                return false;
+            }
+        }
+        self.modify(id, None, true, |element| {
+            element.is_connector() && element.set_binding(end, binding)
+        })
+    }
+
+    pub fn set_control_points(&mut self, id: u64, control_points: Vec<Point>) -> bool {
+        let patch = ElementPatch {
+            control_points: Some(control_points),
+            ..Default::default()
+        };
+        self.update(id, &patch)
+    }
+
+    pub fn add_control_point(&mut self, id: u64, point: Point) -> bool {
+        self.modify(id, None, true, |element| match element.control_points_mut() {
+            Some(controls) if controls.len() < MAX_BEZIER_CONTROL_POINTS => {
+                controls.push(point);
+                true
             }
-        }
+            _ => false,
+        })
     }
 
-    pub fn resize_rectangle(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter().find(|r| r.id == id) {
-            if rect.width != width || rect.height != height {
-                if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-                    rect.width = width;
-                    rect.height = height;
-                }
-                if save_history {
-                    self.save_snapshot();
+    pub fn move_control_point(&mut self, id: u64, index: usize, point: Point) -> bool {
+        self.modify(id, None, true, |element| {
+            match element.control_points_mut().and_then(|controls| controls.get_mut(index)) {
+                Some(control) if *control != point => {
+                    *control = point;
+                    true
                 }
+                _ => false,
             }
-        }
+        })
     }
 
-    pub fn delete_rectangle(&mut self, id: u64) {
-        let existed = self.delete_rectangle_without_snapshot(id);
-        if existed {
-            self.save_snapshot();
-        }
+    pub fn remove_control_point(&mut self, id: u64, index: usize) -> bool {
+        self.modify(id, None, true, |element| match element.control_points_mut() {
+            Some(controls) if index < controls.len() => {
+                controls.remove(index);
+                true
+            }
+            _ => false,
+        })
     }
 
-    pub fn delete_rectangle_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.rectangles.iter().any(|r| r.id == id);
-        self.rectangles.retain(|r| r.id != id);
-        existed
+    pub fn get_bound_connectors(&self, id: u64) -> Vec<u64> {
+        let mut connectors = self.bindings.referrers(id).to_vec();
+        connectors.sort_unstable();
+        connectors
     }
 
-    pub fn set_rectangle_stroke_color(&mut self, id: u64, color: String, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            if rect.stroke_color != color {
-                rect.stroke_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+    fn refresh_bindings(&mut self, id: u64) {
+        let Some(element) = self.elements.get(&id) else {
+            return;
+        };
+        let mut connectors = if element.is_connector() { vec![id] } else { Vec::new() };
+        connectors.extend(self.get_bound_connectors(id));
+        for connector in connectors {
+            self.refresh_connector(connector);
         }
     }
 
-    pub fn set_rectangle_fill_color(&mut self, id: u64, color: Option<String>, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            if rect.fill_color != color {
-                rect.fill_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+    fn refresh_connector(&mut self, id: u64) {
+        let Some(connector) = self.elements.get(&id) else {
+            return;
+        };
+        let Some((start, end)) = connector.endpoints() else {
+            return;
+        };
+        let start_binding = connector.binding(ConnectorEnd::Start).copied();
+        let end_binding = connector.binding(ConnectorEnd::End).copied();
+        let target = |binding: Option<Binding>| binding.and_then(|binding| self.elements.get(&binding.element_id));
+        let start_reference = target(start_binding).and_then(Element::anchor_center).unwrap_or(start);
+        let end_reference = target(end_binding).and_then(Element::anchor_center).unwrap_or(end);
+        let resolve = |binding: Option<Binding>, current: Point, toward: Point| {
+            binding
+                .and_then(|binding| target(Some(binding))?.resolve_anchor(&binding.anchor, toward))
+                .unwrap_or(current)
+        };
+        let new_start = resolve(start_binding, start, end_reference);
+        let new_end = resolve(end_binding, end, start_reference);
+        let waypoints = match connector.routing() {
+            Some(Routing::Elbow | Routing::Curved) if connector.control_points().is_empty() => {
+                let heading = |binding: Option<Binding>, point: Point, toward: Point| {
+                    binding
+                        .and_then(|binding| target(Some(binding))?.exit_heading(&binding.anchor, point))
+                        .unwrap_or_else(|| Heading::from_vector(toward.x - point.x, toward.y - point.y))
+                };
+                let route_start = RouteEnd {
+                    point: new_start,
+                    heading: heading(start_binding, new_start, new_end),
+                };
+                let route_end = RouteEnd {
+                    point: new_end,
+                    heading: heading(end_binding, new_end, new_start),
+                };
+                let obstacles = self.route_obstacles(id, new_start, new_end);
+                let route = route_elbow(route_start, route_end, &obstacles);
+                route[1..route.len() - 1].to_vec()
+            }
+            _ => Vec::new(),
+        };
+        if (new_start, new_end) == (start, end) && connector.waypoints() == waypoints.as_slice() {
+            return;
         }
+        if let Some(element) = self.element_mut(id) {
+            element.set_endpoints(new_start, new_end);
+            element.set_waypoints(waypoints);
+        }
+        self.reindex(id);
+        self.refresh_label(id);
     }
 
-    pub fn set_rectangle_line_width(&mut self, id: u64, width: f64, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            if (rect.line_width - width).abs() > f64::EPSILON {
-                rect.line_width = width.max(0.1);
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    fn route_obstacles(&self, connector: u64, start: Point, end: Point) -> Vec<Rect> {
+        let area = Rect::from_points([start, end])
+            .unwrap_or(Rect::new(start.x, start.y, 0.0, 0.0))
+            .expand(ROUTE_SEARCH_DISTANCE);
+        self.spatial
+            .query(area)
+            .into_iter()
+            .filter(|id| *id != connector)
+            .filter_map(|id| self.elements.get(&id))
+            .filter(|element| element.can_bind())
+            .filter_map(Element::bounds)
+            .collect()
     }
 
-    pub fn set_rectangle_dash_pattern(&mut self, id: u64, pattern: String, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            if rect.dash_pattern != pattern {
-                rect.dash_pattern = pattern;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    fn obstacle_bounds(&self, id: u64) -> Option<Rect> {
+        self.elements
+            .get(&id)
+            .filter(|element| element.can_bind())
+            .and_then(Element::bounds)
     }
 
-    pub fn set_rectangle_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            if (rect.rotation_angle - angle).abs() > f64::EPSILON {
-                rect.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+    fn obstacle_changed(&mut self, old_bounds: Option<Rect>, new_bounds: Option<Rect>) {
+        let area = match (old_bounds, new_bounds) {
+            (Some(old), Some(new)) => old.union(&new),
+            (Some(bounds), None) | (None, Some(bounds)) => bounds,
+            (None, None) => return,
+        };
+        self.reroute_around(area);
+    }
+
+    fn reroute_around(&mut self, area: Rect) {
+        let connectors: Vec<u64> = self
+            .spatial
+            .query(area.expand(ROUTE_SEARCH_DISTANCE))
+            .into_iter()
+            .filter(|id| {
+                self.elements[id]
+                    .routing()
+                    .is_some_and(|routing| routing != Routing::Straight)
+            })
+            .collect();
+        for connector in connectors {
+            self.refresh_connector(connector);
         }
     }
 
-    pub fn set_rectangle_border_radius(&mut self, id: u64, radius: f64, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            if (rect.border_radius - radius).abs() > f64::EPSILON {
-                rect.border_radius = radius.max(0.0);
-                if save_history {
-                    self.save_snapshot();
-                }
+    pub fn set_connector_routing(&mut self, id: u64, routing: Routing) -> bool {
+        let patch = ElementPatch {
+            routing: Some(routing),
+            ..Default::default()
+        };
+        self.update(id, &patch)
+    }
+
+    fn unbind_connectors_from(&mut self, id: u64) {
+        for connector in self.get_bound_connectors(id) {
+            if let Some(element) = self.element_mut(connector) {
+                for end in [ConnectorEnd::Start, ConnectorEnd::End] {
+                    if element.binding(end).is_some_and(|binding| binding.element_id == id) {
+                        element.set_binding(end, None);
+                    }
+                }
+            }
+            self.reindex_relations(connector);
+        }
+    }
+
+    fn reindex(&mut self, id: u64) {
+        let bounds = self.elements.get(&id).and_then(|element| {
+            let padding = element.line_width().unwrap_or(0.0) / 2.0;
+            element.bounds().map(|bounds| bounds.expand(padding))
+        });
+        match bounds {
+            Some(bounds) => self.spatial.insert(id, bounds),
+            None => {
+                self.spatial.remove(id);
+            }
+        }
+        self.reindex_relations(id);
+    }
+
+    fn reindex_relations(&mut self, id: u64) {
+        let (mut layer, mut frame, mut group, mut label, mut bound) = Default::default();
+        match self.elements.get(&id) {
+            Some(Element::Layer(element)) => layer = element.element_ids.clone(),
+            Some(Element::Frame(element)) => frame = element.element_ids.clone(),
+            Some(Element::Group(element)) => group = element.element_ids.clone(),
+            Some(Element::Text(element)) => label = element.container_id.into_iter().collect(),
+            Some(element) => {
+                bound = [ConnectorEnd::Start, ConnectorEnd::End]
+                    .into_iter()
+                    .filter_map(|end| element.binding(end))
+                    .map(|binding| binding.element_id)
+                    .collect()
             }
+            None => {}
         }
+        self.layer_members.set(id, layer);
+        self.frame_members.set(id, frame);
+        self.group_members.set(id, group);
+        self.labels.set(id, label);
+        self.bindings.set(id, bound);
     }
 
-    pub fn add_diamond(&mut self, position: Point, width: f64, height: f64) -> u64 {
-        let id = self.add_diamond_without_snapshot(position, width, height);
-        self.save_snapshot();
-        id
+    fn rebuild_spatial_index(&mut self) {
+        self.spatial.clear();
+        for relations in [
+            &mut self.layer_members,
+            &mut self.frame_members,
+            &mut self.group_members,
+            &mut self.labels,
+            &mut self.bindings,
+        ] {
+            relations.clear();
+        }
+        let ids: Vec<u64> = self.elements.keys().copied().collect();
+        for id in ids {
+            self.reindex(id);
+        }
     }
 
-    pub fn add_diamond_without_snapshot(
-        &mut self,
-        position: Point,
-        width: f64,
-        height: f64,
-    ) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut diamond = Diamond::new(id, position, width, height);
-        diamond.z_index = self.get_max_z_index() + 1;
-        self.diamonds.push(diamond);
-        id
+    pub fn elements_at_point(&self, point: Point, tolerance: f64) -> Vec<u64> {
+        let area = Rect::new(point.x, point.y, 0.0, 0.0).expand(tolerance.max(0.0));
+        let hits = self
+            .spatial
+            .query(area)
+            .into_iter()
+            .filter(|id| self.elements[id].contains_point(point, tolerance.max(0.0)))
+            .collect();
+        self.sort_topmost_first(hits)
     }
 
-    pub fn get_diamonds(&self) -> &[Diamond] {
-        &self.diamonds
+    pub fn elements_in_rect(&self, rect: Rect, mode: SelectionMode) -> Vec<u64> {
+        let rect = rect.normalized();
+        let hits = self
+            .spatial
+            .query(rect)
+            .into_iter()
+            .filter(|id| match mode {
+                SelectionMode::Intersect => self.elements[id].intersects_rect(&rect),
+                SelectionMode::Contain => self.elements[id]
+                    .bounds()
+                    .is_some_and(|bounds| rect.contains_rect(&bounds)),
+            })
+            .collect();
+        self.sort_topmost_first(hits)
     }
 
-    pub fn move_diamond(&mut self, id: u64, new_position: Point, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter().find(|d| d.id == id) {
-            if diamond.position != new_position {
-                if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-                    diamond.position = new_position;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
+    // `exclude` is what is being dragged; group members, frame children and attached connectors are skipped too
+    pub fn snap(&self, bounds: Rect, handle: SnapHandle, exclude: &[u64], options: &SnapOptions) -> Snap {
+        let mut excluded = HashSet::new();
+        for id in exclude {
+            excluded.insert(*id);
+            excluded.extend(self.stack_members(*id).unwrap_or_default());
+        }
+        let connectors: Vec<u64> = excluded
+            .iter()
+            .flat_map(|id| self.get_bound_connectors(*id))
+            .collect();
+        excluded.extend(connectors);
+        let others: Vec<Rect> = self
+            .spatial
+            .query(bounds.normalized().expand(SNAP_SEARCH_DISTANCE))
+            .into_iter()
+            .filter(|id| !excluded.contains(id) && !self.is_hidden(*id))
+            .filter_map(|id| self.elements.get(&id)?.bounds())
+            .collect();
+        snap_bounds(bounds, handle, &others, options)
+    }
+
+    // shapes on hidden layers are dropped
+    fn sort_topmost_first(&self, ids: Vec<u64>) -> Vec<u64> {
+        let mut ids: Vec<u64> = ids.into_iter().filter(|id| !self.is_hidden(*id)).collect();
+        ids.sort_by_key(|id| std::cmp::Reverse((self.stacking_key(*id), *id)));
+        ids
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Element> {
+        self.elements.get(&id)
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.elements.values()
+    }
+
+    pub fn update(&mut self, id: u64, patch: &ElementPatch) -> bool {
+        self.modify(id, None, true, |element| element.apply_patch(patch))
+    }
+
+    pub fn translate(&mut self, id: u64, delta_x: f64, delta_y: f64) -> bool {
+        self.translate_element(id, delta_x, delta_y, true)
+    }
+
+    fn translate_element(&mut self, id: u64, delta_x: f64, delta_y: f64, save_history: bool) -> bool {
+        if delta_x == 0.0 && delta_y == 0.0 {
+            return false;
+        }
+        if self.is_group(id) {
+            return self.modify_group(id, save_history, |element| {
+                element.translate(delta_x, delta_y);
+                true
+            });
+        }
+        if let Some(Element::Frame(frame)) = self.elements.get(&id) {
+            // children travel with the frame without being reassigned on the way
+            let mut changed = false;
+            for member in std::iter::once(id).chain(frame.element_ids.clone()) {
+                changed |= self.modify_element(member, None, false, false, |element| {
+                    element.translate(delta_x, delta_y);
+                    true
+                });
+            }
+            if changed && save_history {
+                self.save_snapshot();
             }
+            return changed;
         }
+        self.modify(id, None, save_history, |element| {
+            element.translate(delta_x, delta_y);
+            true
+        })
     }
 
-    pub fn resize_diamond(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter().find(|d| d.id == id) {
-            if diamond.width != width || diamond.height != height {
-                if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-                    diamond.width = width;
-                    diamond.height = height;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn align(&mut self, ids: &[u64], alignment: Alignment) -> bool {
+        let (ids, bounds) = self.arrangeable(ids);
+        let offsets = align_offsets(&bounds, alignment);
+        self.apply_offsets(&ids, &offsets, true)
     }
 
-    pub fn delete_diamond(&mut self, id: u64) {
-        let existed = self.delete_diamond_without_snapshot(id);
-        if existed {
+    pub fn distribute(&mut self, ids: &[u64], axis: Axis, spacing: Spacing) -> bool {
+        let (ids, bounds) = self.arrangeable(ids);
+        let offsets = distribute_offsets(&bounds, axis, spacing);
+        self.apply_offsets(&ids, &offsets, true)
+    }
+
+    // `transform` keeps `origin` fixed; groups and frames carry their members along
+    pub fn transform(&mut self, ids: &[u64], transform: &Affine, origin: Point) -> bool {
+        let transform = transform.around(origin);
+        let (ids, _) = self.arrangeable(ids);
+        let mut changed = false;
+        for id in ids {
+            if !self.is_element_locked(id) {
+                changed |= self.transform_element(id, &transform);
+            }
+        }
+        if changed {
             self.save_snapshot();
         }
+        changed
     }
 
-    pub fn delete_diamond_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.diamonds.iter().any(|d| d.id == id);
-        self.diamonds.retain(|d| d.id != id);
-        existed
+    // mirrors the selection about the center of its bounds
+    pub fn flip(&mut self, ids: &[u64], axis: Axis) -> bool {
+        let (_, bounds) = self.arrangeable(ids);
+        let Some(bounds) = bounds.into_iter().reduce(|all, other| all.union(&other)) else {
+            return false;
+        };
+        let mirror = match axis {
+            Axis::Horizontal => Affine::scale(-1.0, 1.0),
+            Axis::Vertical => Affine::scale(1.0, -1.0),
+        };
+        self.transform(ids, &mirror, bounds.center())
     }
 
-    pub fn set_diamond_stroke_color(&mut self, id: u64, color: String, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            if diamond.stroke_color != color {
-                diamond.stroke_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
+    fn transform_element(&mut self, id: u64, transform: &Affine) -> bool {
+        if self.is_group(id) {
+            return self.transform_group(id, transform, false);
+        }
+        if let Some(Element::Frame(frame)) = self.elements.get(&id) {
+            let mut changed = false;
+            for member in std::iter::once(id).chain(frame.element_ids.clone()) {
+                changed |= self.modify_element(member, None, false, false, |element| {
+                    element.apply_transform(transform);
+                    true
+                });
             }
+            return changed;
         }
+        self.modify(id, None, false, |element| {
+            element.apply_transform(transform);
+            true
+        })
     }
 
-    pub fn set_diamond_fill_color(&mut self, id: u64, color: Option<String>, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            if diamond.fill_color != color {
-                diamond.fill_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
+    // one entry per independently movable element; members of a selected group or frame move with it
+    fn arrangeable(&self, ids: &[u64]) -> (Vec<u64>, Vec<Rect>) {
+        let selected: HashSet<u64> = ids.iter().copied().collect();
+        let mut seen = HashSet::new();
+        let mut arranged = Vec::new();
+        let mut bounds = Vec::new();
+        for id in ids {
+            if !seen.insert(*id) || self.has_selected_ancestor(*id, &selected) {
+                continue;
+            }
+            if let Some(rect) = self.element_bounds(*id) {
+                arranged.push(*id);
+                bounds.push(rect);
             }
         }
+        (arranged, bounds)
     }
 
-    pub fn set_diamond_line_width(&mut self, id: u64, width: f64, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            if (diamond.line_width - width).abs() > f64::EPSILON {
-                diamond.line_width = width.max(0.1);
-                if save_history {
-                    self.save_snapshot();
+    fn has_selected_ancestor(&self, id: u64, selected: &HashSet<u64>) -> bool {
+        let mut visited = HashSet::from([id]);
+        let mut current = id;
+        loop {
+            if self.frame_of(current).is_some_and(|frame| selected.contains(&frame)) {
+                return true;
+            }
+            match self.parent_group(current) {
+                Some(parent) if visited.insert(parent) => {
+                    if selected.contains(&parent) {
+                        return true;
+                    }
+                    current = parent;
                 }
+                _ => return false,
             }
         }
     }
 
-    pub fn set_diamond_dash_pattern(&mut self, id: u64, pattern: String, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            if diamond.dash_pattern != pattern {
-                diamond.dash_pattern = pattern;
-                if save_history {
-                    self.save_snapshot();
-                }
+    // locked elements still count towards the bounds but stay where they are
+    fn apply_offsets(&mut self, ids: &[u64], offsets: &[Vector], save_history: bool) -> bool {
+        let mut changed = false;
+        for (id, offset) in ids.iter().zip(offsets) {
+            if !self.is_element_locked(*id) {
+                changed |= self.translate_element(*id, offset.x, offset.y, false);
             }
         }
+        if changed && save_history {
+            self.save_snapshot();
+        }
+        changed
     }
 
-    pub fn set_diamond_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            if (diamond.rotation_angle - angle).abs() > f64::EPSILON {
-                diamond.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+    pub fn delete(&mut self, id: u64) -> bool {
+        let existed = self.delete_without_snapshot(id);
+        if existed {
+            self.save_snapshot();
         }
+        existed
     }
 
-    pub fn set_diamond_border_radius(&mut self, id: u64, radius: f64, save_history: bool) {
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            if (diamond.border_radius - radius).abs() > f64::EPSILON {
-                diamond.border_radius = radius.max(0.0);
-                if save_history {
-                    self.save_snapshot();
-                }
+    // removes the whole subtree of a group, frame or layer, the label of a shape or connector, and
+    // drops parent groups left empty
+    pub fn delete_without_snapshot(&mut self, id: u64) -> bool {
+        if !self.elements.contains_key(&id) {
+            return false;
+        }
+        if let Some(label) = self.get_label(id) {
+            self.delete_without_snapshot(label);
+        }
+        let parent = self.detach_from_parent(id);
+        self.detach_from_frame(id);
+        self.detach_from_layer(id);
+        if let Some(Element::Frame(Frame { element_ids, .. }) | Element::Layer(Layer { element_ids, .. })) =
+            self.elements.get(&id)
+        {
+            for child in element_ids.clone() {
+                self.delete_without_snapshot(child);
             }
         }
+        for descendant in self.group_descendants(id) {
+            self.detach_from_frame(descendant);
+            self.detach_from_layer(descendant);
+            self.remove_element(descendant);
+        }
+        self.remove_element(id);
+        if let Some(parent) = parent {
+            self.prune_empty_group(parent);
+        }
+        true
     }
 
-    pub fn add_ellipse(&mut self, position: Point, radius_x: f64, radius_y: f64) -> u64 {
-        let id = self.add_ellipse_without_snapshot(position, radius_x, radius_y);
-        self.save_snapshot();
+    fn allocate_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
         id
     }
 
-    pub fn add_ellipse_without_snapshot(
-        &mut self,
-        position: Point,
-        radius_x: f64,
-        radius_y: f64,
-    ) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut ellipse = Ellipse::new(id, position, radius_x, radius_y);
-        ellipse.z_index = self.get_max_z_index() + 1;
-        self.ellipses.push(ellipse);
+    fn insert_shape(&mut self, mut element: Element) -> u64 {
+        element.set_z_index(self.get_max_z_index() + 1);
+        if let Element::Text(text) = &mut element {
+            text.reflow(self.metrics.as_ref());
+        }
+        let id = self.insert_element(element);
+        self.update_frame_membership(id);
         id
     }
 
-    pub fn get_ellipses(&self) -> &[Ellipse] {
-        &self.ellipses
+    fn modify<F>(&mut self, id: u64, kind: Option<ElementKind>, save_history: bool, apply: F) -> bool
+    where
+        F: FnOnce(&mut Element) -> bool,
+    {
+        self.modify_element(id, kind, save_history, true, apply)
     }
 
-    pub fn move_ellipse(&mut self, id: u64, new_position: Point, save_history: bool) {
-        if let Some(ellipse) = self.ellipses.iter().find(|e| e.id == id) {
-            if ellipse.position != new_position {
-                if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-                    ellipse.position = new_position;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
+    // `reparent` lets a changed shape join or leave frames
+    fn modify_element<F>(
+        &mut self,
+        id: u64,
+        kind: Option<ElementKind>,
+        save_history: bool,
+        reparent: bool,
+        apply: F,
+    ) -> bool
+    where
+        F: FnOnce(&mut Element) -> bool,
+    {
+        if !self
+            .elements
+            .get(&id)
+            .is_some_and(|element| kind.is_none_or(|kind| element.kind() == kind))
+        {
+            return false;
+        }
+        let newly_tracked = self.track(id);
+        let old_bounds = self.obstacle_bounds(id);
+        let changed = self.elements.get_mut(&id).is_some_and(apply);
+        if !changed && newly_tracked {
+            self.pending.remove(&id);
+        }
+        if changed {
+            self.reflow_text(id);
+            self.reindex(id);
+            self.refresh_bindings(id);
+            self.refresh_label(id);
+            let new_bounds = self.obstacle_bounds(id);
+            self.obstacle_changed(old_bounds, new_bounds);
+            if reparent {
+                self.update_frame_membership(id);
             }
         }
+        if changed && save_history {
+            self.save_snapshot();
+        }
+        changed
     }
 
-    pub fn resize_ellipse(&mut self, id: u64, radius_x: f64, radius_y: f64, save_history: bool) {
-        if let Some(ellipse) = self.ellipses.iter().find(|e| e.id == id) {
-            if ellipse.radius_x != radius_x || ellipse.radius_y != radius_y {
-                if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-                    ellipse.radius_x = radius_x;
-                    ellipse.radius_y = radius_y;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    fn update_kind(&mut self, id: u64, kind: ElementKind, patch: ElementPatch, save_history: bool) {
+        self.modify(id, Some(kind), save_history, |element| element.apply_patch(&patch));
     }
 
-    pub fn delete_ellipse(&mut self, id: u64) {
-        let existed = self.delete_ellipse_without_snapshot(id);
-        if existed {
+    fn delete_kind(&mut self, id: u64, kind: ElementKind) {
+        if self.delete_kind_without_snapshot(id, kind) {
             self.save_snapshot();
         }
     }
 
-    pub fn delete_ellipse_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.ellipses.iter().any(|e| e.id == id);
-        self.ellipses.retain(|e| e.id != id);
-        existed
+    fn delete_kind_without_snapshot(&mut self, id: u64, kind: ElementKind) -> bool {
+        if self.elements.get(&id).is_some_and(|element| element.kind() == kind) {
+            return self.delete_without_snapshot(id);
+        }
+        false
     }
 
-    pub fn set_ellipse_stroke_color(&mut self, id: u64, color: String, save_history: bool) {
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            if ellipse.stroke_color != color {
-                ellipse.stroke_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn add_rectangle(&mut self, position: Point, width: f64, height: f64) -> u64 {
+        let id = self.add_rectangle_without_snapshot(position, width, height);
+        self.save_snapshot();
+        id
     }
 
-    pub fn set_ellipse_fill_color(&mut self, id: u64, color: Option<String>, save_history: bool) {
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            if ellipse.fill_color != color {
-                ellipse.fill_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn add_rectangle_without_snapshot(
+        &mut self,
+        position: Point,
+        width: f64,
+        height: f64,
+    ) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::Rectangle(Rectangle::new(id, position, width, height)))
+    }
+
+    pub fn get_rectangles(&self) -> Vec<Rectangle> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Rectangle(rect) => Some(rect.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn move_rectangle(&mut self, id: u64, new_position: Point, save_history: bool) {
+        let patch = ElementPatch {
+            position: Some(new_position),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn resize_rectangle(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
+        let patch = ElementPatch {
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn delete_rectangle(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Rectangle);
+    }
+
+    pub fn delete_rectangle_without_snapshot(&mut self, id: u64) -> bool {
+        self.delete_kind_without_snapshot(id, ElementKind::Rectangle)
+    }
+
+    pub fn set_rectangle_stroke_color(&mut self, id: u64, color: Color, save_history: bool) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn set_rectangle_fill_color(&mut self, id: u64, color: Option<Color>, save_history: bool) {
+        let patch = ElementPatch {
+            fill: Some(color.map(Fill::Solid)),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn set_rectangle_line_width(&mut self, id: u64, width: f64, save_history: bool) {
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn set_rectangle_dash_pattern(&mut self, id: u64, pattern: DashPattern, save_history: bool) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn set_rectangle_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn set_rectangle_border_radius(&mut self, id: u64, radius: f64, save_history: bool) {
+        let patch = ElementPatch {
+            border_radius: Some(radius),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Rectangle, patch, save_history);
+    }
+
+    pub fn add_diamond(&mut self, position: Point, width: f64, height: f64) -> u64 {
+        let id = self.add_diamond_without_snapshot(position, width, height);
+        self.save_snapshot();
+        id
+    }
+
+    pub fn add_diamond_without_snapshot(
+        &mut self,
+        position: Point,
+        width: f64,
+        height: f64,
+    ) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::Diamond(Diamond::new(id, position, width, height)))
+    }
+
+    pub fn get_diamonds(&self) -> Vec<Diamond> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Diamond(diamond) => Some(diamond.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn move_diamond(&mut self, id: u64, new_position: Point, save_history: bool) {
+        let patch = ElementPatch {
+            position: Some(new_position),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn resize_diamond(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
+        let patch = ElementPatch {
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn delete_diamond(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Diamond);
+    }
+
+    pub fn delete_diamond_without_snapshot(&mut self, id: u64) -> bool {
+        self.delete_kind_without_snapshot(id, ElementKind::Diamond)
+    }
+
+    pub fn set_diamond_stroke_color(&mut self, id: u64, color: Color, save_history: bool) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn set_diamond_fill_color(&mut self, id: u64, color: Option<Color>, save_history: bool) {
+        let patch = ElementPatch {
+            fill: Some(color.map(Fill::Solid)),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn set_diamond_line_width(&mut self, id: u64, width: f64, save_history: bool) {
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn set_diamond_dash_pattern(&mut self, id: u64, pattern: DashPattern, save_history: bool) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn set_diamond_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn set_diamond_border_radius(&mut self, id: u64, radius: f64, save_history: bool) {
+        let patch = ElementPatch {
+            border_radius: Some(radius),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Diamond, patch, save_history);
+    }
+
+    pub fn add_polygon(&mut self, points: Vec<Point>) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::Polygon(Polygon::new(id, points)));
+        self.save_snapshot();
+        id
+    }
+
+    pub fn get_polygons(&self) -> Vec<Polygon> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Polygon(polygon) => Some(polygon.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn move_polygon(&mut self, id: u64, delta_x: f64, delta_y: f64) {
+        self.modify(id, Some(ElementKind::Polygon), true, |element| {
+            element.translate(delta_x, delta_y);
+            delta_x != 0.0 || delta_y != 0.0
+        });
+    }
+
+    pub fn resize_polygon(
+        &mut self,
+        id: u64,
+        new_x: f64,
+        new_y: f64,
+        new_width: f64,
+        new_height: f64,
+    ) {
+        self.modify(id, Some(ElementKind::Polygon), true, |element| {
+            let Element::Polygon(polygon) = element else {
+                return false;
+            };
+            fit_points(&mut polygon.points, new_x, new_y, new_width, new_height)
+        });
+    }
+
+    pub fn set_polygon_points(&mut self, id: u64, points: Vec<Point>) {
+        let patch = ElementPatch {
+            points: Some(points),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Polygon, patch, true);
+    }
+
+    pub fn delete_polygon(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Polygon);
+    }
+
+    pub fn set_polygon_stroke_color(&mut self, id: u64, color: Color) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Polygon, patch, true);
+    }
+
+    pub fn set_polygon_fill_color(&mut self, id: u64, color: Option<Color>) {
+        let patch = ElementPatch {
+            fill: Some(color.map(Fill::Solid)),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Polygon, patch, true);
+    }
+
+    pub fn set_polygon_line_width(&mut self, id: u64, width: f64) {
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Polygon, patch, true);
+    }
+
+    pub fn set_polygon_dash_pattern(&mut self, id: u64, pattern: DashPattern) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Polygon, patch, true);
+    }
+
+    pub fn set_polygon_rotation(&mut self, id: u64, angle: f64) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Polygon, patch, true);
+    }
+
+    pub fn set_polygon_border_radius(&mut self, id: u64, radius: f64) {
+        let patch = ElementPatch {
+            border_radius: Some(radius),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Polygon, patch, true);
+    }
+
+    pub fn add_regular_polygon(&mut self, position: Point, width: f64, height: f64, sides: u32) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::RegularPolygon(RegularPolygon::new(id, position, width, height, sides)));
+        self.save_snapshot();
+        id
+    }
+
+    pub fn get_regular_polygons(&self) -> Vec<RegularPolygon> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::RegularPolygon(shape) => Some(shape.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn move_regular_polygon(&mut self, id: u64, new_position: Point) {
+        let patch = ElementPatch {
+            position: Some(new_position),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn resize_regular_polygon(&mut self, id: u64, width: f64, height: f64) {
+        let patch = ElementPatch {
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn delete_regular_polygon(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::RegularPolygon);
+    }
+
+    pub fn set_regular_polygon_stroke_color(&mut self, id: u64, color: Color) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn set_regular_polygon_fill_color(&mut self, id: u64, color: Option<Color>) {
+        let patch = ElementPatch {
+            fill: Some(color.map(Fill::Solid)),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn set_regular_polygon_line_width(&mut self, id: u64, width: f64) {
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn set_regular_polygon_dash_pattern(&mut self, id: u64, pattern: DashPattern) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn set_regular_polygon_rotation(&mut self, id: u64, angle: f64) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn set_regular_polygon_border_radius(&mut self, id: u64, radius: f64) {
+        let patch = ElementPatch {
+            border_radius: Some(radius),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn set_regular_polygon_sides(&mut self, id: u64, sides: u32) {
+        let patch = ElementPatch {
+            sides: Some(sides),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::RegularPolygon, patch, true);
+    }
+
+    pub fn add_star(&mut self, position: Point, width: f64, height: f64, sides: u32) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::Star(Star::new(id, position, width, height, sides)));
+        self.save_snapshot();
+        id
+    }
+
+    pub fn get_stars(&self) -> Vec<Star> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Star(shape) => Some(shape.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn move_star(&mut self, id: u64, new_position: Point) {
+        let patch = ElementPatch {
+            position: Some(new_position),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn resize_star(&mut self, id: u64, width: f64, height: f64) {
+        let patch = ElementPatch {
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn delete_star(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Star);
+    }
+
+    pub fn set_star_stroke_color(&mut self, id: u64, color: Color) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn set_star_fill_color(&mut self, id: u64, color: Option<Color>) {
+        let patch = ElementPatch {
+            fill: Some(color.map(Fill::Solid)),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn set_star_line_width(&mut self, id: u64, width: f64) {
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn set_star_dash_pattern(&mut self, id: u64, pattern: DashPattern) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn set_star_rotation(&mut self, id: u64, angle: f64) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn set_star_border_radius(&mut self, id: u64, radius: f64) {
+        let patch = ElementPatch {
+            border_radius: Some(radius),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn set_star_sides(&mut self, id: u64, sides: u32) {
+        let patch = ElementPatch {
+            sides: Some(sides),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn set_star_inner_radius(&mut self, id: u64, ratio: f64) {
+        let patch = ElementPatch {
+            inner_radius: Some(ratio),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Star, patch, true);
+    }
+
+    pub fn add_ellipse(&mut self, position: Point, radius_x: f64, radius_y: f64) -> u64 {
+        let id = self.add_ellipse_without_snapshot(position, radius_x, radius_y);
+        self.save_snapshot();
+        id
+    }
+
+    pub fn add_ellipse_without_snapshot(
+        &mut self,
+        position: Point,
+        radius_x: f64,
+        radius_y: f64,
+    ) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::Ellipse(Ellipse::new(id, position, radius_x, radius_y)))
+    }
+
+    pub fn get_ellipses(&self) -> Vec<Ellipse> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Ellipse(ellipse) => Some(ellipse.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn move_ellipse(&mut self, id: u64, new_position: Point, save_history: bool) {
+        let patch = ElementPatch {
+            position: Some(new_position),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Ellipse, patch, save_history);
+    }
+
+    pub fn resize_ellipse(&mut self, id: u64, radius_x: f64, radius_y: f64, save_history: bool) {
+        let patch = ElementPatch {
+            radius_x: Some(radius_x),
+            radius_y: Some(radius_y),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Ellipse, patch, save_history);
+    }
+
+    pub fn delete_ellipse(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Ellipse);
+    }
+
+    pub fn delete_ellipse_without_snapshot(&mut self, id: u64) -> bool {
+        self.delete_kind_without_snapshot(id, ElementKind::Ellipse)
+    }
+
+    pub fn set_ellipse_stroke_color(&mut self, id: u64, color: Color, save_history: bool) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Ellipse, patch, save_history);
+    }
+
+    pub fn set_ellipse_fill_color(&mut self, id: u64, color: Option<Color>, save_history: bool) {
+        let patch = ElementPatch {
+            fill: Some(color.map(Fill::Solid)),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Ellipse, patch, save_history);
     }
 
     pub fn set_ellipse_line_width(&mut self, id: u64, width: f64, save_history: bool) {
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            if (ellipse.line_width - width).abs() > f64::EPSILON {
-                ellipse.line_width = width.max(0.1);
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Ellipse, patch, save_history);
     }
 
-    pub fn set_ellipse_dash_pattern(&mut self, id: u64, pattern: String, save_history: bool) {
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            if ellipse.dash_pattern != pattern {
-                ellipse.dash_pattern = pattern;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_ellipse_dash_pattern(&mut self, id: u64, pattern: DashPattern, save_history: bool) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Ellipse, patch, save_history);
     }
 
     pub fn set_ellipse_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            if (ellipse.rotation_angle - angle).abs() > f64::EPSILON {
-                ellipse.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Ellipse, patch, save_history);
     }
 
     pub fn add_line(&mut self, start: Point, end: Point) -> u64 {
@@ -525,87 +1587,91 @@ impl Document {
     }
 
     pub fn add_line_without_snapshot(&mut self, start: Point, end: Point) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut line = Line::new(id, start, end);
-        line.z_index = self.get_max_z_index() + 1;
-        self.lines.push(line);
-        id
+        let id = self.allocate_id();
+        self.insert_shape(Element::Line(Line::new(id, start, end)))
     }
 
-    pub fn get_lines(&self) -> &[Line] {
-        &self.lines
+    pub fn get_lines(&self) -> Vec<Line> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Line(line) => Some(line.clone()),
+                _ => None,
+            })
+            .collect()
     }
 
     pub fn move_line(&mut self, id: u64, new_start: Point, new_end: Point, save_history: bool) {
-        if let Some(line) = self.lines.iter().find(|l| l.id == id) {
-            if line.start != new_start || line.end != new_end {
-                if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-                    line.start = new_start;
-                    line.end = new_end;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            start: Some(new_start),
+            end: Some(new_end),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, save_history);
     }
 
     pub fn delete_line(&mut self, id: u64) {
-        let existed = self.delete_line_without_snapshot(id);
-        if existed {
-            self.save_snapshot();
-        }
+        self.delete_kind(id, ElementKind::Line);
     }
 
     pub fn delete_line_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.lines.iter().any(|l| l.id == id);
-        self.lines.retain(|l| l.id != id);
-        existed
+        self.delete_kind_without_snapshot(id, ElementKind::Line)
     }
 
-    pub fn set_line_stroke_color(&mut self, id: u64, color: String, save_history: bool) {
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            if line.stroke_color != color {
-                line.stroke_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_line_stroke_color(&mut self, id: u64, color: Color, save_history: bool) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, save_history);
     }
 
     pub fn set_line_line_width(&mut self, id: u64, width: f64, save_history: bool) {
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            if (line.line_width - width).abs() > f64::EPSILON {
-                line.line_width = width.max(0.1);
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, save_history);
     }
 
-    pub fn set_line_dash_pattern(&mut self, id: u64, pattern: String, save_history: bool) {
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            if line.dash_pattern != pattern {
-                line.dash_pattern = pattern;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_line_dash_pattern(&mut self, id: u64, pattern: DashPattern, save_history: bool) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, save_history);
+    }
+
+    pub fn set_line_start_marker(&mut self, id: u64, marker: Marker) {
+        let patch = ElementPatch {
+            start_marker: Some(marker),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, true);
+    }
+
+    pub fn set_line_end_marker(&mut self, id: u64, marker: Marker) {
+        let patch = ElementPatch {
+            end_marker: Some(marker),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, true);
+    }
+
+    pub fn set_line_marker_size(&mut self, id: u64, size: f64) {
+        let patch = ElementPatch {
+            marker_size: Some(size),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, true);
     }
 
     pub fn set_line_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            if (line.rotation_angle - angle).abs() > f64::EPSILON {
-                line.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Line, patch, save_history);
     }
 
     pub fn add_arrow(&mut self, start: Point, end: Point) -> u64 {
@@ -615,87 +1681,91 @@ impl Document {
     }
 
     pub fn add_arrow_without_snapshot(&mut self, start: Point, end: Point) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut arrow = Arrow::new(id, start, end);
-        arrow.z_index = self.get_max_z_index() + 1;
-        self.arrows.push(arrow);
-        id
+        let id = self.allocate_id();
+        self.insert_shape(Element::Arrow(Arrow::new(id, start, end)))
     }
 
-    pub fn get_arrows(&self) -> &[Arrow] {
-        &self.arrows
+    pub fn get_arrows(&self) -> Vec<Arrow> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Arrow(arrow) => Some(arrow.clone()),
+                _ => None,
+            })
+            .collect()
     }
 
     pub fn move_arrow(&mut self, id: u64, new_start: Point, new_end: Point, save_history: bool) {
-        if let Some(arrow) = self.arrows.iter().find(|a| a.id == id) {
-            if arrow.start != new_start || arrow.end != new_end {
-                if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-                    arrow.start = new_start;
-                    arrow.end = new_end;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            start: Some(new_start),
+            end: Some(new_end),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, save_history);
     }
 
     pub fn delete_arrow(&mut self, id: u64) {
-        let existed = self.delete_arrow_without_snapshot(id);
-        if existed {
-            self.save_snapshot();
-        }
+        self.delete_kind(id, ElementKind::Arrow);
     }
 
     pub fn delete_arrow_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.arrows.iter().any(|a| a.id == id);
-        self.arrows.retain(|a| a.id != id);
-        existed
+        self.delete_kind_without_snapshot(id, ElementKind::Arrow)
     }
 
-    pub fn set_arrow_stroke_color(&mut self, id: u64, color: String, save_history: bool) {
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            if arrow.stroke_color != color {
-                arrow.stroke_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_arrow_stroke_color(&mut self, id: u64, color: Color, save_history: bool) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, save_history);
     }
 
     pub fn set_arrow_line_width(&mut self, id: u64, width: f64, save_history: bool) {
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            if (arrow.line_width - width).abs() > f64::EPSILON {
-                arrow.line_width = width.max(0.1);
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, save_history);
     }
 
-    pub fn set_arrow_dash_pattern(&mut self, id: u64, pattern: String, save_history: bool) {
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            if arrow.dash_pattern != pattern {
-                arrow.dash_pattern = pattern;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_arrow_dash_pattern(&mut self, id: u64, pattern: DashPattern, save_history: bool) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, save_history);
+    }
+
+    pub fn set_arrow_start_marker(&mut self, id: u64, marker: Marker) {
+        let patch = ElementPatch {
+            start_marker: Some(marker),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, true);
+    }
+
+    pub fn set_arrow_end_marker(&mut self, id: u64, marker: Marker) {
+        let patch = ElementPatch {
+            end_marker: Some(marker),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, true);
+    }
+
+    pub fn set_arrow_marker_size(&mut self, id: u64, size: f64) {
+        let patch = ElementPatch {
+            marker_size: Some(size),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, true);
     }
 
     pub fn set_arrow_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            if (arrow.rotation_angle - angle).abs() > f64::EPSILON {
-                arrow.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Arrow, patch, save_history);
     }
 
     pub fn add_path(&mut self, points: Vec<Point>) -> u64 {
@@ -705,1097 +1775,1349 @@ impl Document {
     }
 
     pub fn add_path_without_snapshot(&mut self, points: Vec<Point>) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut path = Path::new(id, points);
-        path.z_index = self.get_max_z_index() + 1;
-        self.paths.push(path);
+        let id = self.allocate_id();
+        self.insert_shape(Element::Path(Path::new(id, points)))
+    }
+
+    pub fn get_paths(&self) -> Vec<Path> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Path(path) => Some(path.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn delete_path(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Path);
+    }
+
+    pub fn delete_path_without_snapshot(&mut self, id: u64) -> bool {
+        self.delete_kind_without_snapshot(id, ElementKind::Path)
+    }
+
+    pub fn set_path_stroke_color(&mut self, id: u64, color: Color, save_history: bool) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Path, patch, save_history);
+    }
+
+    pub fn set_path_line_width(&mut self, id: u64, width: f64, save_history: bool) {
+        let patch = ElementPatch {
+            line_width: Some(width),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Path, patch, save_history);
+    }
+
+    pub fn set_path_dash_pattern(&mut self, id: u64, pattern: DashPattern, save_history: bool) {
+        let patch = ElementPatch {
+            dash_pattern: Some(pattern),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Path, patch, save_history);
+    }
+
+    pub fn move_path(&mut self, id: u64, delta_x: f64, delta_y: f64, save_history: bool) {
+        self.modify(id, Some(ElementKind::Path), save_history, |element| {
+            element.translate(delta_x, delta_y);
+            delta_x != 0.0 || delta_y != 0.0
+        });
+    }
+
+    pub fn resize_path(
+        &mut self,
+        id: u64,
+        new_x: f64,
+        new_y: f64,
+        new_width: f64,
+        new_height: f64,
+        save_history: bool,
+    ) {
+        self.modify(id, Some(ElementKind::Path), save_history, |element| {
+            let Element::Path(path) = element else {
+                return false;
+            };
+            fit_points(&mut path.points, new_x, new_y, new_width, new_height)
+        });
+    }
+
+    pub fn set_path_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Path, patch, save_history);
+    }
+
+    pub fn set_path_points(&mut self, id: u64, points: Vec<Point>, save_history: bool) {
+        let patch = ElementPatch {
+            points: Some(points),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Path, patch, save_history);
+    }
+
+    pub fn set_path_pressures(&mut self, id: u64, pressures: Vec<f64>) {
+        let patch = ElementPatch {
+            pressures: Some(pressures),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Path, patch, true);
+    }
+
+    pub fn process_path(&mut self, id: u64, options: &PathOptions) -> bool {
+        self.modify(id, Some(ElementKind::Path), true, |element| {
+            let Element::Path(path) = element else {
+                return false;
+            };
+            let pressures = if path.has_pressure() {
+                path.pressures.clone()
+            } else if options.simulate_pressure {
+                simulate_pressure(&path.points)
+            } else {
+                Vec::new()
+            };
+            let (points, pressures) = if pressures.is_empty() {
+                (process_path(&path.points, options), Vec::new())
+            } else {
+                let samples: Vec<(Point, f64)> = path.points.iter().copied().zip(pressures).collect();
+                process_path(&samples, options).into_iter().unzip()
+            };
+            if points == path.points && pressures == path.pressures && path.smoothing == options.smoothing {
+                return false;
+            }
+            path.points = points;
+            path.pressures = pressures;
+            path.smoothing = options.smoothing;
+            true
+        })
+    }
+
+    pub fn add_image(
+        &mut self,
+        position: Point,
+        width: f64,
+        height: f64,
+        image_data: String,
+    ) -> u64 {
+        let id = self.add_image_without_snapshot(position, width, height, image_data);
+        self.save_snapshot();
         id
     }
 
-    pub fn get_paths(&self) -> &[Path] {
-        &self.paths
+    pub fn add_image_without_snapshot(
+        &mut self,
+        position: Point,
+        width: f64,
+        height: f64,
+        image_data: String,
+    ) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::Image(Image::new(id, position, width, height, image_data)))
+    }
+
+    pub fn get_images(&self) -> Vec<Image> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Image(image) => Some(image.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn move_image(&mut self, id: u64, new_position: Point, save_history: bool) {
+        let patch = ElementPatch {
+            position: Some(new_position),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Image, patch, save_history);
+    }
+
+    pub fn resize_image(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
+        let patch = ElementPatch {
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Image, patch, save_history);
+    }
+
+    pub fn set_image_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Image, patch, save_history);
+    }
+
+    pub fn delete_image(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Image);
+    }
+
+    pub fn delete_image_without_snapshot(&mut self, id: u64) -> bool {
+        self.delete_kind_without_snapshot(id, ElementKind::Image)
+    }
+
+    pub fn add_text(&mut self, position: Point, width: f64, height: f64, content: String) -> u64 {
+        let id = self.add_text_without_snapshot(position, width, height, content);
+        self.save_snapshot();
+        id
+    }
+
+    pub fn add_text_without_snapshot(
+        &mut self,
+        position: Point,
+        width: f64,
+        height: f64,
+        content: String,
+    ) -> u64 {
+        let id = self.allocate_id();
+        self.insert_shape(Element::Text(Text::new(id, position, width, height, content)))
+    }
+
+    pub fn get_texts(&self) -> Vec<Text> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // labels are centered texts that wrap inside a shape or sit on a connector's midpoint; each
+    // container holds at most one
+    pub fn add_label(&mut self, container_id: u64, content: String) -> Option<u64> {
+        let container = self.elements.get(&container_id).filter(|element| element.can_have_label())?;
+        if self.get_label(container_id).is_some() {
+            return None;
+        }
+        let (sizing, vertical_align) = if container.is_connector() {
+            (TextSizing::AutoWidth, VerticalAlign::Top)
+        } else {
+            (TextSizing::Fixed, VerticalAlign::Middle)
+        };
+        let id = self.allocate_id();
+        let mut text = Text::new(id, Point::new(0.0, 0.0), 0.0, 0.0, content);
+        text.container_id = Some(container_id);
+        text.text_align = TextAlign::Center;
+        text.sizing = sizing;
+        text.vertical_align = vertical_align;
+        self.fit_label(&mut text);
+        let id = self.insert_shape(Element::Text(text));
+        self.save_snapshot();
+        Some(id)
+    }
+
+    pub fn get_label(&self, container_id: u64) -> Option<u64> {
+        self.labels.referrers(container_id).first().copied()
+    }
+
+    // puts a label back on its container after either of them changed
+    fn refresh_label(&mut self, id: u64) {
+        let label = match self.elements.get(&id) {
+            Some(Element::Text(text)) if text.container_id.is_some() => id,
+            Some(element) if element.can_have_label() => match self.get_label(id) {
+                Some(label) => label,
+                None => return,
+            },
+            _ => return,
+        };
+        let Some(Element::Text(text)) = self.elements.get(&label) else {
+            return;
+        };
+        let mut text = text.clone();
+        if !self.fit_label(&mut text) {
+            return;
+        }
+        if let Some(element) = self.element_mut(label) {
+            *element = Element::Text(text);
+        }
+        self.reindex(label);
+        self.update_frame_membership(label);
+    }
+
+    // shapes lend their inner box and rotation; connector labels keep their own size and are
+    // centered on the midpoint. Returns whether the label moved
+    fn fit_label(&self, text: &mut Text) -> bool {
+        let Some(container) = text.container_id.and_then(|id| self.elements.get(&id)) else {
+            return false;
+        };
+        let before = (text.position, text.width, text.height, text.rotation_angle);
+        if let Some((frame, angle)) = container.label_bounds() {
+            text.position = Point::new(frame.x, frame.y);
+            text.width = frame.width;
+            text.height = frame.height;
+            text.rotation_angle = angle;
+        } else if let Some(anchor) = container.label_anchor() {
+            text.reflow(self.metrics.as_ref());
+            text.position = Point::new(anchor.x - text.width / 2.0, anchor.y - text.height / 2.0);
+            text.rotation_angle = 0.0;
+        }
+        (text.position, text.width, text.height, text.rotation_angle) != before
+    }
+
+    pub fn move_text(&mut self, id: u64, new_position: Point, save_history: bool) {
+        let patch = ElementPatch {
+            position: Some(new_position),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    // an auto-width text that is resized by hand starts wrapping at the new width
+    pub fn resize_text(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
+        let auto_width = matches!(self.elements.get(&id), Some(Element::Text(text)) if text.sizing == TextSizing::AutoWidth);
+        let patch = ElementPatch {
+            width: Some(width),
+            height: Some(height),
+            sizing: auto_width.then_some(TextSizing::AutoHeight),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn delete_text(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Text);
+    }
+
+    pub fn delete_text_without_snapshot(&mut self, id: u64) -> bool {
+        self.delete_kind_without_snapshot(id, ElementKind::Text)
+    }
+
+    pub fn set_text_content(&mut self, id: u64, content: String, save_history: bool) {
+        let patch = ElementPatch {
+            content: Some(content),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_font_family(&mut self, id: u64, font_family: String, save_history: bool) {
+        let patch = ElementPatch {
+            font_family: Some(font_family),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_font_size(&mut self, id: u64, font_size: f64, save_history: bool) {
+        let patch = ElementPatch {
+            font_size: Some(font_size),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_font_weight(&mut self, id: u64, font_weight: FontWeight, save_history: bool) {
+        let patch = ElementPatch {
+            font_weight: Some(font_weight),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_text_align(&mut self, id: u64, text_align: TextAlign, save_history: bool) {
+        let patch = ElementPatch {
+            text_align: Some(text_align),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_color(&mut self, id: u64, color: Color, save_history: bool) {
+        let patch = ElementPatch {
+            color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_opacity(&mut self, id: u64, opacity: f64, save_history: bool) {
+        let patch = ElementPatch {
+            opacity: Some(opacity),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
+        let patch = ElementPatch {
+            rotation_angle: Some(angle),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, save_history);
+    }
+
+    pub fn set_text_sizing(&mut self, id: u64, sizing: TextSizing) {
+        let patch = ElementPatch {
+            sizing: Some(sizing),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, true);
+    }
+
+    pub fn set_text_vertical_align(&mut self, id: u64, vertical_align: VerticalAlign) {
+        let patch = ElementPatch {
+            vertical_align: Some(vertical_align),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Text, patch, true);
     }
 
-    pub fn delete_path(&mut self, id: u64) {
-        let existed = self.delete_path_without_snapshot(id);
-        if existed {
-            self.save_snapshot();
+    // the same layout backs rendering, hit testing and export
+    pub fn text_layout(&self, id: u64) -> Option<TextLayout> {
+        match self.elements.get(&id) {
+            Some(Element::Text(text)) => Some(text.layout(self.metrics.as_ref())),
+            _ => None,
         }
     }
 
-    pub fn delete_path_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.paths.iter().any(|p| p.id == id);
-        self.paths.retain(|p| p.id != id);
-        existed
+    // the caret offset under a document point, taking the text's rotation and flips into account
+    pub fn text_offset_at(&self, id: u64, point: Point) -> Option<usize> {
+        let Some(element @ Element::Text(text)) = self.elements.get(&id) else {
+            return None;
+        };
+        let local = element.transform().inverse()?.apply(point);
+        Some(text.offset_at(&text.layout(self.metrics.as_ref()), local, self.metrics.as_ref()))
     }
 
-    pub fn set_path_stroke_color(&mut self, id: u64, color: String, save_history: bool) {
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            if path.stroke_color != color {
-                path.stroke_color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    // lays every text out again with the new metrics
+    pub fn set_font_metrics(&mut self, metrics: Box<dyn FontMetrics>) {
+        self.metrics = metrics;
+        let ids: Vec<u64> = self
+            .elements
+            .values()
+            .filter(|element| element.kind() == ElementKind::Text)
+            .map(Element::id)
+            .collect();
+        self.refresh_layout(&ids);
     }
 
-    pub fn set_path_line_width(&mut self, id: u64, width: f64, save_history: bool) {
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            if (path.line_width - width).abs() > f64::EPSILON {
-                path.line_width = width.max(0.1);
-                if save_history {
-                    self.save_snapshot();
-                }
+    // Text sizes, and the connectors and labels that follow them, are derived from the metrics,
+    // so bringing them up to date is not an edit and stays out of `pending`. Elements coming back
+    // from history go through here too, as they may have been laid out with other metrics
+    fn refresh_layout(&mut self, ids: &[u64]) {
+        let tracked: HashSet<u64> = self.pending.keys().copied().collect();
+        for &id in ids {
+            let old_bounds = self.obstacle_bounds(id);
+            if self.reflow_text(id) {
+                self.reindex(id);
+                self.refresh_bindings(id);
+                self.refresh_label(id);
+                let new_bounds = self.obstacle_bounds(id);
+                self.obstacle_changed(old_bounds, new_bounds);
             }
         }
+        self.pending.retain(|id, _| tracked.contains(id));
     }
 
-    pub fn set_path_dash_pattern(&mut self, id: u64, pattern: String, save_history: bool) {
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            if path.dash_pattern != pattern {
-                path.dash_pattern = pattern;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub(crate) fn font_metrics(&self) -> &dyn FontMetrics {
+        self.metrics.as_ref()
     }
 
-    pub fn move_path(&mut self, id: u64, delta_x: f64, delta_y: f64, save_history: bool) {
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            for point in &mut path.points {
-                point.x += delta_x;
-                point.y += delta_y;
-            }
-            if save_history {
-                self.save_snapshot();
-            }
+    fn reflow_text(&mut self, id: u64) -> bool {
+        match self.elements.get_mut(&id) {
+            Some(Element::Text(text)) => text.reflow(self.metrics.as_ref()),
+            _ => false,
         }
     }
 
-    pub fn resize_path(
-        &mut self,
-        id: u64,
-        new_x: f64,
-        new_y: f64,
-        new_width: f64,
-        new_height: f64,
-        save_history: bool,
-    ) {
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            if path.points.is_empty() {
-                return;
-            }
-
-            let mut min_x = path.points[0].x;
-            let mut min_y = path.points[0].y;
-            let mut max_x = path.points[0].x;
-            let mut max_y = path.points[0].y;
-
-            for point in &path.points {
-                min_x = min_x.min(point.x);
-                min_y = min_y.min(point.y);
-                max_x = max_x.max(point.x);
-                max_y = max_y.max(point.y);
-            }
+    // offsets are in characters; these keep the styling of the untouched text, unlike
+    // set_text_content which replaces everything
+    pub fn insert_text(&mut self, id: u64, offset: usize, text: &str, style: Option<TextStyle>) {
+        self.modify_text(id, true, |element| element.insert_text(offset, text, style.as_ref()));
+    }
 
-            let old_width = max_x - min_x;
-            let old_height = max_y - min_y;
+    pub fn delete_text_range(&mut self, id: u64, start: usize, end: usize) {
+        self.modify_text(id, true, |element| element.delete_range(start, end));
+    }
 
-            if old_width.abs() < f64::EPSILON || old_height.abs() < f64::EPSILON {
-                return;
-            }
+    pub fn set_text_style_range(&mut self, id: u64, start: usize, end: usize, patch: &TextStylePatch) {
+        self.modify_text(id, true, |element| element.set_style_range(start, end, patch));
+    }
 
-            let scale_x = new_width / old_width;
-            let scale_y = new_height / old_height;
+    pub fn set_text_list(&mut self, id: u64, start: usize, end: usize, list: ListKind) {
+        self.modify_text(id, true, |element| element.set_list_range(start, end, list));
+    }
 
-            for point in &mut path.points {
-                let relative_x = point.x - min_x;
-                let relative_y = point.y - min_y;
-                point.x = new_x + relative_x * scale_x;
-                point.y = new_y + relative_y * scale_y;
-            }
+    fn modify_text<F>(&mut self, id: u64, save_history: bool, apply: F)
+    where
+        F: FnOnce(&mut Text) -> bool,
+    {
+        self.modify(id, Some(ElementKind::Text), save_history, |element| match element {
+            Element::Text(text) => apply(text),
+            _ => false,
+        });
+    }
 
-            if save_history {
-                self.save_snapshot();
+    pub fn group_elements(&mut self, element_ids: Vec<u64>) -> u64 {
+        let id = self.allocate_id();
+        let mut members = Vec::new();
+        for member in element_ids {
+            if self.elements.contains_key(&member) && !members.contains(&member) {
+                members.push(member);
             }
         }
-    }
-
-    pub fn set_path_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            if (path.rotation_angle - angle).abs() > f64::EPSILON {
-                path.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
+        // the new group takes the place of its members in their previous parent
+        let mut parents = Vec::new();
+        for member in &members {
+            if let Some(parent) = self.detach_from_parent(*member) {
+                if !parents.contains(&parent) {
+                    parents.push(parent);
                 }
             }
         }
-    }
-
-    pub fn set_path_points(&mut self, id: u64, points: Vec<Point>, save_history: bool) {
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            if path.points != points {
-                path.points = points;
-                if save_history {
-                    self.save_snapshot();
+        self.insert_element(Element::Group(Group::new(id, members)));
+        if let Some(&parent) = parents.first() {
+            self.modify(parent, Some(ElementKind::Group), false, |element| match element {
+                Element::Group(group) => {
+                    group.element_ids.push(id);
+                    true
                 }
-            }
+                _ => false,
+            });
+        }
+        for parent in parents.into_iter().skip(1) {
+            self.prune_empty_group(parent);
         }
+        self.save_snapshot();
+        id
     }
 
-    pub fn add_image(
-        &mut self,
-        position: Point,
-        width: f64,
-        height: f64,
-        image_data: String,
-    ) -> u64 {
-        let id = self.add_image_without_snapshot(position, width, height, image_data);
+    // children move up into the parent group, if there is one
+    pub fn ungroup_elements(&mut self, group_id: u64) -> Vec<u64> {
+        if !self.is_group(group_id) {
+            return Vec::new();
+        }
+        let parent = self.parent_group(group_id);
+        let Some(Element::Group(group)) = self.remove_element(group_id) else {
+            return Vec::new();
+        };
+        if let Some(parent) = parent {
+            self.modify(parent, Some(ElementKind::Group), false, |element| match element {
+                Element::Group(parent) => {
+                    let index = parent
+                        .element_ids
+                        .iter()
+                        .position(|child| *child == group_id)
+                        .unwrap_or(parent.element_ids.len());
+                    parent.element_ids.retain(|child| *child != group_id);
+                    let index = index.min(parent.element_ids.len());
+                    parent.element_ids.splice(index..index, group.element_ids.iter().copied());
+                    true
+                }
+                _ => false,
+            });
+        }
         self.save_snapshot();
-        id
+        group.element_ids
     }
 
-    pub fn add_image_without_snapshot(
-        &mut self,
-        position: Point,
-        width: f64,
-        height: f64,
-        image_data: String,
-    ) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut image = Image::new(id, position, width, height, image_data);
-        image.z_index = self.get_max_z_index() + 1;
-        self.images.push(image);
-        id
+    pub fn delete_group(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Group);
     }
 
-    pub fn get_images(&self) -> &[Image] {
-        &self.images
+    fn is_group(&self, id: u64) -> bool {
+        matches!(self.elements.get(&id), Some(Element::Group(_)))
     }
 
-    pub fn move_image(&mut self, id: u64, new_position: Point, save_history: bool) {
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            image.position = new_position;
-            if save_history {
-                self.save_snapshot();
+    pub fn parent_group(&self, id: u64) -> Option<u64> {
+        self.group_members.referrers(id).first().copied()
+    }
+
+    // everything below the group, nested groups included; ids that close a cycle are skipped
+    pub fn group_descendants(&self, id: u64) -> Vec<u64> {
+        let mut descendants = Vec::new();
+        let mut visited = HashSet::from([id]);
+        let mut pending = vec![id];
+        while let Some(current) = pending.pop() {
+            let Some(Element::Group(group)) = self.elements.get(&current) else {
+                continue;
+            };
+            for child in &group.element_ids {
+                if self.elements.contains_key(child) && visited.insert(*child) {
+                    descendants.push(*child);
+                    pending.push(*child);
+                }
             }
         }
+        descendants
     }
 
-    pub fn resize_image(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            image.width = width.max(1.0);
-            image.height = height.max(1.0);
-            if save_history {
-                self.save_snapshot();
-            }
+    pub fn group_bounds(&self, id: u64) -> Option<Rect> {
+        self.group_descendants(id)
+            .iter()
+            .filter_map(|descendant| self.elements.get(descendant)?.bounds())
+            .reduce(|bounds, other| bounds.union(&other))
+    }
+
+    pub fn element_bounds(&self, id: u64) -> Option<Rect> {
+        if self.is_group(id) {
+            return self.group_bounds(id);
         }
+        self.elements.get(&id).and_then(Element::bounds)
     }
 
-    pub fn set_image_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            if (image.rotation_angle - angle).abs() > f64::EPSILON {
-                image.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
-                }
+    fn detach_from_parent(&mut self, id: u64) -> Option<u64> {
+        let parent = self.parent_group(id)?;
+        self.modify(parent, Some(ElementKind::Group), false, |element| match element {
+            Element::Group(group) => {
+                group.element_ids.retain(|child| *child != id);
+                true
             }
+            _ => false,
+        });
+        Some(parent)
+    }
+
+    fn prune_empty_group(&mut self, id: u64) {
+        let empty = matches!(self.elements.get(&id), Some(Element::Group(group)) if group.element_ids.is_empty());
+        if !empty {
+            return;
+        }
+        let parent = self.detach_from_parent(id);
+        self.remove_element(id);
+        if let Some(parent) = parent {
+            self.prune_empty_group(parent);
         }
     }
 
-    pub fn delete_image(&mut self, id: u64) {
-        let existed = self.delete_image_without_snapshot(id);
-        if existed {
+    // applies to the group itself and every descendant as one history step
+    fn modify_group<F>(&mut self, id: u64, save_history: bool, mut apply: F) -> bool
+    where
+        F: FnMut(&mut Element) -> bool,
+    {
+        if !self.is_group(id) {
+            return false;
+        }
+        let mut changed = false;
+        for member in std::iter::once(id).chain(self.group_descendants(id)) {
+            changed |= self.modify(member, None, false, &mut apply);
+        }
+        if changed && save_history {
             self.save_snapshot();
         }
+        changed
     }
 
-    pub fn delete_image_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.images.iter().any(|i| i.id == id);
-        self.images.retain(|i| i.id != id);
-        existed
+    fn transform_group(&mut self, id: u64, transform: &Affine, save_history: bool) -> bool {
+        self.modify_group(id, save_history, |element| {
+            element.apply_transform(transform);
+            true
+        })
     }
 
-    pub fn add_text(&mut self, position: Point, width: f64, height: f64, content: String) -> u64 {
-        let id = self.add_text_without_snapshot(position, width, height, content);
-        self.save_snapshot();
-        id
+    pub fn move_group(&mut self, id: u64, delta_x: f64, delta_y: f64) -> bool {
+        self.is_group(id) && self.translate(id, delta_x, delta_y)
     }
 
-    pub fn add_text_without_snapshot(
-        &mut self,
-        position: Point,
-        width: f64,
-        height: f64,
-        content: String,
-    ) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let mut text = Text::new(id, position, width, height, content);
-        text.z_index = self.get_max_z_index() + 1;
-        self.texts.push(text);
-        id
+    // rotates around the center of the group bounds
+    pub fn rotate_group(&mut self, id: u64, angle: f64) -> bool {
+        let Some(bounds) = self.group_bounds(id) else {
+            return false;
+        };
+        if angle == 0.0 {
+            return false;
+        }
+        self.transform_group(id, &Affine::rotate_around(bounds.center(), angle), true)
     }
 
-    pub fn get_texts(&self) -> &[Text] {
-        &self.texts
+    // scales the group so its bounds become the given rectangle
+    pub fn resize_group(
+        &mut self,
+        id: u64,
+        new_x: f64,
+        new_y: f64,
+        new_width: f64,
+        new_height: f64,
+    ) -> bool {
+        let Some(bounds) = self.group_bounds(id) else {
+            return false;
+        };
+        let scale = |new: f64, old: f64| if old.abs() < f64::EPSILON { 1.0 } else { new / old };
+        let transform = Affine::translate(-bounds.x, -bounds.y)
+            .then(&Affine::scale(scale(new_width, bounds.width), scale(new_height, bounds.height)))
+            .then(&Affine::translate(new_x, new_y));
+        self.transform_group(id, &transform, true)
+    }
+
+    pub fn get_groups(&self) -> Vec<Group> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Group(group) => Some(group.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // frames go behind everything and take in the shapes already inside them
+    pub fn add_frame(&mut self, position: Point, width: f64, height: f64, name: String) -> u64 {
+        let id = self.allocate_id();
+        // frames go behind the shapes outside any layer; only the frame itself is written, so
+        // adding one stays a single small history entry
+        let z_index = self
+            .elements
+            .values()
+            .filter(|element| self.layer_of(element.id()).is_none())
+            .filter_map(Element::z_index)
+            .min()
+            .map_or(0, |z| z - 1);
+        let mut frame = Frame::new(id, position, width, height, name);
+        frame.z_index = z_index;
+        self.insert_element(Element::Frame(frame));
+        self.refresh_frame(id);
+        self.save_snapshot();
+        id
     }
 
-    pub fn move_text(&mut self, id: u64, new_position: Point, save_history: bool) {
-        if let Some(text) = self.texts.iter().find(|t| t.id == id) {
-            if text.position != new_position {
-                if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-                    text.position = new_position;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn get_frames(&self) -> Vec<Frame> {
+        self.elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Frame(frame) => Some(frame.clone()),
+                _ => None,
+            })
+            .collect()
     }
 
-    pub fn resize_text(&mut self, id: u64, width: f64, height: f64, save_history: bool) {
-        if let Some(text) = self.texts.iter().find(|t| t.id == id) {
-            if text.width != width || text.height != height {
-                if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-                    text.width = width;
-                    text.height = height;
-                }
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn move_frame(&mut self, id: u64, new_position: Point) {
+        let Some(Element::Frame(frame)) = self.elements.get(&id) else {
+            return;
+        };
+        let delta = new_position - frame.position;
+        self.translate(id, delta.x, delta.y);
     }
 
-    pub fn delete_text(&mut self, id: u64) {
-        let existed = self.delete_text_without_snapshot(id);
-        if existed {
+    // children stay where they are; shapes now inside or outside the frame are reassigned
+    pub fn resize_frame(&mut self, id: u64, width: f64, height: f64) {
+        let patch = ElementPatch {
+            width: Some(width),
+            height: Some(height),
+            ..Default::default()
+        };
+        if self.modify(id, Some(ElementKind::Frame), false, |element| element.apply_patch(&patch)) {
+            self.refresh_frame(id);
             self.save_snapshot();
         }
     }
 
-    pub fn delete_text_without_snapshot(&mut self, id: u64) -> bool {
-        let existed = self.texts.iter().any(|t| t.id == id);
-        self.texts.retain(|t| t.id != id);
-        existed
+    pub fn set_frame_name(&mut self, id: u64, name: String) {
+        let patch = ElementPatch {
+            name: Some(name),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Frame, patch, true);
     }
 
-    pub fn set_text_content(&mut self, id: u64, content: String, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if text.content != content {
-                text.content = content;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_frame_stroke_color(&mut self, id: u64, color: Color) {
+        let patch = ElementPatch {
+            stroke_color: Some(color),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Frame, patch, true);
     }
 
-    pub fn set_text_font_family(&mut self, id: u64, font_family: String, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if text.font_family != font_family {
-                text.font_family = font_family;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_frame_fill_color(&mut self, id: u64, color: Option<Color>) {
+        let patch = ElementPatch {
+            fill: Some(color.map(Fill::Solid)),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Frame, patch, true);
     }
 
-    pub fn set_text_font_size(&mut self, id: u64, font_size: f64, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if (text.font_size - font_size).abs() > f64::EPSILON {
-                text.font_size = font_size;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn set_frame_clip(&mut self, id: u64, clip: bool) {
+        let patch = ElementPatch {
+            clip: Some(clip),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Frame, patch, true);
     }
 
-    pub fn set_text_font_weight(&mut self, id: u64, font_weight: String, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if text.font_weight != font_weight {
-                text.font_weight = font_weight;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn delete_frame(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Frame);
     }
 
-    pub fn set_text_text_align(&mut self, id: u64, text_align: String, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if text.text_align != text_align {
-                text.text_align = text_align;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+    pub fn frame_of(&self, id: u64) -> Option<u64> {
+        self.frame_members.referrers(id).first().copied()
     }
 
-    pub fn set_text_color(&mut self, id: u64, color: String, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if text.color != color {
-                text.color = color;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+    pub fn frame_children(&self, id: u64) -> Vec<u64> {
+        match self.elements.get(&id) {
+            Some(Element::Frame(frame)) => frame.element_ids.clone(),
+            _ => Vec::new(),
         }
     }
 
-    pub fn set_text_opacity(&mut self, id: u64, opacity: f64, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            let clamped = opacity.clamp(0.0, 1.0);
-            if (text.opacity - clamped).abs() > f64::EPSILON {
-                text.opacity = clamped;
-                if save_history {
-                    self.save_snapshot();
-                }
+    // a shape belongs to the topmost frame holding its center; its current frame wins while it still holds it
+    fn update_frame_membership(&mut self, id: u64) {
+        let Some(center) = self
+            .elements
+            .get(&id)
+            .filter(|element| !matches!(element.kind(), ElementKind::Group | ElementKind::Frame))
+            .and_then(Element::bounds)
+            .map(|bounds| bounds.center())
+        else {
+            return;
+        };
+        let holds = |element: &Element| match element {
+            Element::Frame(frame) => frame.rect().contains_point(center),
+            _ => false,
+        };
+        let current = self.frame_of(id);
+        if current.and_then(|frame| self.elements.get(&frame)).is_some_and(holds) {
+            return;
+        }
+        let target = self
+            .spatial
+            .query(Rect::new(center.x, center.y, 0.0, 0.0))
+            .into_iter()
+            .filter_map(|candidate| self.elements.get(&candidate))
+            .filter(|element| holds(element))
+            .max_by_key(|element| element.z_index())
+            .map(Element::id);
+        if target == current {
+            return;
+        }
+        self.detach_from_frame(id);
+        if let Some(target) = target {
+            if let Some(Element::Frame(frame)) = self.element_mut(target) {
+                frame.element_ids.push(id);
             }
+            self.reindex_relations(target);
         }
     }
 
-    pub fn set_text_rotation(&mut self, id: u64, angle: f64, save_history: bool) {
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if (text.rotation_angle - angle).abs() > f64::EPSILON {
-                text.rotation_angle = angle;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+    fn detach_from_frame(&mut self, id: u64) {
+        let Some(frame_id) = self.frame_of(id) else {
+            return;
+        };
+        if let Some(Element::Frame(frame)) = self.element_mut(frame_id) {
+            frame.element_ids.retain(|child| *child != id);
         }
+        self.reindex_relations(frame_id);
     }
 
-    pub fn group_elements(&mut self, element_ids: Vec<u64>) -> u64 {
-        let id = self.next_id;
-        self.next_id += 1;
-        let group = Group::new(id, element_ids);
-        self.groups.push(group);
+    fn refresh_frame(&mut self, id: u64) {
+        let Some(Element::Frame(frame)) = self.elements.get(&id) else {
+            return;
+        };
+        let rect = frame.rect();
+        let mut candidates = frame.element_ids.clone();
+        candidates.extend(self.spatial.query(rect).into_iter().filter(|candidate| {
+            self.elements
+                .get(candidate)
+                .and_then(Element::bounds)
+                .is_some_and(|bounds| rect.contains_point(bounds.center()))
+        }));
+        for candidate in candidates {
+            self.update_frame_membership(candidate);
+        }
+    }
+
+    // new layers go on top; shapes start outside any layer, below all layers, until moved into one
+    pub fn add_layer(&mut self, name: String) -> u64 {
+        let id = self.allocate_id();
+        let index = self.get_layers().last().map_or(0, |layer| layer.index + 1);
+        self.insert_element(Element::Layer(Layer::new(id, name, index)));
         self.save_snapshot();
         id
     }
 
-    pub fn ungroup_elements(&mut self, group_id: u64) -> Vec<u64> {
-        if let Some(index) = self.groups.iter().position(|g| g.id == group_id) {
-            let group = self.groups.remove(index);
-            self.save_snapshot();
-            return group.element_ids;
-        }
-        Vec::new()
+    // bottom layer first
+    pub fn get_layers(&self) -> Vec<Layer> {
+        let mut layers: Vec<Layer> = self
+            .elements
+            .values()
+            .filter_map(|element| match element {
+                Element::Layer(layer) => Some(layer.clone()),
+                _ => None,
+            })
+            .collect();
+        layers.sort_by_key(|layer| (layer.index, layer.id));
+        layers
     }
 
-    pub fn get_groups(&self) -> &[Group] {
-        &self.groups
+    pub fn layer_of(&self, id: u64) -> Option<u64> {
+        self.layer_members.referrers(id).first().copied()
     }
 
-    pub fn bring_shape_to_front(&mut self, id: u64) {
-        let max_z = self.get_max_z_index();
-        let new_z = max_z + 1;
-
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            rect.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            ellipse.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            diamond.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            line.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            arrow.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            path.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            image.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            text.z_index = new_z;
-            self.save_snapshot();
-        }
+    pub fn set_layer_name(&mut self, id: u64, name: String) {
+        let patch = ElementPatch {
+            name: Some(name),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Layer, patch, true);
     }
 
-    pub fn bring_shape_forward(&mut self, id: u64) {
-        let current_z = self.get_shape_z_index(id);
-        if current_z.is_none() {
-            return;
-        }
-        let current_z = current_z.unwrap();
-        let max_z = self.get_max_z_index();
-
-        if current_z >= max_z {
-            return;
-        }
+    pub fn set_layer_visible(&mut self, id: u64, visible: bool) {
+        let patch = ElementPatch {
+            visible: Some(visible),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Layer, patch, true);
+    }
 
-        let mut next_z: Option<i32> = None;
-        for rect in &self.rectangles {
-            if rect.id != id && rect.z_index > current_z {
-                next_z = Some(next_z.map_or(rect.z_index, |z: i32| z.min(rect.z_index)));
-            }
-        }
-        for ellipse in &self.ellipses {
-            if ellipse.id != id && ellipse.z_index > current_z {
-                next_z = Some(next_z.map_or(ellipse.z_index, |z: i32| z.min(ellipse.z_index)));
-            }
-        }
-        for diamond in &self.diamonds {
-            if diamond.id != id && diamond.z_index > current_z {
-                next_z = Some(next_z.map_or(diamond.z_index, |z: i32| z.min(diamond.z_index)));
-            }
-        }
-        for line in &self.lines {
-            if line.id != id && line.z_index > current_z {
-                next_z = Some(next_z.map_or(line.z_index, |z: i32| z.min(line.z_index)));
-            }
-        }
-        for arrow in &self.arrows {
-            if arrow.id != id && arrow.z_index > current_z {
-                next_z = Some(next_z.map_or(arrow.z_index, |z: i32| z.min(arrow.z_index)));
-            }
-        }
-        for path in &self.paths {
-            if path.id != id && path.z_index > current_z {
-                next_z = Some(next_z.map_or(path.z_index, |z: i32| z.min(path.z_index)));
-            }
-        }
-        for image in &self.images {
-            if image.id != id && image.z_index > current_z {
-                next_z = Some(next_z.map_or(image.z_index, |z: i32| z.min(image.z_index)));
-            }
-        }
-        for text in &self.texts {
-            if text.id != id && text.z_index > current_z {
-                next_z = Some(next_z.map_or(text.z_index, |z: i32| z.min(text.z_index)));
-            }
-        }
+    pub fn set_layer_locked(&mut self, id: u64, locked: bool) {
+        let patch = ElementPatch {
+            locked: Some(locked),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Layer, patch, true);
+    }
 
-        let new_z = next_z.unwrap_or(current_z + 1);
+    pub fn set_layer_opacity(&mut self, id: u64, opacity: f64) {
+        let patch = ElementPatch {
+            opacity: Some(opacity),
+            ..Default::default()
+        };
+        self.update_kind(id, ElementKind::Layer, patch, true);
+    }
 
-        for rect in &mut self.rectangles {
-            if rect.id != id && rect.z_index == new_z {
-                rect.z_index = current_z;
-                break;
-            }
-        }
-        for ellipse in &mut self.ellipses {
-            if ellipse.id != id && ellipse.z_index == new_z {
-                ellipse.z_index = current_z;
-                break;
-            }
-        }
-        for diamond in &mut self.diamonds {
-            if diamond.id != id && diamond.z_index == new_z {
-                diamond.z_index = current_z;
-                break;
-            }
-        }
-        for line in &mut self.lines {
-            if line.id != id && line.z_index == new_z {
-                line.z_index = current_z;
-                break;
-            }
-        }
-        for arrow in &mut self.arrows {
-            if arrow.id != id && arrow.z_index == new_z {
-                arrow.z_index = current_z;
-                break;
-            }
-        }
-        for path in &mut self.paths {
-            if path.id != id && path.z_index == new_z {
-                path.z_index = current_z;
-                break;
-            }
-        }
-        for image in &mut self.images {
-            if image.id != id && image.z_index == new_z {
-                image.z_index = current_z;
-                break;
+    // moves the layer to `index` in bottom-to-top order
+    pub fn reorder_layer(&mut self, id: u64, index: usize) -> bool {
+        let mut layers: Vec<u64> = self.get_layers().iter().map(|layer| layer.id).collect();
+        let Some(current) = layers.iter().position(|layer| *layer == id) else {
+            return false;
+        };
+        layers.remove(current);
+        layers.insert(index.min(layers.len()), id);
+        let mut changed = false;
+        for (position, layer) in layers.into_iter().enumerate() {
+            if let Some(Element::Layer(layer)) = self.elements.get(&layer) {
+                if layer.index == position as i32 {
+                    continue;
+                }
             }
-        }
-        for text in &mut self.texts {
-            if text.id != id && text.z_index == new_z {
-                text.z_index = current_z;
-                break;
+            if let Some(Element::Layer(layer)) = self.element_mut(layer) {
+                layer.index = position as i32;
+                changed = true;
             }
         }
-
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            rect.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            ellipse.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            diamond.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            line.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            arrow.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            path.z_index = new_z;
+        if changed {
             self.save_snapshot();
-            return;
         }
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            image.z_index = new_z;
-            self.save_snapshot();
-            return;
+        changed
+    }
+
+    // groups and frames bring their members along; moved shapes land on top of the target layer
+    pub fn move_to_layer(&mut self, element_ids: Vec<u64>, layer_id: u64) -> bool {
+        if !matches!(self.elements.get(&layer_id), Some(Element::Layer(_))) {
+            return false;
         }
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            text.z_index = new_z;
+        let mut moving = HashSet::new();
+        for id in element_ids {
+            moving.insert(id);
+            moving.extend(self.stack_members(id).unwrap_or_default());
+        }
+        let order = self.render_order_of(|element| moving.contains(&element.id()));
+        let mut changed = false;
+        for id in order {
+            if self.layer_of(id) == Some(layer_id) {
+                continue;
+            }
+            self.detach_from_layer(id);
+            let new_z = self
+                .layer_members(layer_id)
+                .iter()
+                .filter_map(|member| self.get_shape_z_index(*member))
+                .max()
+                .map_or(0, |z| z + 1);
+            if let Some(Element::Layer(layer)) = self.element_mut(layer_id) {
+                layer.element_ids.push(id);
+            }
+            self.reindex_relations(layer_id);
+            self.set_shape_z_index(id, new_z);
+            changed = true;
+        }
+        if changed {
             self.save_snapshot();
         }
+        changed
     }
 
-    pub fn send_shape_backward(&mut self, id: u64) {
-        let current_z = self.get_shape_z_index(id);
-        if current_z.is_none() {
-            return;
+    pub fn delete_layer(&mut self, id: u64) {
+        self.delete_kind(id, ElementKind::Layer);
+    }
+
+    fn layer_members(&self, id: u64) -> Vec<u64> {
+        match self.elements.get(&id) {
+            Some(Element::Layer(layer)) => layer.element_ids.clone(),
+            _ => Vec::new(),
         }
-        let current_z = current_z.unwrap();
+    }
 
-        if current_z <= 0 {
+    fn detach_from_layer(&mut self, id: u64) {
+        let Some(layer_id) = self.layer_of(id) else {
             return;
+        };
+        if let Some(Element::Layer(layer)) = self.element_mut(layer_id) {
+            layer.element_ids.retain(|member| *member != id);
         }
+        self.reindex_relations(layer_id);
+    }
 
-        let mut prev_z: Option<i32> = None;
-        for rect in &self.rectangles {
-            if rect.id != id && rect.z_index < current_z {
-                prev_z = Some(prev_z.map_or(rect.z_index, |z: i32| z.max(rect.z_index)));
-            }
-        }
-        for ellipse in &self.ellipses {
-            if ellipse.id != id && ellipse.z_index < current_z {
-                prev_z = Some(prev_z.map_or(ellipse.z_index, |z: i32| z.max(ellipse.z_index)));
-            }
-        }
-        for diamond in &self.diamonds {
-            if diamond.id != id && diamond.z_index < current_z {
-                prev_z = Some(prev_z.map_or(diamond.z_index, |z: i32| z.max(diamond.z_index)));
-            }
-        }
-        for line in &self.lines {
-            if line.id != id && line.z_index < current_z {
-                prev_z = Some(prev_z.map_or(line.z_index, |z: i32| z.max(line.z_index)));
-            }
-        }
-        for arrow in &self.arrows {
-            if arrow.id != id && arrow.z_index < current_z {
-                prev_z = Some(prev_z.map_or(arrow.z_index, |z: i32| z.max(arrow.z_index)));
-            }
-        }
-        for path in &self.paths {
-            if path.id != id && path.z_index < current_z {
-                prev_z = Some(prev_z.map_or(path.z_index, |z: i32| z.max(path.z_index)));
-            }
-        }
-        for image in &self.images {
-            if image.id != id && image.z_index < current_z {
-                prev_z = Some(prev_z.map_or(image.z_index, |z: i32| z.max(image.z_index)));
-            }
-        }
-        for text in &self.texts {
-            if text.id != id && text.z_index < current_z {
-                prev_z = Some(prev_z.map_or(text.z_index, |z: i32| z.max(text.z_index)));
-            }
+    fn layer(&self, id: u64) -> Option<&Layer> {
+        match self.elements.get(&self.layer_of(id)?) {
+            Some(Element::Layer(layer)) => Some(layer),
+            _ => None,
         }
+    }
+
+    fn is_hidden(&self, id: u64) -> bool {
+        self.layer(id).is_some_and(|layer| !layer.visible)
+    }
 
-        let new_z = prev_z.unwrap_or(current_z - 1);
+    pub fn is_element_visible(&self, id: u64) -> bool {
+        self.elements.contains_key(&id) && !self.is_hidden(id)
+    }
 
-        for rect in &mut self.rectangles {
-            if rect.id != id && rect.z_index == new_z {
-                rect.z_index = current_z;
-                break;
-            }
-        }
-        for ellipse in &mut self.ellipses {
-            if ellipse.id != id && ellipse.z_index == new_z {
-                ellipse.z_index = current_z;
-                break;
-            }
-        }
-        for diamond in &mut self.diamonds {
-            if diamond.id != id && diamond.z_index == new_z {
-                diamond.z_index = current_z;
-                break;
-            }
-        }
-        for line in &mut self.lines {
-            if line.id != id && line.z_index == new_z {
-                line.z_index = current_z;
-                break;
-            }
-        }
-        for arrow in &mut self.arrows {
-            if arrow.id != id && arrow.z_index == new_z {
-                arrow.z_index = current_z;
-                break;
-            }
-        }
-        for path in &mut self.paths {
-            if path.id != id && path.z_index == new_z {
-                path.z_index = current_z;
-                break;
-            }
-        }
-        for image in &mut self.images {
-            if image.id != id && image.z_index == new_z {
-                image.z_index = current_z;
-                break;
-            }
+    pub fn element_layer_opacity(&self, id: u64) -> f64 {
+        self.layer(id).map_or(1.0, |layer| layer.opacity)
+    }
+
+    // visible shapes bottom to top: layer by layer, shapes outside any layer first
+    pub fn render_order(&self) -> Vec<u64> {
+        self.render_order_of(|element| !self.is_hidden(element.id()))
+    }
+
+    fn render_order_of<F>(&self, include: F) -> Vec<u64>
+    where
+        F: Fn(&Element) -> bool,
+    {
+        let mut ids: Vec<(Option<i32>, i32, u64)> = self
+            .elements
+            .values()
+            .filter(|element| include(element))
+            .filter_map(|element| {
+                let (layer, z) = self.stacking_key(element.id())?;
+                Some((layer, z, element.id()))
+            })
+            .collect();
+        ids.sort();
+        ids.into_iter().map(|(_, _, id)| id).collect()
+    }
+
+    // (layer index, z index) for a stacked shape; no layer sorts below any layer
+    fn stacking_key(&self, id: u64) -> Option<(Option<i32>, i32)> {
+        let z = self.get_shape_z_index(id)?;
+        Some((self.layer(id).map(|layer| layer.index), z))
+    }
+
+    fn set_shape_z_index(&mut self, id: u64, z_index: i32) -> bool {
+        if self.get_shape_z_index(id).is_none() {
+            return false;
         }
-        for text in &mut self.texts {
-            if text.id != id && text.z_index == new_z {
-                text.z_index = current_z;
-                break;
-            }
+        if let Some(element) = self.element_mut(id) {
+            element.set_z_index(z_index);
         }
+        true
+    }
 
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            rect.z_index = new_z;
+    fn swap_shape_z_index(&mut self, id: u64, current_z: i32, other_id: u64, new_z: i32) {
+        self.set_shape_z_index(other_id, current_z);
+        if self.set_shape_z_index(id, new_z) {
             self.save_snapshot();
-            return;
         }
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            ellipse.z_index = new_z;
-            self.save_snapshot();
+    }
+
+    // z-ordering is scoped to a layer, so only shapes sharing the layer take part
+    fn layer_peers(&self, id: u64) -> Vec<(u64, i32)> {
+        let layer = self.layer_of(id);
+        self.elements
+            .values()
+            .filter(|element| element.id() != id && self.layer_of(element.id()) == layer)
+            .filter_map(|element| element.z_index().map(|z| (element.id(), z)))
+            .collect()
+    }
+
+    pub fn bring_shape_to_front(&mut self, id: u64) {
+        if let Some(members) = self.stack_members(id) {
+            self.restack(&members, Restack::Front);
             return;
         }
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            diamond.z_index = new_z;
+        let new_z = self.get_max_z_index() + 1;
+        if self.set_shape_z_index(id, new_z) {
             self.save_snapshot();
-            return;
         }
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            line.z_index = new_z;
-            self.save_snapshot();
+    }
+
+    pub fn bring_shape_forward(&mut self, id: u64) {
+        if let Some(members) = self.stack_members(id) {
+            self.restack(&members, Restack::Forward);
             return;
         }
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            arrow.z_index = new_z;
-            self.save_snapshot();
+        let Some(current_z) = self.get_shape_z_index(id) else {
             return;
+        };
+        let above = self
+            .layer_peers(id)
+            .into_iter()
+            .filter(|(_, z)| *z > current_z)
+            .min_by_key(|(_, z)| *z);
+        if let Some((other_id, new_z)) = above {
+            self.swap_shape_z_index(id, current_z, other_id, new_z);
         }
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            path.z_index = new_z;
-            self.save_snapshot();
+    }
+
+    pub fn send_shape_backward(&mut self, id: u64) {
+        if let Some(members) = self.stack_members(id) {
+            self.restack(&members, Restack::Backward);
             return;
         }
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            image.z_index = new_z;
-            self.save_snapshot();
+        let Some(current_z) = self.get_shape_z_index(id) else {
             return;
-        }
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            text.z_index = new_z;
-            self.save_snapshot();
+        };
+        let below = self
+            .layer_peers(id)
+            .into_iter()
+            .filter(|(_, z)| *z < current_z)
+            .max_by_key(|(_, z)| *z);
+        if let Some((other_id, new_z)) = below {
+            self.swap_shape_z_index(id, current_z, other_id, new_z);
         }
     }
 
     pub fn send_shape_to_back(&mut self, id: u64) {
-        let new_z = 0;
-
-        let current_z = self.get_shape_z_index(id);
-        if current_z.is_none() || current_z.unwrap() == 0 {
+        if let Some(members) = self.stack_members(id) {
+            self.restack(&members, Restack::Back);
             return;
         }
+        let current_z = match self.get_shape_z_index(id) {
+            Some(z) if z != 0 => z,
+            _ => return,
+        };
 
-        for rect in &mut self.rectangles {
-            if rect.id != id && rect.z_index < current_z.unwrap() {
-                rect.z_index += 1;
-            }
-        }
-        for ellipse in &mut self.ellipses {
-            if ellipse.id != id && ellipse.z_index < current_z.unwrap() {
-                ellipse.z_index += 1;
-            }
-        }
-        for diamond in &mut self.diamonds {
-            if diamond.id != id && diamond.z_index < current_z.unwrap() {
-                diamond.z_index += 1;
-            }
-        }
-        for line in &mut self.lines {
-            if line.id != id && line.z_index < current_z.unwrap() {
-                line.z_index += 1;
-            }
-        }
-        for arrow in &mut self.arrows {
-            if arrow.id != id && arrow.z_index < current_z.unwrap() {
-                arrow.z_index += 1;
-            }
-        }
-        for path in &mut self.paths {
-            if path.id != id && path.z_index < current_z.unwrap() {
-                path.z_index += 1;
-            }
-        }
-        for image in &mut self.images {
-            if image.id != id && image.z_index < current_z.unwrap() {
-                image.z_index += 1;
-            }
-        }
-        for text in &mut self.texts {
-            if text.id != id && text.z_index < current_z.unwrap() {
-                text.z_index += 1;
-            }
+        let below: Vec<(u64, i32)> = self
+            .layer_peers(id)
+            .into_iter()
+            .filter(|(_, z)| *z < current_z)
+            .collect();
+        for (other_id, z) in below {
+            self.set_shape_z_index(other_id, z + 1);
         }
 
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            rect.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            ellipse.z_index = new_z;
-            self.save_snapshot();
-            return;
-        }
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            diamond.z_index = new_z;
+        if self.set_shape_z_index(id, 0) {
             self.save_snapshot();
-            return;
-        }
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            line.z_index = new_z;
-            self.save_snapshot();
-            return;
         }
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            arrow.z_index = new_z;
-            self.save_snapshot();
-            return;
+    }
+
+    // groups move their shapes, frames themselves along with their children
+    fn stack_members(&self, id: u64) -> Option<HashSet<u64>> {
+        match self.elements.get(&id)? {
+            Element::Group(_) => Some(self.group_descendants(id).into_iter().collect()),
+            Element::Frame(frame) => Some(std::iter::once(id).chain(frame.element_ids.iter().copied()).collect()),
+            _ => None,
         }
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            path.z_index = new_z;
-            self.save_snapshot();
-            return;
+    }
+
+    // moves the members as one block, keeping their order inside it; members on different layers
+    // are restacked within each of their layers
+    fn restack(&mut self, members: &HashSet<u64>, restack: Restack) {
+        let layers: BTreeSet<Option<u64>> = members
+            .iter()
+            .filter(|id| self.get_shape_z_index(**id).is_some())
+            .map(|id| self.layer_of(*id))
+            .collect();
+        let mut changed = false;
+        for layer in layers {
+            changed |= self.restack_layer(members, layer, restack);
         }
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            image.z_index = new_z;
+        if changed {
             self.save_snapshot();
-            return;
         }
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            text.z_index = new_z;
-            self.save_snapshot();
+    }
+
+    fn restack_layer(&mut self, members: &HashSet<u64>, layer: Option<u64>, restack: Restack) -> bool {
+        let mut order: Vec<(i32, u64)> = self
+            .elements
+            .values()
+            .filter(|element| self.layer_of(element.id()) == layer)
+            .filter_map(|element| element.z_index().map(|z| (z, element.id())))
+            .collect();
+        order.sort();
+        let Some(first) = order.iter().position(|(_, id)| members.contains(id)) else {
+            return false;
+        };
+        let (block, mut rest): (Vec<u64>, Vec<u64>) =
+            order.iter().map(|(_, id)| *id).partition(|id| members.contains(id));
+        let last = order.iter().rposition(|(_, id)| members.contains(id)).unwrap_or(first);
+        let index = match restack {
+            Restack::Front => rest.len(),
+            Restack::Forward => (last + 2 - block.len()).min(rest.len()),
+            Restack::Backward => first.saturating_sub(1),
+            Restack::Back => 0,
+        };
+        rest.splice(index..index, block);
+
+        let mut changed = false;
+        for (z, element_id) in rest.into_iter().enumerate() {
+            if self.get_shape_z_index(element_id) != Some(z as i32) {
+                changed |= self.set_shape_z_index(element_id, z as i32);
+            }
         }
+        changed
     }
 
     fn get_shape_z_index(&self, id: u64) -> Option<i32> {
-        if let Some(rect) = self.rectangles.iter().find(|r| r.id == id) {
-            return Some(rect.z_index);
-        }
-        if let Some(ellipse) = self.ellipses.iter().find(|e| e.id == id) {
-            return Some(ellipse.z_index);
-        }
-        if let Some(diamond) = self.diamonds.iter().find(|d| d.id == id) {
-            return Some(diamond.z_index);
-        }
-        if let Some(line) = self.lines.iter().find(|l| l.id == id) {
-            return Some(line.z_index);
-        }
-        if let Some(arrow) = self.arrows.iter().find(|a| a.id == id) {
-            return Some(arrow.z_index);
-        }
-        if let Some(path) = self.paths.iter().find(|p| p.id == id) {
-            return Some(path.z_index);
-        }
-        if let Some(image) = self.images.iter().find(|i| i.id == id) {
-            return Some(image.z_index);
-        }
-        if let Some(text) = self.texts.iter().find(|t| t.id == id) {
-            return Some(text.z_index);
-        }
-        None
+        self.elements.get(&id).and_then(Element::z_index)
     }
 
     fn get_max_z_index(&self) -> i32 {
-        let mut max_z = 0i32;
-        for rect in &self.rectangles {
-            max_z = max_z.max(rect.z_index);
-        }
-        for ellipse in &self.ellipses {
-            max_z = max_z.max(ellipse.z_index);
-        }
-        for diamond in &self.diamonds {
-            max_z = max_z.max(diamond.z_index);
-        }
-        for line in &self.lines {
-            max_z = max_z.max(line.z_index);
-        }
-        for arrow in &self.arrows {
-            max_z = max_z.max(arrow.z_index);
-        }
-        for path in &self.paths {
-            max_z = max_z.max(path.z_index);
-        }
-        for image in &self.images {
-            max_z = max_z.max(image.z_index);
-        }
-        for text in &self.texts {
-            max_z = max_z.max(text.z_index);
-        }
-        max_z
+        self.elements
+            .values()
+            .filter_map(Element::z_index)
+            .fold(0, i32::max)
     }
 
     fn normalize_z_indices(&mut self) {
-        use std::collections::HashMap;
-
-        let mut shapes: Vec<(u64, i32)> = Vec::new();
-
-        for rect in &self.rectangles {
-            shapes.push((rect.id, rect.z_index));
-        }
-        for ellipse in &self.ellipses {
-            shapes.push((ellipse.id, ellipse.z_index));
-        }
-        for diamond in &self.diamonds {
-            shapes.push((diamond.id, diamond.z_index));
-        }
-        for line in &self.lines {
-            shapes.push((line.id, line.z_index));
-        }
-        for arrow in &self.arrows {
-            shapes.push((arrow.id, arrow.z_index));
-        }
-        for path in &self.paths {
-            shapes.push((path.id, path.z_index));
-        }
-        for image in &self.images {
-            shapes.push((image.id, image.z_index));
-        }
-        for text in &self.texts {
-            shapes.push((text.id, text.z_index));
-        }
+        let mut shapes: Vec<(u64, i32)> = self
+            .elements
+            .values()
+            .filter_map(|element| element.z_index().map(|z| (element.id(), z)))
+            .collect();
 
-        shapes.sort_by(|a, b| a.1.cmp(&b.1));
+        shapes.sort_by_key(|shape| shape.1);
 
-        let mut id_to_new_z: HashMap<u64, i32> = HashMap::new();
-        for (new_z, (id, _)) in shapes.iter().enumerate() {
-            id_to_new_z.insert(*id, new_z as i32);
-        }
+        let id_to_new_z: HashMap<u64, i32> = shapes
+            .iter()
+            .enumerate()
+            .map(|(new_z, (id, _))| (*id, new_z as i32))
+            .collect();
 
-        for rect in &mut self.rectangles {
-            if let Some(&new_z) = id_to_new_z.get(&rect.id) {
-                rect.z_index = new_z;
-            }
-        }
-        for ellipse in &mut self.ellipses {
-            if let Some(&new_z) = id_to_new_z.get(&ellipse.id) {
-                ellipse.z_index = new_z;
-            }
-        }
-        for diamond in &mut self.diamonds {
-            if let Some(&new_z) = id_to_new_z.get(&diamond.id) {
-                diamond.z_index = new_z;
-            }
-        }
-        for line in &mut self.lines {
-            if let Some(&new_z) = id_to_new_z.get(&line.id) {
-                line.z_index = new_z;
-            }
-        }
-        for arrow in &mut self.arrows {
-            if let Some(&new_z) = id_to_new_z.get(&arrow.id) {
-                arrow.z_index = new_z;
-            }
-        }
-        for path in &mut self.paths {
-            if let Some(&new_z) = id_to_new_z.get(&path.id) {
-                path.z_index = new_z;
-            }
-        }
-        for image in &mut self.images {
-            if let Some(&new_z) = id_to_new_z.get(&image.id) {
-                image.z_index = new_z;
-            }
-        }
-        for text in &mut self.texts {
-            if let Some(&new_z) = id_to_new_z.get(&text.id) {
-                text.z_index = new_z;
+        for element in self.elements.values_mut() {
+            if let Some(&new_z) = id_to_new_z.get(&element.id()) {
+                element.set_z_index(new_z);
             }
         }
     }
 
+    // shapes on a locked layer count as locked
     pub fn is_element_locked(&self, id: u64) -> bool {
-        if let Some(rect) = self.rectangles.iter().find(|r| r.id == id) {
-            return rect.locked;
-        }
-        if let Some(ellipse) = self.ellipses.iter().find(|e| e.id == id) {
-            return ellipse.locked;
-        }
-        if let Some(diamond) = self.diamonds.iter().find(|d| d.id == id) {
-            return diamond.locked;
-        }
-        if let Some(line) = self.lines.iter().find(|l| l.id == id) {
-            return line.locked;
-        }
-        if let Some(arrow) = self.arrows.iter().find(|a| a.id == id) {
-            return arrow.locked;
-        }
-        if let Some(path) = self.paths.iter().find(|p| p.id == id) {
-            return path.locked;
-        }
-        if let Some(image) = self.images.iter().find(|i| i.id == id) {
-            return image.locked;
-        }
-        if let Some(text) = self.texts.iter().find(|t| t.id == id) {
-            return text.locked;
-        }
-        if let Some(group) = self.groups.iter().find(|g| g.id == id) {
-            return group.locked;
-        }
-        false
+        self.elements.get(&id).is_some_and(Element::is_locked)
+            || self
+                .layer_of(id)
+                .and_then(|layer| self.elements.get(&layer))
+                .is_some_and(Element::is_locked)
     }
 
+    // cascades to every descendant of a group
     pub fn set_element_locked(&mut self, id: u64, locked: bool, save_history: bool) {
-        if let Some(rect) = self.rectangles.iter_mut().find(|r| r.id == id) {
-            if rect.locked != locked {
-                rect.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-            return;
-        }
-        if let Some(ellipse) = self.ellipses.iter_mut().find(|e| e.id == id) {
-            if ellipse.locked != locked {
-                ellipse.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-            return;
-        }
-        if let Some(diamond) = self.diamonds.iter_mut().find(|d| d.id == id) {
-            if diamond.locked != locked {
-                diamond.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-            return;
-        }
-        if let Some(line) = self.lines.iter_mut().find(|l| l.id == id) {
-            if line.locked != locked {
-                line.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+        let patch = ElementPatch {
+            locked: Some(locked),
+            ..Default::default()
+        };
+        if self.is_group(id) {
+            self.modify_group(id, save_history, |element| element.apply_patch(&patch));
             return;
         }
-        if let Some(arrow) = self.arrows.iter_mut().find(|a| a.id == id) {
-            if arrow.locked != locked {
-                arrow.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+        self.modify(id, None, save_history, |element| element.apply_patch(&patch));
+    }
+
+    pub fn set_element_opacity(&mut self, id: u64, opacity: f64) {
+        let patch = ElementPatch {
+            opacity: Some(opacity),
+            ..Default::default()
+        };
+        if self.is_group(id) {
+            self.modify_group(id, true, |element| element.apply_patch(&patch));
             return;
         }
-        if let Some(path) = self.paths.iter_mut().find(|p| p.id == id) {
-            if path.locked != locked {
-                path.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+        self.update(id, &patch);
+    }
+
+    pub fn set_element_blend_mode(&mut self, id: u64, blend_mode: BlendMode) {
+        let patch = ElementPatch {
+            blend_mode: Some(blend_mode),
+            ..Default::default()
+        };
+        if self.is_group(id) {
+            self.modify_group(id, true, |element| element.apply_patch(&patch));
             return;
         }
-        if let Some(image) = self.images.iter_mut().find(|i| i.id == id) {
-            if image.locked != locked {
-                image.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+        self.update(id, &patch);
+    }
+
+    // fills every closed shape in the selection; frames keep the fill's base color
+    pub fn set_element_fill(&mut self, id: u64, fill: Option<Fill>) {
+        let patch = ElementPatch {
+            fill: Some(fill),
+            ..Default::default()
+        };
+        if self.is_group(id) {
+            self.modify_group(id, true, |element| element.apply_patch(&patch));
             return;
         }
-        if let Some(text) = self.texts.iter_mut().find(|t| t.id == id) {
-            if text.locked != locked {
-                text.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
+        self.update(id, &patch);
+    }
+
+    // 0 switches back to clean geometry; the seed is left alone so the wobble comes back the same
+    pub fn set_element_roughness(&mut self, id: u64, roughness: f64) {
+        let patch = ElementPatch {
+            roughness: Some(roughness),
+            ..Default::default()
+        };
+        if self.is_group(id) {
+            self.modify_group(id, true, |element| element.apply_patch(&patch));
             return;
         }
-        if let Some(group) = self.groups.iter_mut().find(|g| g.id == id) {
-            if group.locked != locked {
-                group.locked = locked;
-                if save_history {
-                    self.save_snapshot();
-                }
-            }
-        }
+        self.update(id, &patch);
+    }
+
+    // collaborators send the seed along with the roughness so ids remapped on their side still
+    // draw the same strokes
+    pub fn set_element_seed(&mut self, id: u64, seed: u32) {
+        let patch = ElementPatch {
+            seed: Some(seed),
+            ..Default::default()
+        };
+        self.update(id, &patch);
+    }
+
+    pub fn sketch(&self, id: u64) -> Option<Sketch> {
+        self.get(id).and_then(sketch)
     }
 
     pub fn serialize(&self) -> String {
-        let snapshot = DocumentSnapshot {
-            rectangles: self.rectangles.clone(),
-            ellipses: self.ellipses.clone(),
-            lines: self.lines.clone(),
-            arrows: self.arrows.clone(),
-            diamonds: self.diamonds.clone(),
-            paths: self.paths.clone(),
-            images: self.images.clone(),
-            texts: self.texts.clone(),
-            groups: self.groups.clone(),
+        let data = SerializedDocument {
+            rectangles: self.get_rectangles(),
+            ellipses: self.get_ellipses(),
+            lines: self.get_lines(),
+            arrows: self.get_arrows(),
+            diamonds: self.get_diamonds(),
+            paths: self.get_paths(),
+            images: self.get_images(),
+            texts: self.get_texts(),
+            groups: self.get_groups(),
+            polygons: self.get_polygons(),
+            regular_polygons: self.get_regular_polygons(),
+            stars: self.get_stars(),
+            frames: self.get_frames(),
+            layers: self.get_layers(),
             next_id: self.next_id,
         };
-        serde_json::to_string(&snapshot).unwrap_or_default()
+        serde_json::to_string(&data).unwrap_or_default()
     }
 
     pub fn deserialize(&mut self, data: &str) -> bool {
-        match serde_json::from_str::<DocumentSnapshot>(data) {
-            Ok(snapshot) => {
-                self.restore_snapshot(&snapshot);
+        match serde_json::from_str::<SerializedDocument>(data) {
+            Ok(data) => {
+                self.next_id = data.next_id;
+                self.committed_next_id = data.next_id;
+                self.elements = data.into_elements();
+                self.elements.values_mut().for_each(Element::seed_if_missing);
                 self.normalize_z_indices();
+                self.rebuild_spatial_index();
+                self.pending.clear();
+                self.transaction = None;
                 self.history.clear();
                 self.history_index = 0;
-                self.save_snapshot();
                 true
             }
             Err(_) => false,
@@ -1803,8 +3125,471 @@ impl Document {
     }
 }
 
+#[derive(Clone, Copy)]
+enum Restack {
+    Front,
+    Forward,
+    Backward,
+    Back,
+}
+
 impl Default for Document {
     fn default() -> Self {
         Self::new()
     }
 }
+
+// scales points so their bounding box becomes the given one
+fn fit_points(points: &mut [Point], new_x: f64, new_y: f64, new_width: f64, new_height: f64) -> bool {
+    if points.is_empty() {
+        return false;
+    }
+
+    let mut min_x = points[0].x;
+    let mut min_y = points[0].y;
+    let mut max_x = points[0].x;
+    let mut max_y = points[0].y;
+
+    for point in points.iter() {
+        min_x = min_x.min(point.x);
+        min_y = min_y.min(point.y);
+        max_x = max_x.max(point.x);
+        max_y = max_y.max(point.y);
+    }
+
+    let old_width = max_x - min_x;
+    let old_height = max_y - min_y;
+
+    if old_width.abs() < f64::EPSILON || old_height.abs() < f64::EPSILON {
+        return false;
+    }
+
+    let scale_x = new_width / old_width;
+    let scale_y = new_height / old_height;
+
+    for point in points.iter_mut() {
+        let relative_x = point.x - min_x;
+        let relative_y = point.y - min_y;
+        point.x = new_x + relative_x * scale_x;
+        point.y = new_y + relative_y * scale_y;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::{Anchor, Side};
+
+    fn position(doc: &Document, id: u64) -> Point {
+        match doc.get(id) {
+            Some(Element::Rectangle(rect)) => rect.position,
+            other => panic!("expected a rectangle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_replay_recorded_changes() {
+        let mut doc = Document::new();
+        let id = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        doc.move_rectangle(id, Point::new(5.0, 5.0), true);
+        doc.move_rectangle(id, Point::new(9.0, 9.0), true);
+
+        assert!(doc.undo());
+        assert_eq!(position(&doc, id), Point::new(5.0, 5.0));
+        assert!(doc.undo());
+        assert_eq!(position(&doc, id), Point::new(0.0, 0.0));
+        assert!(doc.undo());
+        assert!(doc.get(id).is_none());
+        assert!(!doc.undo());
+
+        assert!(doc.redo());
+        assert_eq!(position(&doc, id), Point::new(0.0, 0.0));
+        assert!(doc.redo());
+        assert!(doc.redo());
+        assert_eq!(position(&doc, id), Point::new(9.0, 9.0));
+        assert!(!doc.redo());
+    }
+
+    #[test]
+    fn new_change_after_undo_drops_the_redo_branch() {
+        let mut doc = Document::new();
+        let id = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        doc.move_rectangle(id, Point::new(5.0, 5.0), true);
+        doc.undo();
+        doc.move_rectangle(id, Point::new(7.0, 7.0), true);
+
+        assert!(!doc.can_redo());
+        doc.undo();
+        assert_eq!(position(&doc, id), Point::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn history_is_trimmed_to_max_history() {
+        let mut doc = Document::new();
+        doc.max_history = 3;
+        let id = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        for step in 1..=5 {
+            doc.move_rectangle(id, Point::new(step as f64, 0.0), true);
+        }
+
+        let mut undone = 0;
+        while doc.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+        assert_eq!(position(&doc, id), Point::new(2.0, 0.0));
+
+        while doc.redo() {}
+        assert_eq!(position(&doc, id), Point::new(5.0, 0.0));
+    }
+
+    #[test]
+    fn transaction_becomes_one_labeled_entry() {
+        let mut doc = Document::new();
+        let id = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        doc.begin_transaction("move twice");
+        doc.move_rectangle(id, Point::new(5.0, 0.0), true);
+        doc.begin_transaction("inner");
+        doc.move_rectangle(id, Point::new(9.0, 0.0), true);
+        assert!(doc.commit_transaction());
+        assert!(doc.in_transaction());
+        assert!(doc.commit_transaction());
+
+        assert_eq!(doc.undo_label(), Some("move twice"));
+        doc.undo();
+        assert_eq!(position(&doc, id), Point::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn nested_rollback_reverts_the_whole_transaction() {
+        let mut doc = Document::new();
+        let id = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        doc.begin_transaction("outer");
+        doc.move_rectangle(id, Point::new(5.0, 0.0), true);
+        doc.begin_transaction("inner");
+        doc.add_rectangle(Point::new(50.0, 50.0), 10.0, 10.0);
+        assert!(doc.rollback_transaction());
+        assert!(doc.in_transaction());
+        doc.move_rectangle(id, Point::new(9.0, 0.0), true);
+
+        assert!(!doc.commit_transaction());
+        assert!(!doc.in_transaction());
+        assert_eq!(position(&doc, id), Point::new(0.0, 0.0));
+        assert_eq!(doc.get_rectangles().len(), 1);
+        assert_eq!(doc.undo_label(), None);
+        assert!(doc.undo());
+        assert!(doc.get(id).is_none());
+    }
+
+    #[test]
+    fn transact_rolls_back_on_error() {
+        let mut doc = Document::new();
+        let id = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        let result: Result<(), &str> = doc.transact("fail", |doc| {
+            doc.move_rectangle(id, Point::new(5.0, 0.0), true);
+            Err("nope")
+        });
+
+        assert!(result.is_err());
+        assert!(!doc.in_transaction());
+        assert_eq!(position(&doc, id), Point::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn spatial_queries_follow_moves_deletes_and_undo() {
+        let mut doc = Document::new();
+        let id = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        let other = doc.add_rectangle(Point::new(100.0, 100.0), 10.0, 10.0);
+        assert_eq!(doc.elements_at_point(Point::new(5.0, 5.0), 0.0), vec![id]);
+
+        doc.move_rectangle(id, Point::new(200.0, 0.0), true);
+        assert!(doc.elements_at_point(Point::new(5.0, 5.0), 0.0).is_empty());
+        assert_eq!(doc.elements_at_point(Point::new(205.0, 5.0), 0.0), vec![id]);
+        let area = Rect::new(-10.0, -10.0, 500.0, 500.0);
+        assert_eq!(doc.elements_in_rect(area, SelectionMode::Contain).len(), 2);
+
+        doc.delete_rectangle(other);
+        assert!(doc.elements_at_point(Point::new(105.0, 105.0), 0.0).is_empty());
+        assert_eq!(doc.elements_in_rect(area, SelectionMode::Intersect), vec![id]);
+
+        doc.undo();
+        assert_eq!(doc.elements_at_point(Point::new(105.0, 105.0), 0.0), vec![other]);
+        doc.undo();
+        assert_eq!(doc.elements_at_point(Point::new(5.0, 5.0), 0.0), vec![id]);
+    }
+
+    #[test]
+    fn overlapping_hits_come_topmost_first() {
+        let mut doc = Document::new();
+        let bottom = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        let top = doc.add_rectangle(Point::new(5.0, 5.0), 10.0, 10.0);
+        assert_eq!(doc.elements_at_point(Point::new(7.0, 7.0), 0.0), vec![top, bottom]);
+
+        doc.bring_shape_to_front(bottom);
+        assert_eq!(doc.elements_at_point(Point::new(7.0, 7.0), 0.0), vec![bottom, top]);
+    }
+
+    fn endpoints(doc: &Document, id: u64) -> (Point, Point) {
+        doc.get(id).and_then(Element::endpoints).expect("a connector")
+    }
+
+    fn assert_near(actual: Point, expected: Point) {
+        assert!(actual.distance(expected) < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn bound_endpoint_follows_a_moved_target_and_undo_restores_it() {
+        let mut doc = Document::new();
+        let target = doc.add_rectangle(Point::new(100.0, 0.0), 20.0, 20.0);
+        let arrow = doc.add_arrow(Point::new(0.0, 10.0), Point::new(100.0, 10.0));
+        let binding = Binding::new(target, Anchor::Side { side: Side::Left });
+        assert!(doc.bind_connector(arrow, ConnectorEnd::End, Some(binding)));
+
+        doc.move_rectangle(target, Point::new(200.0, 50.0), true);
+        assert_near(endpoints(&doc, arrow).1, Point::new(200.0, 60.0));
+        assert_near(endpoints(&doc, arrow).0, Point::new(0.0, 10.0));
+
+        doc.undo();
+        assert_near(endpoints(&doc, arrow).1, Point::new(100.0, 10.0));
+        doc.redo();
+        assert_near(endpoints(&doc, arrow).1, Point::new(200.0, 60.0));
+    }
+
+    #[test]
+    fn bound_endpoint_follows_a_rotated_target() {
+        let mut doc = Document::new();
+        let target = doc.add_rectangle(Point::new(100.0, 0.0), 20.0, 20.0);
+        let arrow = doc.add_arrow(Point::new(0.0, 10.0), Point::new(100.0, 10.0));
+        let binding = Binding::new(target, Anchor::Side { side: Side::Left });
+        doc.bind_connector(arrow, ConnectorEnd::End, Some(binding));
+
+        // a quarter turn about the center (110, 10) brings the left side to the top
+        doc.set_rectangle_rotation(target, std::f64::consts::FRAC_PI_2, true);
+        assert_near(endpoints(&doc, arrow).1, Point::new(110.0, 0.0));
+
+        doc.undo();
+        assert_near(endpoints(&doc, arrow).1, Point::new(100.0, 10.0));
+    }
+
+    #[test]
+    fn deleting_the_target_unbinds_the_connector() {
+        let mut doc = Document::new();
+        let target = doc.add_rectangle(Point::new(100.0, 0.0), 20.0, 20.0);
+        let arrow = doc.add_arrow(Point::new(0.0, 10.0), Point::new(100.0, 10.0));
+        doc.bind_connector(arrow, ConnectorEnd::End, Some(Binding::new(target, Anchor::NearestEdge)));
+
+        doc.delete(target);
+        assert!(doc.get_bound_connectors(target).is_empty());
+        doc.undo();
+        assert_eq!(doc.get_bound_connectors(target), vec![arrow]);
+    }
+
+    fn text_element(doc: &Document, id: u64) -> Text {
+        match doc.get(id) {
+            Some(Element::Text(text)) => text.clone(),
+            other => panic!("expected a text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn label_follows_its_container_through_move_and_rotate() {
+        let mut doc = Document::new();
+        let rectangle = doc.add_rectangle(Point::new(0.0, 0.0), 100.0, 60.0);
+        let id = doc.add_label(rectangle, "hello".to_string()).unwrap();
+        assert_eq!(doc.get_label(rectangle), Some(id));
+        let text = text_element(&doc, id);
+        assert_eq!((text.position, text.width, text.height), (Point::new(8.0, 8.0), 84.0, 44.0));
+
+        doc.move_rectangle(rectangle, Point::new(50.0, 40.0), true);
+        assert_eq!(text_element(&doc, id).position, Point::new(58.0, 48.0));
+
+        doc.set_rectangle_rotation(rectangle, 0.5, true);
+        let text = text_element(&doc, id);
+        assert_eq!((text.position, text.rotation_angle), (Point::new(58.0, 48.0), 0.5));
+
+        doc.undo();
+        doc.undo();
+        let text = text_element(&doc, id);
+        assert_eq!((text.position, text.rotation_angle), (Point::new(8.0, 8.0), 0.0));
+    }
+
+    #[test]
+    fn deleting_the_container_deletes_its_label_and_undo_restores_both() {
+        let mut doc = Document::new();
+        let rectangle = doc.add_rectangle(Point::new(0.0, 0.0), 100.0, 60.0);
+        let id = doc.add_label(rectangle, "hello".to_string()).unwrap();
+
+        doc.delete(rectangle);
+        assert!(doc.get(id).is_none());
+        assert!(doc.elements_at_point(Point::new(50.0, 30.0), 0.0).is_empty());
+
+        doc.undo();
+        assert_eq!(doc.get_label(rectangle), Some(id));
+        assert_eq!(text_element(&doc, id).container_id, Some(rectangle));
+    }
+
+    #[test]
+    fn connector_label_sits_at_the_midpoint() {
+        let mut doc = Document::new();
+        let arrow = doc.add_arrow(Point::new(0.0, 0.0), Point::new(100.0, 0.0));
+        let id = doc.add_label(arrow, "go".to_string()).unwrap();
+        let center = |text: Text| Point::new(text.position.x + text.width / 2.0, text.position.y + text.height / 2.0);
+        assert_near(center(text_element(&doc, id)), Point::new(50.0, 0.0));
+
+        doc.move_arrow(arrow, Point::new(0.0, 0.0), Point::new(0.0, 80.0), true);
+        assert_near(center(text_element(&doc, id)), Point::new(0.0, 40.0));
+    }
+
+    #[test]
+    fn stored_documents_with_unreadable_style_values_still_load() {
+        let data = r##"{
+            "rectangles": [{"id": 1, "position": {"x": 0, "y": 0}, "width": 10, "height": 10,
+                "stroke_color": "hsl(0, 100%, 50%)", "fill_color": "", "dash_pattern": "5 x"}],
+            "ellipses": [{"id": 2, "position": {"x": 0, "y": 0}, "radius_x": 5, "radius_y": 5,
+                "stroke_color": "#ff0000", "fill_color": "none"}],
+            "lines": [], "arrows": [], "diamonds": [], "paths": [], "images": [],
+            "texts": [{"id": 3, "position": {"x": 0, "y": 0}, "width": 50, "height": 20, "content": "hi",
+                "font_weight": "heavy", "text_align": "justify", "color": "#12"}],
+            "groups": [], "next_id": 4
+        }"##;
+        let mut doc = Document::new();
+        assert!(doc.deserialize(data));
+        assert_eq!(doc.elements.len(), 3);
+        match doc.get(1) {
+            Some(Element::Rectangle(rectangle)) => {
+                assert_eq!(rectangle.stroke_color, Color::BLACK);
+                assert_eq!(rectangle.fill, None);
+                assert_eq!(rectangle.dash_pattern, DashPattern::Solid);
+            }
+            other => panic!("expected a rectangle, got {:?}", other),
+        }
+        match doc.get(2) {
+            Some(Element::Ellipse(ellipse)) => {
+                assert_eq!(ellipse.stroke_color, Color::rgb(255, 0, 0));
+                assert_eq!(ellipse.fill, None);
+            }
+            other => panic!("expected an ellipse, got {:?}", other),
+        }
+        let text = text_element(&doc, 3);
+        assert_eq!((text.font_weight, text.text_align, text.color), (FontWeight::NORMAL, TextAlign::Left, Color::BLACK));
+    }
+
+    #[test]
+    fn adding_a_frame_puts_it_behind_without_touching_other_shapes() {
+        let mut doc = Document::new();
+        let shapes: Vec<u64> = (0..5)
+            .map(|i| doc.add_rectangle(Point::new(i as f64 * 20.0, 0.0), 10.0, 10.0))
+            .collect();
+        let before: Vec<Option<i32>> = shapes.iter().map(|id| doc.get_shape_z_index(*id)).collect();
+
+        let frame = doc.add_frame(Point::new(-10.0, -10.0), 200.0, 50.0, "Frame".to_string());
+        let after: Vec<Option<i32>> = shapes.iter().map(|id| doc.get_shape_z_index(*id)).collect();
+        assert_eq!(before, after);
+        assert!(doc.get_shape_z_index(frame) < before.into_iter().min().flatten());
+        let entry = &doc.history[doc.history_index - 1];
+        assert_eq!(entry.changes.iter().map(|change| change.id).collect::<Vec<_>>(), vec![frame]);
+    }
+
+    #[test]
+    fn reverse_lookups_follow_edits_and_undo() {
+        let mut doc = Document::new();
+        let first = doc.add_rectangle(Point::new(0.0, 0.0), 10.0, 10.0);
+        let second = doc.add_rectangle(Point::new(100.0, 0.0), 10.0, 10.0);
+        let arrow = doc.add_arrow(Point::new(10.0, 5.0), Point::new(100.0, 5.0));
+        doc.bind_connector(arrow, ConnectorEnd::Start, Some(Binding::new(first, Anchor::NearestEdge)));
+        let label = doc.add_label(first, "a".to_string()).unwrap();
+        let group = doc.group_elements(vec![first, second]);
+        let frame = doc.add_frame(Point::new(90.0, -10.0), 50.0, 50.0, "Frame".to_string());
+
+        assert_eq!(doc.get_bound_connectors(first), vec![arrow]);
+        assert_eq!(doc.get_label(first), Some(label));
+        assert_eq!((doc.parent_group(first), doc.parent_group(second)), (Some(group), Some(group)));
+        assert_eq!((doc.frame_of(second), doc.frame_of(first)), (Some(frame), None));
+
+        doc.move_rectangle(second, Point::new(300.0, 0.0), true);
+        assert_eq!(doc.frame_of(second), None);
+        doc.undo();
+        assert_eq!(doc.frame_of(second), Some(frame));
+
+        doc.ungroup_elements(group);
+        assert_eq!(doc.parent_group(first), None);
+        doc.undo();
+        assert_eq!(doc.parent_group(first), Some(group));
+
+        doc.delete(first);
+        assert!(doc.get_bound_connectors(first).is_empty());
+        assert_eq!(doc.get_label(first), None);
+        doc.undo();
+        assert_eq!(doc.get_bound_connectors(first), vec![arrow]);
+        assert_eq!(doc.get_label(first), Some(label));
+    }
+
+    // every character as wide as the font size
+    struct WideMetrics;
+
+    impl FontMetrics for WideMetrics {
+        fn measure(&self, text: &str, font: &crate::text_layout::Font) -> f64 {
+            text.chars().count() as f64 * font.size
+        }
+    }
+
+    #[test]
+    fn new_font_metrics_stay_out_of_history_and_survive_undo() {
+        let mut doc = Document::new();
+        let text = doc.add_text(Point::new(0.0, 0.0), 10.0, 10.0, "hello".to_string());
+        let other = doc.add_rectangle(Point::new(100.0, 0.0), 10.0, 10.0);
+        doc.move_text(text, Point::new(0.0, 50.0), true);
+        let entries = doc.history.len();
+
+        doc.set_font_metrics(Box::new(WideMetrics));
+        let wide = text_element(&doc, text).width;
+        assert_eq!(wide, 5.0 * 36.0 + crate::text_layout::TEXT_PADDING);
+        assert!(doc.pending.is_empty());
+        assert_eq!(doc.history.len(), entries);
+
+        doc.move_rectangle(other, Point::new(200.0, 0.0), true);
+        assert_eq!(doc.history[doc.history_index - 1].changes.len(), 1);
+
+        // both entries hold the text as it was laid out with the old metrics
+        doc.undo();
+        doc.undo();
+        let restored = text_element(&doc, text);
+        assert_eq!((restored.position, restored.width), (Point::new(0.0, 0.0), wide));
+        doc.redo();
+        let restored = text_element(&doc, text);
+        assert_eq!((restored.position, restored.width), (Point::new(0.0, 50.0), wide));
+        assert!(doc.pending.is_empty());
+    }
+
+    #[test]
+    fn transforming_a_rotated_line_bakes_its_rotation_into_the_points() {
+        let mut doc = Document::new();
+        let line = doc.add_line(Point::new(0.0, 0.0), Point::new(10.0, 0.0));
+        doc.set_line_rotation(line, std::f64::consts::FRAC_PI_2, true);
+
+        assert!(doc.transform(&[line], &Affine::translate(5.0, 0.0), Point::new(0.0, 0.0)));
+        let (start, end) = endpoints(&doc, line);
+        assert_near(start, Point::new(10.0, -5.0));
+        assert_near(end, Point::new(10.0, 5.0));
+        assert_eq!(doc.get(line).map(Element::rotation), Some(0.0));
+
+        doc.undo();
+        assert_eq!(endpoints(&doc, line), (Point::new(0.0, 0.0), Point::new(10.0, 0.0)));
+        assert_eq!(doc.get(line).map(Element::rotation), Some(std::f64::consts::FRAC_PI_2));
+    }
+
+    #[test]
+    fn hit_testing_follows_a_rotated_line() {
+        let mut doc = Document::new();
+        let line = doc.add_line(Point::new(0.0, 0.0), Point::new(10.0, 0.0));
+        doc.set_line_rotation(line, std::f64::consts::FRAC_PI_2, true);
+
+        assert!(doc.elements_at_point(Point::new(5.0, 4.0), 1.0).contains(&line));
+        assert!(!doc.elements_at_point(Point::new(1.0, 0.0), 1.0).contains(&line));
+        let across = Rect::new(4.0, 3.0, 2.0, 2.0);
+        assert!(doc.elements_in_rect(across, SelectionMode::Intersect).contains(&line));
+        let along = Rect::new(0.0, -1.0, 3.0, 2.0);
+        assert!(!doc.elements_in_rect(along, SelectionMode::Intersect).contains(&line));
+    }
+}
@@ -1,5 +1,27 @@
-use crate::geometry::Point;
-use serde::{Deserialize, Serialize};
+use crate::connector::{Binding, Marker};
+use crate::fill::Fill;
+use crate::geometry::{bezier_bounds, Affine, Point, Rect, Vector, MAX_BEZIER_CONTROL_POINTS};
+use crate::rich_text::{ParagraphStyle, TextRun};
+use crate::routing::Routing;
+use crate::stroke::{pressure_width, stroke_outline, Smoothing};
+use crate::style::{lenient, lenient_option, Color, DashPattern, FontWeight, TextAlign};
+use crate::text_layout::{TextSizing, VerticalAlign};
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub const MIN_POLYGON_SIDES: u32 = 3;
+pub const MAX_POLYGON_SIDES: u32 = 128;
+pub const MAX_ROUGHNESS: f64 = 5.0;
+// shear left over from rounding when a transform had none
+const SKEW_EPSILON: f64 = 1e-9;
+
+// how an element's colors combine with what is drawn beneath it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+}
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Rectangle {
@@ -7,18 +29,33 @@ pub struct Rectangle {
     pub position: Point,
     pub width: f64,
     pub height: f64,
-    #[serde(default = "default_stroke_color")]
-    pub stroke_color: String,
-    #[serde(default = "default_fill_color")]
-    pub fill_color: Option<String>,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
+    #[serde(default = "default_fill", deserialize_with = "lenient_option", rename = "fill_color")]
+    pub fill: Option<Fill>,
     #[serde(default = "default_line_width")]
     pub line_width: f64,
-    #[serde(default = "default_dash_pattern")]
-    pub dash_pattern: String,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
     #[serde(default = "default_border_radius")]
     pub border_radius: f64,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    // slant of the vertical edges in radians, applied after flipping and before rotating
+    #[serde(default = "default_skew")]
+    pub skew: f64,
+    #[serde(default = "default_flip")]
+    pub flip_x: bool,
+    #[serde(default = "default_flip")]
+    pub flip_y: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default)]
+    pub roughness: f64,
+    #[serde(default)]
+    pub seed: u32,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -33,11 +70,18 @@ impl Rectangle {
             width,
             height,
             stroke_color: default_stroke_color(),
-            fill_color: default_fill_color(),
+            fill: default_fill(),
             line_width: default_line_width(),
             dash_pattern: default_dash_pattern(),
             border_radius: default_border_radius(),
             rotation_angle: default_rotation(),
+            skew: default_skew(),
+            flip_x: default_flip(),
+            flip_y: default_flip(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
+            roughness: 0.0,
+            seed: default_seed(id),
             z_index: default_z_index(),
             locked: default_locked(),
         }
@@ -50,16 +94,30 @@ pub struct Ellipse {
     pub position: Point,
     pub radius_x: f64,
     pub radius_y: f64,
-    #[serde(default = "default_stroke_color")]
-    pub stroke_color: String,
-    #[serde(default = "default_fill_color")]
-    pub fill_color: Option<String>,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
+    #[serde(default = "default_fill", deserialize_with = "lenient_option", rename = "fill_color")]
+    pub fill: Option<Fill>,
     #[serde(default = "default_line_width")]
     pub line_width: f64,
-    #[serde(default = "default_dash_pattern")]
-    pub dash_pattern: String,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    #[serde(default = "default_skew")]
+    pub skew: f64,
+    #[serde(default = "default_flip")]
+    pub flip_x: bool,
+    #[serde(default = "default_flip")]
+    pub flip_y: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default)]
+    pub roughness: f64,
+    #[serde(default)]
+    pub seed: u32,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -74,10 +132,17 @@ impl Ellipse {
             radius_x,
             radius_y,
             stroke_color: default_stroke_color(),
-            fill_color: default_fill_color(),
+            fill: default_fill(),
             line_width: default_line_width(),
             dash_pattern: default_dash_pattern(),
             rotation_angle: default_rotation(),
+            skew: default_skew(),
+            flip_x: default_flip(),
+            flip_y: default_flip(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
+            roughness: 0.0,
+            seed: default_seed(id),
             z_index: default_z_index(),
             locked: default_locked(),
         }
@@ -89,14 +154,38 @@ pub struct Line {
     pub id: u64,
     pub start: Point,
     pub end: Point,
-    #[serde(default = "default_stroke_color")]
-    pub stroke_color: String,
+    #[serde(default)]
+    pub start_binding: Option<Binding>,
+    #[serde(default)]
+    pub end_binding: Option<Binding>,
+    #[serde(default)]
+    pub routing: Routing,
+    #[serde(default)]
+    pub waypoints: Vec<Point>,
+    #[serde(default)]
+    pub control_points: Vec<Point>,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
     #[serde(default = "default_line_width")]
     pub line_width: f64,
-    #[serde(default = "default_dash_pattern")]
-    pub dash_pattern: String,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
+    #[serde(default)]
+    pub start_marker: Marker,
+    #[serde(default = "default_line_end_marker")]
+    pub end_marker: Marker,
+    #[serde(default = "default_marker_size")]
+    pub marker_size: f64,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default)]
+    pub roughness: f64,
+    #[serde(default)]
+    pub seed: u32,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -109,10 +198,22 @@ impl Line {
             id,
             start,
             end,
+            start_binding: None,
+            end_binding: None,
+            routing: Routing::default(),
+            waypoints: Vec::new(),
+            control_points: Vec::new(),
             stroke_color: default_stroke_color(),
             line_width: default_line_width(),
             dash_pattern: default_dash_pattern(),
+            start_marker: Marker::None,
+            end_marker: default_line_end_marker(),
+            marker_size: default_marker_size(),
             rotation_angle: default_rotation(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
+            roughness: 0.0,
+            seed: default_seed(id),
             z_index: default_z_index(),
             locked: default_locked(),
         }
@@ -124,14 +225,38 @@ pub struct Arrow {
     pub id: u64,
     pub start: Point,
     pub end: Point,
-    #[serde(default = "default_stroke_color")]
-    pub stroke_color: String,
+    #[serde(default)]
+    pub start_binding: Option<Binding>,
+    #[serde(default)]
+    pub end_binding: Option<Binding>,
+    #[serde(default)]
+    pub routing: Routing,
+    #[serde(default)]
+    pub waypoints: Vec<Point>,
+    #[serde(default)]
+    pub control_points: Vec<Point>,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
     #[serde(default = "default_line_width")]
     pub line_width: f64,
-    #[serde(default = "default_dash_pattern")]
-    pub dash_pattern: String,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
+    #[serde(default)]
+    pub start_marker: Marker,
+    #[serde(default = "default_arrow_end_marker")]
+    pub end_marker: Marker,
+    #[serde(default = "default_marker_size")]
+    pub marker_size: f64,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default)]
+    pub roughness: f64,
+    #[serde(default)]
+    pub seed: u32,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -144,10 +269,22 @@ impl Arrow {
             id,
             start,
             end,
+            start_binding: None,
+            end_binding: None,
+            routing: Routing::default(),
+            waypoints: Vec::new(),
+            control_points: Vec::new(),
             stroke_color: default_stroke_color(),
             line_width: default_line_width(),
             dash_pattern: default_dash_pattern(),
+            start_marker: Marker::None,
+            end_marker: default_arrow_end_marker(),
+            marker_size: default_marker_size(),
             rotation_angle: default_rotation(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
+            roughness: 0.0,
+            seed: default_seed(id),
             z_index: default_z_index(),
             locked: default_locked(),
         }
@@ -160,18 +297,32 @@ pub struct Diamond {
     pub position: Point,
     pub width: f64,
     pub height: f64,
-    #[serde(default = "default_stroke_color")]
-    pub stroke_color: String,
-    #[serde(default = "default_fill_color")]
-    pub fill_color: Option<String>,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
+    #[serde(default = "default_fill", deserialize_with = "lenient_option", rename = "fill_color")]
+    pub fill: Option<Fill>,
     #[serde(default = "default_line_width")]
     pub line_width: f64,
-    #[serde(default = "default_dash_pattern")]
-    pub dash_pattern: String,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
     #[serde(default = "default_border_radius")]
     pub border_radius: f64,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    #[serde(default = "default_skew")]
+    pub skew: f64,
+    #[serde(default = "default_flip")]
+    pub flip_x: bool,
+    #[serde(default = "default_flip")]
+    pub flip_y: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default)]
+    pub roughness: f64,
+    #[serde(default)]
+    pub seed: u32,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -186,22 +337,211 @@ impl Diamond {
             width,
             height,
             stroke_color: default_stroke_color(),
-            fill_color: default_fill_color(),
+            fill: default_fill(),
+            line_width: default_line_width(),
+            dash_pattern: default_dash_pattern(),
+            border_radius: default_border_radius(),
+            rotation_angle: default_rotation(),
+            skew: default_skew(),
+            flip_x: default_flip(),
+            flip_y: default_flip(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
+            roughness: 0.0,
+            seed: default_seed(id),
+            z_index: default_z_index(),
+            locked: default_locked(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Polygon {
+    pub id: u64,
+    pub points: Vec<Point>,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
+    #[serde(default = "default_fill", deserialize_with = "lenient_option", rename = "fill_color")]
+    pub fill: Option<Fill>,
+    #[serde(default = "default_line_width")]
+    pub line_width: f64,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
+    #[serde(default = "default_border_radius")]
+    pub border_radius: f64,
+    #[serde(default = "default_rotation")]
+    pub rotation_angle: f64,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default = "default_z_index")]
+    pub z_index: i32,
+    #[serde(default = "default_locked")]
+    pub locked: bool,
+}
+
+impl Polygon {
+    pub fn new(id: u64, points: Vec<Point>) -> Self {
+        Self {
+            id,
+            points,
+            stroke_color: default_stroke_color(),
+            fill: default_fill(),
+            line_width: default_line_width(),
+            dash_pattern: default_dash_pattern(),
+            border_radius: default_border_radius(),
+            rotation_angle: default_rotation(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
+            z_index: default_z_index(),
+            locked: default_locked(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RegularPolygon {
+    pub id: u64,
+    pub position: Point,
+    pub width: f64,
+    pub height: f64,
+    pub sides: u32,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
+    #[serde(default = "default_fill", deserialize_with = "lenient_option", rename = "fill_color")]
+    pub fill: Option<Fill>,
+    #[serde(default = "default_line_width")]
+    pub line_width: f64,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
+    #[serde(default = "default_border_radius")]
+    pub border_radius: f64,
+    #[serde(default = "default_rotation")]
+    pub rotation_angle: f64,
+    #[serde(default = "default_skew")]
+    pub skew: f64,
+    #[serde(default = "default_flip")]
+    pub flip_x: bool,
+    #[serde(default = "default_flip")]
+    pub flip_y: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default = "default_z_index")]
+    pub z_index: i32,
+    #[serde(default = "default_locked")]
+    pub locked: bool,
+}
+
+impl RegularPolygon {
+    pub fn new(id: u64, position: Point, width: f64, height: f64, sides: u32) -> Self {
+        Self {
+            id,
+            position,
+            width,
+            height,
+            sides: clamp_sides(sides),
+            stroke_color: default_stroke_color(),
+            fill: default_fill(),
+            line_width: default_line_width(),
+            dash_pattern: default_dash_pattern(),
+            border_radius: default_border_radius(),
+            rotation_angle: default_rotation(),
+            skew: default_skew(),
+            flip_x: default_flip(),
+            flip_y: default_flip(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
+            z_index: default_z_index(),
+            locked: default_locked(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Star {
+    pub id: u64,
+    pub position: Point,
+    pub width: f64,
+    pub height: f64,
+    // number of tips
+    pub sides: u32,
+    // inner vertices as a fraction of the outer radius
+    #[serde(default = "default_inner_radius")]
+    pub inner_radius: f64,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
+    #[serde(default = "default_fill", deserialize_with = "lenient_option", rename = "fill_color")]
+    pub fill: Option<Fill>,
+    #[serde(default = "default_line_width")]
+    pub line_width: f64,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
+    #[serde(default = "default_border_radius")]
+    pub border_radius: f64,
+    #[serde(default = "default_rotation")]
+    pub rotation_angle: f64,
+    #[serde(default = "default_skew")]
+    pub skew: f64,
+    #[serde(default = "default_flip")]
+    pub flip_x: bool,
+    #[serde(default = "default_flip")]
+    pub flip_y: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default = "default_z_index")]
+    pub z_index: i32,
+    #[serde(default = "default_locked")]
+    pub locked: bool,
+}
+
+impl Star {
+    pub fn new(id: u64, position: Point, width: f64, height: f64, sides: u32) -> Self {
+        Self {
+            id,
+            position,
+            width,
+            height,
+            sides: clamp_sides(sides),
+            inner_radius: default_inner_radius(),
+            stroke_color: default_stroke_color(),
+            fill: default_fill(),
             line_width: default_line_width(),
             dash_pattern: default_dash_pattern(),
             border_radius: default_border_radius(),
             rotation_angle: default_rotation(),
+            skew: default_skew(),
+            flip_x: default_flip(),
+            flip_y: default_flip(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
             z_index: default_z_index(),
             locked: default_locked(),
         }
     }
 }
 
-fn default_stroke_color() -> String {
-    "#000000".to_string()
+fn clamp_sides(sides: u32) -> u32 {
+    sides.clamp(MIN_POLYGON_SIDES, MAX_POLYGON_SIDES)
 }
 
-fn default_fill_color() -> Option<String> {
+fn default_inner_radius() -> f64 {
+    0.5
+}
+
+fn default_stroke_color() -> Color {
+    Color::BLACK
+}
+
+fn default_fill() -> Option<Fill> {
+    None
+}
+
+fn default_fill_color() -> Option<Color> {
     None
 }
 
@@ -217,30 +557,130 @@ fn default_rotation() -> f64 {
     0.0
 }
 
+fn default_skew() -> f64 {
+    0.0
+}
+
+fn default_flip() -> bool {
+    false
+}
+
+fn default_opacity() -> f64 {
+    1.0
+}
+
+// spreads consecutive ids across the seed range so neighbouring shapes wobble differently
+fn default_seed(id: u64) -> u32 {
+    let mut value = id.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (value ^ (value >> 31)) as u32
+}
+
 fn default_z_index() -> i32 {
     0
 }
 
-fn default_dash_pattern() -> String {
-    "solid".to_string()
+fn default_dash_pattern() -> DashPattern {
+    DashPattern::Solid
+}
+
+fn lenient_stroke_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    lenient(deserializer, default_stroke_color)
+}
+
+fn lenient_dash_pattern<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DashPattern, D::Error> {
+    lenient(deserializer, default_dash_pattern)
+}
+
+// the fields that place a box shaped element
+struct BoxFields<'a> {
+    position: &'a mut Point,
+    width: &'a mut f64,
+    height: &'a mut f64,
+    rotation: &'a mut f64,
+    skew: &'a mut f64,
+    flip_x: &'a mut bool,
+    flip_y: &'a mut bool,
+}
+
+// how a box is laid out once its scaled axes land on `x_axis` and `y_axis`
+struct Placement {
+    width: f64,
+    height: f64,
+    rotation: f64,
+    skew: f64,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+// a map reads either as it is or turned half a turn with both flips toggled; the smaller turn from `rotation` wins
+fn decompose(x_axis: Vector, y_axis: Vector, rotation: f64) -> Placement {
+    let width = x_axis.length();
+    let angle = x_axis.y.atan2(x_axis.x);
+    let unit = Vector::new(angle.cos(), angle.sin());
+    let height = unit.cross(y_axis);
+    let skew = if height == 0.0 { 0.0 } else { (unit.dot(y_axis) / height).atan() };
+    let skew = if skew.abs() < SKEW_EPSILON { 0.0 } else { skew };
+    let turned = angle + std::f64::consts::PI;
+    let turn = |target: f64| (target - rotation).sin().atan2((target - rotation).cos()).abs();
+    if turn(turned) < turn(angle) {
+        Placement {
+            width,
+            height: height.abs(),
+            rotation: turned.sin().atan2(turned.cos()),
+            skew,
+            flip_x: true,
+            flip_y: height > 0.0,
+        }
+    } else {
+        Placement {
+            width,
+            height: height.abs(),
+            rotation: angle,
+            skew,
+            flip_x: false,
+            flip_y: height < 0.0,
+        }
+    }
 }
 
 fn default_locked() -> bool {
     false
 }
 
+fn default_line_end_marker() -> Marker {
+    Marker::None
+}
+
+fn default_arrow_end_marker() -> Marker {
+    Marker::Triangle
+}
+
+fn default_marker_size() -> f64 {
+    10.0
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Path {
     pub id: u64,
     pub points: Vec<Point>,
-    #[serde(default = "default_stroke_color")]
-    pub stroke_color: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pressures: Vec<f64>,
+    #[serde(default)]
+    pub smoothing: Smoothing,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub stroke_color: Color,
     #[serde(default = "default_line_width")]
     pub line_width: f64,
-    #[serde(default = "default_dash_pattern")]
-    pub dash_pattern: String,
+    #[serde(default = "default_dash_pattern", deserialize_with = "lenient_dash_pattern")]
+    pub dash_pattern: DashPattern,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -252,14 +692,35 @@ impl Path {
         Self {
             id,
             points,
+            pressures: Vec::new(),
+            smoothing: Smoothing::None,
             stroke_color: default_stroke_color(),
             line_width: default_line_width(),
             dash_pattern: default_dash_pattern(),
             rotation_angle: default_rotation(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
             z_index: default_z_index(),
             locked: default_locked(),
         }
     }
+
+    pub fn has_pressure(&self) -> bool {
+        !self.pressures.is_empty() && self.pressures.len() == self.points.len()
+    }
+
+    pub fn max_width(&self) -> f64 {
+        if !self.has_pressure() {
+            return self.line_width;
+        }
+        let max_pressure = self.pressures.iter().copied().fold(0.0, f64::max);
+        pressure_width(self.line_width, max_pressure)
+    }
+
+    pub fn outline(&self) -> Vec<Point> {
+        let pressures = if self.has_pressure() { self.pressures.as_slice() } else { &[] };
+        stroke_outline(&self.points, pressures, self.line_width)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -271,6 +732,16 @@ pub struct Image {
     pub image_data: String,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    #[serde(default = "default_skew")]
+    pub skew: f64,
+    #[serde(default = "default_flip")]
+    pub flip_x: bool,
+    #[serde(default = "default_flip")]
+    pub flip_y: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -286,6 +757,11 @@ impl Image {
             height,
             image_data,
             rotation_angle: default_rotation(),
+            skew: default_skew(),
+            flip_x: default_flip(),
+            flip_y: default_flip(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
             z_index: default_z_index(),
             locked: default_locked(),
         }
@@ -299,20 +775,40 @@ pub struct Text {
     pub width: f64,
     pub height: f64,
     pub content: String,
+    // styled spans over `content`; empty while the text is plain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runs: Vec<TextRun>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paragraphs: Vec<ParagraphStyle>,
     #[serde(default = "default_font_family")]
     pub font_family: String,
     #[serde(default = "default_font_size")]
     pub font_size: f64,
-    #[serde(default = "default_font_weight")]
-    pub font_weight: String,
-    #[serde(default = "default_text_align")]
-    pub text_align: String,
-    #[serde(default = "default_stroke_color")]
-    pub color: String,
-    #[serde(default = "default_text_opacity")]
+    #[serde(default = "default_font_weight", deserialize_with = "lenient_font_weight")]
+    pub font_weight: FontWeight,
+    #[serde(default = "default_text_align", deserialize_with = "lenient_text_align")]
+    pub text_align: TextAlign,
+    #[serde(default)]
+    pub sizing: TextSizing,
+    #[serde(default)]
+    pub vertical_align: VerticalAlign,
+    // set when this text is the label of a shape or connector and follows it around
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_id: Option<u64>,
+    #[serde(default = "default_stroke_color", deserialize_with = "lenient_stroke_color")]
+    pub color: Color,
+    #[serde(default = "default_opacity")]
     pub opacity: f64,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
     #[serde(default = "default_rotation")]
     pub rotation_angle: f64,
+    #[serde(default = "default_skew")]
+    pub skew: f64,
+    #[serde(default = "default_flip")]
+    pub flip_x: bool,
+    #[serde(default = "default_flip")]
+    pub flip_y: bool,
     #[serde(default = "default_z_index")]
     pub z_index: i32,
     #[serde(default = "default_locked")]
@@ -327,13 +823,22 @@ impl Text {
             width,
             height,
             content,
+            runs: Vec::new(),
+            paragraphs: Vec::new(),
             font_family: default_font_family(),
             font_size: default_font_size(),
             font_weight: default_font_weight(),
             text_align: default_text_align(),
+            sizing: TextSizing::default(),
+            vertical_align: VerticalAlign::default(),
+            container_id: None,
             color: default_stroke_color(),
-            opacity: default_text_opacity(),
+            opacity: default_opacity(),
+            blend_mode: BlendMode::default(),
             rotation_angle: default_rotation(),
+            skew: default_skew(),
+            flip_x: default_flip(),
+            flip_y: default_flip(),
             z_index: default_z_index(),
             locked: default_locked(),
         }
@@ -348,16 +853,20 @@ fn default_font_size() -> f64 {
     36.0
 }
 
-fn default_font_weight() -> String {
-    "normal".to_string()
+fn default_font_weight() -> FontWeight {
+    FontWeight::NORMAL
 }
 
-fn default_text_align() -> String {
-    "left".to_string()
+fn default_text_align() -> TextAlign {
+    TextAlign::Left
 }
 
-fn default_text_opacity() -> f64 {
-    1.0
+fn lenient_font_weight<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FontWeight, D::Error> {
+    lenient(deserializer, default_font_weight)
+}
+
+fn lenient_text_align<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TextAlign, D::Error> {
+    lenient(deserializer, default_text_align)
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -368,6 +877,9 @@ pub struct Group {
     pub rotation_angle: f64,
     #[serde(default = "default_locked")]
     pub locked: bool,
+    // everything applied to the group since it was created; children are stored already transformed
+    #[serde(default = "default_transform")]
+    pub transform: Affine,
 }
 
 impl Group {
@@ -377,6 +889,877 @@ impl Group {
             element_ids,
             rotation_angle: default_rotation(),
             locked: default_locked(),
+            transform: default_transform(),
+        }
+    }
+}
+
+fn default_transform() -> Affine {
+    Affine::IDENTITY
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub id: u64,
+    pub position: Point,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default = "default_frame_name")]
+    pub name: String,
+    #[serde(default)]
+    pub element_ids: Vec<u64>,
+    #[serde(default = "default_frame_stroke_color", deserialize_with = "lenient_frame_stroke_color")]
+    pub stroke_color: Color,
+    #[serde(default = "default_fill_color", deserialize_with = "lenient_option")]
+    pub fill_color: Option<Color>,
+    #[serde(default = "default_frame_line_width")]
+    pub line_width: f64,
+    // children are cut off at the frame edges
+    #[serde(default = "default_clip")]
+    pub clip: bool,
+    #[serde(default = "default_z_index")]
+    pub z_index: i32,
+    #[serde(default = "default_locked")]
+    pub locked: bool,
+}
+
+impl Frame {
+    pub fn new(id: u64, position: Point, width: f64, height: f64, name: String) -> Self {
+        Self {
+            id,
+            position,
+            width,
+            height,
+            name,
+            element_ids: Vec::new(),
+            stroke_color: default_frame_stroke_color(),
+            fill_color: default_fill_color(),
+            line_width: default_frame_line_width(),
+            clip: default_clip(),
+            z_index: default_z_index(),
+            locked: default_locked(),
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.position.x, self.position.y, self.width, self.height).normalized()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    pub id: u64,
+    #[serde(default = "default_layer_name")]
+    pub name: String,
+    // stacking position among layers, bottom first
+    #[serde(default)]
+    pub index: i32,
+    #[serde(default)]
+    pub element_ids: Vec<u64>,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    #[serde(default = "default_locked")]
+    pub locked: bool,
+    #[serde(default = "default_layer_opacity")]
+    pub opacity: f64,
+}
+
+impl Layer {
+    pub fn new(id: u64, name: String, index: i32) -> Self {
+        Self {
+            id,
+            name,
+            index,
+            element_ids: Vec::new(),
+            visible: default_visible(),
+            locked: default_locked(),
+            opacity: default_layer_opacity(),
+        }
+    }
+}
+
+fn default_layer_name() -> String {
+    "Layer".to_string()
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+fn default_layer_opacity() -> f64 {
+    1.0
+}
+
+fn default_frame_name() -> String {
+    "Frame".to_string()
+}
+
+fn default_frame_stroke_color() -> Color {
+    Color::rgb(0xbb, 0xbb, 0xbb)
+}
+
+fn lenient_frame_stroke_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    lenient(deserializer, default_frame_stroke_color)
+}
+
+fn default_frame_line_width() -> f64 {
+    1.0
+}
+
+fn default_clip() -> bool {
+    true
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElementKind {
+    Rectangle,
+    Ellipse,
+    Line,
+    Arrow,
+    Diamond,
+    Path,
+    Image,
+    Text,
+    Group,
+    Polygon,
+    RegularPolygon,
+    Star,
+    Frame,
+    Layer,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Element {
+    Rectangle(Rectangle),
+    Ellipse(Ellipse),
+    Line(Line),
+    Arrow(Arrow),
+    Diamond(Diamond),
+    Path(Path),
+    Image(Image),
+    Text(Text),
+    Group(Group),
+    Polygon(Polygon),
+    RegularPolygon(RegularPolygon),
+    Star(Star),
+    Frame(Frame),
+    Layer(Layer),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElementPatch {
+    pub position: Option<Point>,
+    pub start: Option<Point>,
+    pub end: Option<Point>,
+    pub control_points: Option<Vec<Point>>,
+    pub points: Option<Vec<Point>>,
+    pub pressures: Option<Vec<f64>>,
+    pub smoothing: Option<Smoothing>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub radius_x: Option<f64>,
+    pub radius_y: Option<f64>,
+    pub stroke_color: Option<Color>,
+    #[serde(rename = "fill_color")]
+    pub fill: Option<Option<Fill>>,
+    pub line_width: Option<f64>,
+    pub dash_pattern: Option<DashPattern>,
+    pub start_marker: Option<Marker>,
+    pub end_marker: Option<Marker>,
+    pub marker_size: Option<f64>,
+    pub routing: Option<Routing>,
+    pub border_radius: Option<f64>,
+    pub sides: Option<u32>,
+    pub inner_radius: Option<f64>,
+    pub name: Option<String>,
+    pub clip: Option<bool>,
+    pub visible: Option<bool>,
+    pub rotation_angle: Option<f64>,
+    pub skew: Option<f64>,
+    pub flip_x: Option<bool>,
+    pub flip_y: Option<bool>,
+    pub locked: Option<bool>,
+    pub content: Option<String>,
+    pub font_family: Option<String>,
+    pub font_size: Option<f64>,
+    pub font_weight: Option<FontWeight>,
+    pub text_align: Option<TextAlign>,
+    pub sizing: Option<TextSizing>,
+    pub vertical_align: Option<VerticalAlign>,
+    pub color: Option<Color>,
+    pub opacity: Option<f64>,
+    pub blend_mode: Option<BlendMode>,
+    pub roughness: Option<f64>,
+    pub seed: Option<u32>,
+}
+
+fn assign<T: PartialEq + Clone>(field: &mut T, value: &Option<T>) -> bool {
+    match value {
+        Some(value) if field != value => {
+            *field = value.clone();
+            true
+        }
+        _ => false,
+    }
+}
+
+impl Element {
+    pub fn kind(&self) -> ElementKind {
+        match self {
+            Element::Rectangle(_) => ElementKind::Rectangle,
+            Element::Ellipse(_) => ElementKind::Ellipse,
+            Element::Line(_) => ElementKind::Line,
+            Element::Arrow(_) => ElementKind::Arrow,
+            Element::Diamond(_) => ElementKind::Diamond,
+            Element::Path(_) => ElementKind::Path,
+            Element::Image(_) => ElementKind::Image,
+            Element::Text(_) => ElementKind::Text,
+            Element::Group(_) => ElementKind::Group,
+            Element::Polygon(_) => ElementKind::Polygon,
+            Element::RegularPolygon(_) => ElementKind::RegularPolygon,
+            Element::Star(_) => ElementKind::Star,
+            Element::Frame(_) => ElementKind::Frame,
+            Element::Layer(_) => ElementKind::Layer,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        match self {
+            Element::Rectangle(rect) => rect.id,
+            Element::Ellipse(ellipse) => ellipse.id,
+            Element::Line(line) => line.id,
+            Element::Arrow(arrow) => arrow.id,
+            Element::Diamond(diamond) => diamond.id,
+            Element::Path(path) => path.id,
+            Element::Image(image) => image.id,
+            Element::Text(text) => text.id,
+            Element::Group(group) => group.id,
+            Element::Polygon(polygon) => polygon.id,
+            Element::RegularPolygon(polygon) => polygon.id,
+            Element::Star(star) => star.id,
+            Element::Frame(frame) => frame.id,
+            Element::Layer(layer) => layer.id,
+        }
+    }
+
+    pub fn z_index(&self) -> Option<i32> {
+        match self {
+            Element::Rectangle(rect) => Some(rect.z_index),
+            Element::Ellipse(ellipse) => Some(ellipse.z_index),
+            Element::Line(line) => Some(line.z_index),
+            Element::Arrow(arrow) => Some(arrow.z_index),
+            Element::Diamond(diamond) => Some(diamond.z_index),
+            Element::Path(path) => Some(path.z_index),
+            Element::Image(image) => Some(image.z_index),
+            Element::Text(text) => Some(text.z_index),
+            Element::Group(_) => None,
+            Element::Polygon(polygon) => Some(polygon.z_index),
+            Element::RegularPolygon(polygon) => Some(polygon.z_index),
+            Element::Star(star) => Some(star.z_index),
+            Element::Frame(frame) => Some(frame.z_index),
+            Element::Layer(_) => None,
+        }
+    }
+
+    // containers have no opacity of their own; layers apply theirs to their members
+    pub fn opacity(&self) -> f64 {
+        match self {
+            Element::Rectangle(rect) => rect.opacity,
+            Element::Ellipse(ellipse) => ellipse.opacity,
+            Element::Line(line) => line.opacity,
+            Element::Arrow(arrow) => arrow.opacity,
+            Element::Diamond(diamond) => diamond.opacity,
+            Element::Path(path) => path.opacity,
+            Element::Image(image) => image.opacity,
+            Element::Text(text) => text.opacity,
+            Element::Polygon(polygon) => polygon.opacity,
+            Element::RegularPolygon(polygon) => polygon.opacity,
+            Element::Star(star) => star.opacity,
+            Element::Group(_) | Element::Frame(_) | Element::Layer(_) => 1.0,
+        }
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        match self {
+            Element::Rectangle(rect) => rect.blend_mode,
+            Element::Ellipse(ellipse) => ellipse.blend_mode,
+            Element::Line(line) => line.blend_mode,
+            Element::Arrow(arrow) => arrow.blend_mode,
+            Element::Diamond(diamond) => diamond.blend_mode,
+            Element::Path(path) => path.blend_mode,
+            Element::Image(image) => image.blend_mode,
+            Element::Text(text) => text.blend_mode,
+            Element::Polygon(polygon) => polygon.blend_mode,
+            Element::RegularPolygon(polygon) => polygon.blend_mode,
+            Element::Star(star) => star.blend_mode,
+            Element::Group(_) | Element::Frame(_) | Element::Layer(_) => BlendMode::Normal,
+        }
+    }
+
+    pub fn roughness(&self) -> f64 {
+        match self {
+            Element::Rectangle(rect) => rect.roughness,
+            Element::Ellipse(ellipse) => ellipse.roughness,
+            Element::Diamond(diamond) => diamond.roughness,
+            Element::Line(line) => line.roughness,
+            Element::Arrow(arrow) => arrow.roughness,
+            _ => 0.0,
+        }
+    }
+
+    pub fn seed(&self) -> Option<u32> {
+        match self {
+            Element::Rectangle(rect) => Some(rect.seed),
+            Element::Ellipse(ellipse) => Some(ellipse.seed),
+            Element::Diamond(diamond) => Some(diamond.seed),
+            Element::Line(line) => Some(line.seed),
+            Element::Arrow(arrow) => Some(arrow.seed),
+            _ => None,
+        }
+    }
+
+    // documents saved before seeds existed load with 0, which would give every shape the same strokes
+    pub(crate) fn seed_if_missing(&mut self) {
+        let id = self.id();
+        let seed = match self {
+            Element::Rectangle(Rectangle { seed, .. })
+            | Element::Ellipse(Ellipse { seed, .. })
+            | Element::Diamond(Diamond { seed, .. })
+            | Element::Line(Line { seed, .. })
+            | Element::Arrow(Arrow { seed, .. }) => seed,
+            _ => return,
+        };
+        if *seed == 0 {
+            *seed = default_seed(id);
+        }
+    }
+
+    // only closed shapes have a fill; frames keep a flat background color instead
+    pub fn fill(&self) -> Option<&Fill> {
+        match self {
+            Element::Rectangle(rect) => rect.fill.as_ref(),
+            Element::Ellipse(ellipse) => ellipse.fill.as_ref(),
+            Element::Diamond(diamond) => diamond.fill.as_ref(),
+            Element::Polygon(polygon) => polygon.fill.as_ref(),
+            Element::RegularPolygon(polygon) => polygon.fill.as_ref(),
+            Element::Star(star) => star.fill.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn set_z_index(&mut self, z_index: i32) {
+        match self {
+            Element::Rectangle(rect) => rect.z_index = z_index,
+            Element::Ellipse(ellipse) => ellipse.z_index = z_index,
+            Element::Line(line) => line.z_index = z_index,
+            Element::Arrow(arrow) => arrow.z_index = z_index,
+            Element::Diamond(diamond) => diamond.z_index = z_index,
+            Element::Path(path) => path.z_index = z_index,
+            Element::Image(image) => image.z_index = z_index,
+            Element::Text(text) => text.z_index = z_index,
+            Element::Group(_) => {}
+            Element::Polygon(polygon) => polygon.z_index = z_index,
+            Element::RegularPolygon(polygon) => polygon.z_index = z_index,
+            Element::Star(star) => star.z_index = z_index,
+            Element::Frame(frame) => frame.z_index = z_index,
+            Element::Layer(_) => {}
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        match self {
+            Element::Rectangle(rect) => rect.locked,
+            Element::Ellipse(ellipse) => ellipse.locked,
+            Element::Line(line) => line.locked,
+            Element::Arrow(arrow) => arrow.locked,
+            Element::Diamond(diamond) => diamond.locked,
+            Element::Path(path) => path.locked,
+            Element::Image(image) => image.locked,
+            Element::Text(text) => text.locked,
+            Element::Group(group) => group.locked,
+            Element::Polygon(polygon) => polygon.locked,
+            Element::RegularPolygon(polygon) => polygon.locked,
+            Element::Star(star) => star.locked,
+            Element::Frame(frame) => frame.locked,
+            Element::Layer(layer) => layer.locked,
+        }
+    }
+
+    pub fn rotation(&self) -> f64 {
+        match self {
+            Element::Rectangle(rect) => rect.rotation_angle,
+            Element::Ellipse(ellipse) => ellipse.rotation_angle,
+            Element::Line(line) => line.rotation_angle,
+            Element::Arrow(arrow) => arrow.rotation_angle,
+            Element::Diamond(diamond) => diamond.rotation_angle,
+            Element::Path(path) => path.rotation_angle,
+            Element::Image(image) => image.rotation_angle,
+            Element::Text(text) => text.rotation_angle,
+            Element::Group(group) => group.rotation_angle,
+            Element::Polygon(polygon) => polygon.rotation_angle,
+            Element::RegularPolygon(polygon) => polygon.rotation_angle,
+            Element::Star(star) => star.rotation_angle,
+            Element::Frame(_) | Element::Layer(_) => 0.0,
+        }
+    }
+
+    // flips and then skew in the element's own frame, about its center
+    pub fn skew_transform(&self) -> Affine {
+        let (skew, flip_x, flip_y) = match self {
+            Element::Rectangle(rect) => (rect.skew, rect.flip_x, rect.flip_y),
+            Element::Ellipse(ellipse) => (ellipse.skew, ellipse.flip_x, ellipse.flip_y),
+            Element::Diamond(diamond) => (diamond.skew, diamond.flip_x, diamond.flip_y),
+            Element::RegularPolygon(polygon) => (polygon.skew, polygon.flip_x, polygon.flip_y),
+            Element::Star(star) => (star.skew, star.flip_x, star.flip_y),
+            Element::Image(image) => (image.skew, image.flip_x, image.flip_y),
+            Element::Text(text) => (text.skew, text.flip_x, text.flip_y),
+            _ => return Affine::IDENTITY,
+        };
+        let sign = |flip: bool| if flip { -1.0 } else { 1.0 };
+        Affine::scale(sign(flip_x), sign(flip_y)).then(&Affine::skew(skew, 0.0))
+    }
+
+    fn box_fields(&mut self) -> Option<BoxFields<'_>> {
+        let fields = match self {
+            Element::Rectangle(Rectangle { position, width, height, rotation_angle, skew, flip_x, flip_y, .. })
+            | Element::Diamond(Diamond { position, width, height, rotation_angle, skew, flip_x, flip_y, .. })
+            | Element::RegularPolygon(RegularPolygon {
+                position, width, height, rotation_angle, skew, flip_x, flip_y, ..
+            })
+            | Element::Star(Star { position, width, height, rotation_angle, skew, flip_x, flip_y, .. })
+            | Element::Image(Image { position, width, height, rotation_angle, skew, flip_x, flip_y, .. })
+            | Element::Text(Text { position, width, height, rotation_angle, skew, flip_x, flip_y, .. }) => BoxFields {
+                position,
+                width,
+                height,
+                rotation: rotation_angle,
+                skew,
+                flip_x,
+                flip_y,
+            },
+            _ => return None,
+        };
+        Some(fields)
+    }
+
+    pub fn bounds(&self) -> Option<Rect> {
+        match self {
+            Element::Rectangle(_)
+            | Element::Diamond(_)
+            | Element::Image(_)
+            | Element::Text(_)
+            | Element::RegularPolygon(_)
+            | Element::Star(_) => {
+                let (frame, _) = self.local_frame()?;
+                let transform = self.transform();
+                Rect::from_points(frame.corners().map(|corner| transform.apply(corner)))
+            }
+            Element::Ellipse(ellipse) => {
+                let transform = self.transform();
+                let (radius_x, radius_y) = (ellipse.radius_x.abs(), ellipse.radius_y.abs());
+                let half_width = (transform.a * radius_x).hypot(transform.c * radius_y);
+                let half_height = (transform.b * radius_x).hypot(transform.d * radius_y);
+                Some(Rect::new(
+                    ellipse.position.x - half_width,
+                    ellipse.position.y - half_height,
+                    half_width * 2.0,
+                    half_height * 2.0,
+                ))
+            }
+            Element::Line(_) | Element::Arrow(_) => match (self.endpoints(), self.control_points()) {
+                _ if self.rotation() != 0.0 => {
+                    let transform = self.connector_transform();
+                    Rect::from_points(self.connector_points().into_iter().map(|point| transform.apply(point)))
+                }
+                (Some((start, end)), controls) if !controls.is_empty() => {
+                    Some(bezier_bounds(start, controls, end))
+                }
+                _ => Rect::from_points(self.connector_points()),
+            },
+            Element::Path(path) => {
+                Rect::from_points(path.points.iter().copied()).map(|bounds| bounds.rotated_bounds(path.rotation_angle))
+            }
+            Element::Polygon(polygon) => Rect::from_points(polygon.points.iter().copied())
+                .map(|bounds| bounds.rotated_bounds(polygon.rotation_angle)),
+            Element::Frame(frame) => Some(frame.rect()),
+            Element::Group(_) | Element::Layer(_) => None,
+        }
+    }
+
+    pub fn line_width(&self) -> Option<f64> {
+        match self {
+            Element::Rectangle(rect) => Some(rect.line_width),
+            Element::Ellipse(ellipse) => Some(ellipse.line_width),
+            Element::Line(line) => Some(line.line_width),
+            Element::Arrow(arrow) => Some(arrow.line_width),
+            Element::Diamond(diamond) => Some(diamond.line_width),
+            Element::Path(path) => Some(path.max_width()),
+            Element::Polygon(polygon) => Some(polygon.line_width),
+            Element::RegularPolygon(polygon) => Some(polygon.line_width),
+            Element::Star(star) => Some(star.line_width),
+            Element::Frame(frame) => Some(frame.line_width),
+            Element::Image(_) | Element::Text(_) | Element::Group(_) | Element::Layer(_) => None,
+        }
+    }
+
+    pub fn translate(&mut self, delta_x: f64, delta_y: f64) {
+        let shift = |point: &mut Point| {
+            point.x += delta_x;
+            point.y += delta_y;
+        };
+        match self {
+            Element::Rectangle(rect) => shift(&mut rect.position),
+            Element::Ellipse(ellipse) => shift(&mut ellipse.position),
+            Element::Diamond(diamond) => shift(&mut diamond.position),
+            Element::Polygon(polygon) => polygon.points.iter_mut().for_each(shift),
+            Element::RegularPolygon(polygon) => shift(&mut polygon.position),
+            Element::Star(star) => shift(&mut star.position),
+            Element::Frame(frame) => shift(&mut frame.position),
+            Element::Image(image) => shift(&mut image.position),
+            Element::Text(text) => shift(&mut text.position),
+            Element::Line(line) => {
+                shift(&mut line.start);
+                shift(&mut line.end);
+                line.waypoints.iter_mut().for_each(shift);
+                line.control_points.iter_mut().for_each(shift);
+            }
+            Element::Arrow(arrow) => {
+                shift(&mut arrow.start);
+                shift(&mut arrow.end);
+                arrow.waypoints.iter_mut().for_each(shift);
+                arrow.control_points.iter_mut().for_each(shift);
+            }
+            Element::Path(path) => path.points.iter_mut().for_each(shift),
+            Element::Group(group) => group.transform = group.transform.then(&Affine::translate(delta_x, delta_y)),
+            Element::Layer(_) => {}
+        }
+    }
+
+    // boxes take up any rotation, skew, scale or flip in their own fields, point lists are mapped directly
+    pub fn apply_transform(&mut self, transform: &Affine) {
+        let own = match self {
+            Element::Line(_) | Element::Arrow(_) => self.connector_transform(),
+            _ => self.transform(),
+        };
+        let shape = self.skew_transform().then(&Affine::rotate(self.rotation())).then(transform);
+        // bakes the element's own rotation into its points
+        let bake = |point: &mut Point| *point = transform.apply(own.apply(*point));
+        if let Some(fields) = self.box_fields() {
+            let frame = Rect::new(fields.position.x, fields.position.y, *fields.width, *fields.height).normalized();
+            let placement = decompose(
+                shape.apply_vector(Vector::new(frame.width, 0.0)),
+                shape.apply_vector(Vector::new(0.0, frame.height)),
+                *fields.rotation,
+            );
+            let center = transform.apply(frame.center());
+            *fields.position = Point::new(center.x - placement.width / 2.0, center.y - placement.height / 2.0);
+            *fields.width = placement.width;
+            *fields.height = placement.height;
+            *fields.rotation = placement.rotation;
+            *fields.skew = placement.skew;
+            *fields.flip_x = placement.flip_x;
+            *fields.flip_y = placement.flip_y;
+            // text grows with its box, so reflowing afterwards keeps the new size
+            if let Element::Text(text) = self {
+                if frame.height > 0.0 {
+                    text.scale_font_sizes(placement.height / frame.height);
+                }
+            }
+            return;
+        }
+        match self {
+            Element::Ellipse(ellipse) => {
+                let placement = decompose(
+                    shape.apply_vector(Vector::new(ellipse.radius_x.abs(), 0.0)),
+                    shape.apply_vector(Vector::new(0.0, ellipse.radius_y.abs())),
+                    ellipse.rotation_angle,
+                );
+                ellipse.position = transform.apply(ellipse.position);
+                ellipse.radius_x = placement.width;
+                ellipse.radius_y = placement.height;
+                ellipse.rotation_angle = placement.rotation;
+                ellipse.skew = placement.skew;
+                ellipse.flip_x = placement.flip_x;
+                ellipse.flip_y = placement.flip_y;
+            }
+            Element::Polygon(polygon) => {
+                polygon.points.iter_mut().for_each(bake);
+                polygon.rotation_angle = 0.0;
+            }
+            Element::Path(path) => {
+                path.points.iter_mut().for_each(bake);
+                path.rotation_angle = 0.0;
+            }
+            Element::Line(line) => {
+                bake(&mut line.start);
+                bake(&mut line.end);
+                line.waypoints.iter_mut().for_each(bake);
+                line.control_points.iter_mut().for_each(bake);
+                line.rotation_angle = 0.0;
+            }
+            Element::Arrow(arrow) => {
+                bake(&mut arrow.start);
+                bake(&mut arrow.end);
+                arrow.waypoints.iter_mut().for_each(bake);
+                arrow.control_points.iter_mut().for_each(bake);
+                arrow.rotation_angle = 0.0;
+            }
+            Element::Frame(frame) => {
+                // frames stay axis aligned and cover their transformed corners
+                let corners = frame.rect().corners().map(|corner| transform.apply(corner));
+                if let Some(rect) = Rect::from_points(corners) {
+                    frame.position = Point::new(rect.x, rect.y);
+                    frame.width = rect.width;
+                    frame.height = rect.height;
+                }
+            }
+            Element::Group(group) => {
+                group.transform = group.transform.then(transform);
+                group.rotation_angle = group.transform.b.atan2(group.transform.a);
+            }
+            // boxes are placed above
+            Element::Rectangle(_)
+            | Element::Diamond(_)
+            | Element::RegularPolygon(_)
+            | Element::Star(_)
+            | Element::Image(_)
+            | Element::Text(_)
+            | Element::Layer(_) => {}
+        }
+    }
+
+    pub fn apply_patch(&mut self, patch: &ElementPatch) -> bool {
+        let line_width = patch.line_width.map(|width| width.max(0.1));
+        let border_radius = patch.border_radius.map(|radius| radius.max(0.0));
+        let marker_size = patch.marker_size.map(|size| size.max(1.0));
+        let sides = patch.sides.map(clamp_sides);
+        let inner_radius = patch.inner_radius.map(|radius| radius.clamp(0.0, 1.0));
+        let control_points = patch.control_points.as_ref().map(|points| {
+            points.iter().copied().take(MAX_BEZIER_CONTROL_POINTS).collect::<Vec<_>>()
+        });
+        let opacity = patch.opacity.map(|opacity| opacity.clamp(0.0, 1.0));
+        let roughness = patch.roughness.map(|roughness| roughness.clamp(0.0, MAX_ROUGHNESS));
+        let mut changed = false;
+        match self {
+            Element::Rectangle(rect) => {
+                changed |= assign(&mut rect.position, &patch.position);
+                changed |= assign(&mut rect.width, &patch.width);
+                changed |= assign(&mut rect.height, &patch.height);
+                changed |= assign(&mut rect.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut rect.fill, &patch.fill);
+                changed |= assign(&mut rect.line_width, &line_width);
+                changed |= assign(&mut rect.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut rect.border_radius, &border_radius);
+                changed |= assign(&mut rect.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut rect.skew, &patch.skew);
+                changed |= assign(&mut rect.flip_x, &patch.flip_x);
+                changed |= assign(&mut rect.flip_y, &patch.flip_y);
+                changed |= assign(&mut rect.opacity, &opacity);
+                changed |= assign(&mut rect.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut rect.roughness, &roughness);
+                changed |= assign(&mut rect.seed, &patch.seed);
+                changed |= assign(&mut rect.locked, &patch.locked);
+            }
+            Element::Ellipse(ellipse) => {
+                changed |= assign(&mut ellipse.position, &patch.position);
+                changed |= assign(&mut ellipse.radius_x, &patch.radius_x);
+                changed |= assign(&mut ellipse.radius_y, &patch.radius_y);
+                changed |= assign(&mut ellipse.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut ellipse.fill, &patch.fill);
+                changed |= assign(&mut ellipse.line_width, &line_width);
+                changed |= assign(&mut ellipse.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut ellipse.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut ellipse.skew, &patch.skew);
+                changed |= assign(&mut ellipse.flip_x, &patch.flip_x);
+                changed |= assign(&mut ellipse.flip_y, &patch.flip_y);
+                changed |= assign(&mut ellipse.opacity, &opacity);
+                changed |= assign(&mut ellipse.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut ellipse.roughness, &roughness);
+                changed |= assign(&mut ellipse.seed, &patch.seed);
+                changed |= assign(&mut ellipse.locked, &patch.locked);
+            }
+            Element::Diamond(diamond) => {
+                changed |= assign(&mut diamond.position, &patch.position);
+                changed |= assign(&mut diamond.width, &patch.width);
+                changed |= assign(&mut diamond.height, &patch.height);
+                changed |= assign(&mut diamond.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut diamond.fill, &patch.fill);
+                changed |= assign(&mut diamond.line_width, &line_width);
+                changed |= assign(&mut diamond.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut diamond.border_radius, &border_radius);
+                changed |= assign(&mut diamond.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut diamond.skew, &patch.skew);
+                changed |= assign(&mut diamond.flip_x, &patch.flip_x);
+                changed |= assign(&mut diamond.flip_y, &patch.flip_y);
+                changed |= assign(&mut diamond.opacity, &opacity);
+                changed |= assign(&mut diamond.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut diamond.roughness, &roughness);
+                changed |= assign(&mut diamond.seed, &patch.seed);
+                changed |= assign(&mut diamond.locked, &patch.locked);
+            }
+            Element::Line(line) => {
+                changed |= assign(&mut line.start, &patch.start);
+                changed |= assign(&mut line.end, &patch.end);
+                changed |= assign(&mut line.control_points, &control_points);
+                changed |= assign(&mut line.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut line.line_width, &line_width);
+                changed |= assign(&mut line.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut line.start_marker, &patch.start_marker);
+                changed |= assign(&mut line.end_marker, &patch.end_marker);
+                changed |= assign(&mut line.marker_size, &marker_size);
+                changed |= assign(&mut line.routing, &patch.routing);
+                changed |= assign(&mut line.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut line.opacity, &opacity);
+                changed |= assign(&mut line.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut line.roughness, &roughness);
+                changed |= assign(&mut line.seed, &patch.seed);
+                changed |= assign(&mut line.locked, &patch.locked);
+            }
+            Element::Arrow(arrow) => {
+                changed |= assign(&mut arrow.start, &patch.start);
+                changed |= assign(&mut arrow.end, &patch.end);
+                changed |= assign(&mut arrow.control_points, &control_points);
+                changed |= assign(&mut arrow.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut arrow.line_width, &line_width);
+                changed |= assign(&mut arrow.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut arrow.start_marker, &patch.start_marker);
+                changed |= assign(&mut arrow.end_marker, &patch.end_marker);
+                changed |= assign(&mut arrow.marker_size, &marker_size);
+                changed |= assign(&mut arrow.routing, &patch.routing);
+                changed |= assign(&mut arrow.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut arrow.opacity, &opacity);
+                changed |= assign(&mut arrow.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut arrow.roughness, &roughness);
+                changed |= assign(&mut arrow.seed, &patch.seed);
+                changed |= assign(&mut arrow.locked, &patch.locked);
+            }
+            Element::Path(path) => {
+                changed |= assign(&mut path.points, &patch.points);
+                changed |= assign(&mut path.pressures, &patch.pressures);
+                if !path.pressures.is_empty() && path.pressures.len() != path.points.len() {
+                    path.pressures.clear();
+                    changed = true;
+                }
+                changed |= assign(&mut path.smoothing, &patch.smoothing);
+                changed |= assign(&mut path.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut path.line_width, &line_width);
+                changed |= assign(&mut path.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut path.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut path.opacity, &opacity);
+                changed |= assign(&mut path.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut path.locked, &patch.locked);
+            }
+            Element::Image(image) => {
+                changed |= assign(&mut image.position, &patch.position);
+                changed |= assign(&mut image.width, &patch.width.map(|width| width.max(1.0)));
+                changed |= assign(&mut image.height, &patch.height.map(|height| height.max(1.0)));
+                changed |= assign(&mut image.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut image.skew, &patch.skew);
+                changed |= assign(&mut image.flip_x, &patch.flip_x);
+                changed |= assign(&mut image.flip_y, &patch.flip_y);
+                changed |= assign(&mut image.opacity, &opacity);
+                changed |= assign(&mut image.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut image.locked, &patch.locked);
+            }
+            Element::Text(text) => {
+                changed |= assign(&mut text.position, &patch.position);
+                changed |= assign(&mut text.width, &patch.width);
+                changed |= assign(&mut text.height, &patch.height);
+                if assign(&mut text.content, &patch.content) {
+                    text.reset_rich_text();
+                    changed = true;
+                }
+                changed |= assign(&mut text.font_family, &patch.font_family);
+                changed |= assign(&mut text.font_size, &patch.font_size);
+                changed |= assign(&mut text.font_weight, &patch.font_weight);
+                changed |= assign(&mut text.text_align, &patch.text_align);
+                changed |= assign(&mut text.sizing, &patch.sizing);
+                changed |= assign(&mut text.vertical_align, &patch.vertical_align);
+                changed |= assign(&mut text.color, &patch.color);
+                changed |= assign(&mut text.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut text.skew, &patch.skew);
+                changed |= assign(&mut text.flip_x, &patch.flip_x);
+                changed |= assign(&mut text.flip_y, &patch.flip_y);
+                changed |= assign(&mut text.opacity, &opacity);
+                changed |= assign(&mut text.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut text.locked, &patch.locked);
+            }
+            Element::Group(group) => {
+                changed |= assign(&mut group.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut group.locked, &patch.locked);
+            }
+            Element::Frame(frame) => {
+                changed |= assign(&mut frame.position, &patch.position);
+                changed |= assign(&mut frame.width, &patch.width);
+                changed |= assign(&mut frame.height, &patch.height);
+                changed |= assign(&mut frame.name, &patch.name);
+                changed |= assign(&mut frame.stroke_color, &patch.stroke_color);
+                // frames only take a flat background, so a gradient or pattern falls back to its base color
+                let fill_color = patch.fill.as_ref().map(|fill| fill.as_ref().map(Fill::base_color));
+                changed |= assign(&mut frame.fill_color, &fill_color);
+                changed |= assign(&mut frame.line_width, &line_width);
+                changed |= assign(&mut frame.clip, &patch.clip);
+                changed |= assign(&mut frame.locked, &patch.locked);
+            }
+            Element::Layer(layer) => {
+                changed |= assign(&mut layer.name, &patch.name);
+                changed |= assign(&mut layer.visible, &patch.visible);
+                changed |= assign(&mut layer.locked, &patch.locked);
+                changed |= assign(&mut layer.opacity, &opacity);
+            }
+            Element::Polygon(polygon) => {
+                changed |= assign(&mut polygon.points, &patch.points);
+                changed |= assign(&mut polygon.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut polygon.fill, &patch.fill);
+                changed |= assign(&mut polygon.line_width, &line_width);
+                changed |= assign(&mut polygon.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut polygon.border_radius, &border_radius);
+                changed |= assign(&mut polygon.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut polygon.opacity, &opacity);
+                changed |= assign(&mut polygon.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut polygon.locked, &patch.locked);
+            }
+            Element::RegularPolygon(polygon) => {
+                changed |= assign(&mut polygon.position, &patch.position);
+                changed |= assign(&mut polygon.width, &patch.width);
+                changed |= assign(&mut polygon.height, &patch.height);
+                changed |= assign(&mut polygon.sides, &sides);
+                changed |= assign(&mut polygon.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut polygon.fill, &patch.fill);
+                changed |= assign(&mut polygon.line_width, &line_width);
+                changed |= assign(&mut polygon.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut polygon.border_radius, &border_radius);
+                changed |= assign(&mut polygon.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut polygon.skew, &patch.skew);
+                changed |= assign(&mut polygon.flip_x, &patch.flip_x);
+                changed |= assign(&mut polygon.flip_y, &patch.flip_y);
+                changed |= assign(&mut polygon.opacity, &opacity);
+                changed |= assign(&mut polygon.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut polygon.locked, &patch.locked);
+            }
+            Element::Star(star) => {
+                changed |= assign(&mut star.position, &patch.position);
+                changed |= assign(&mut star.width, &patch.width);
+                changed |= assign(&mut star.height, &patch.height);
+                changed |= assign(&mut star.sides, &sides);
+                changed |= assign(&mut star.inner_radius, &inner_radius);
+                changed |= assign(&mut star.stroke_color, &patch.stroke_color);
+                changed |= assign(&mut star.fill, &patch.fill);
+                changed |= assign(&mut star.line_width, &line_width);
+                changed |= assign(&mut star.dash_pattern, &patch.dash_pattern);
+                changed |= assign(&mut star.border_radius, &border_radius);
+                changed |= assign(&mut star.rotation_angle, &patch.rotation_angle);
+                changed |= assign(&mut star.skew, &patch.skew);
+                changed |= assign(&mut star.flip_x, &patch.flip_x);
+                changed |= assign(&mut star.flip_y, &patch.flip_y);
+                changed |= assign(&mut star.opacity, &opacity);
+                changed |= assign(&mut star.blend_mode, &patch.blend_mode);
+                changed |= assign(&mut star.locked, &patch.locked);
+            }
         }
+        changed
     }
 }
@@ -0,0 +1,306 @@
+use crate::geometry::Point;
+use crate::style::{Color, StyleError};
+use serde::de::{self, value::MapAccessDeserializer, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::f64::consts::FRAC_PI_4;
+use std::fmt;
+
+const DEFAULT_PATTERN_SPACING: f64 = 8.0;
+const DEFAULT_PATTERN_LINE_WIDTH: f64 = 1.0;
+
+// how the inside of a closed shape is painted; a solid fill is stored as a plain color
+// string, so documents written before gradients and patterns still load unchanged
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fill {
+    Solid(Color),
+    LinearGradient(LinearGradient),
+    RadialGradient(RadialGradient),
+    Pattern(Pattern),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    // position along the gradient, from 0 to 1
+    pub offset: f64,
+    pub color: Color,
+}
+
+// runs across the shape's own box; an angle of 0 goes from left to right
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinearGradient {
+    #[serde(default)]
+    pub angle: f64,
+    pub stops: Vec<GradientStop>,
+}
+
+// center and radius are fractions of the shape's own box
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RadialGradient {
+    #[serde(default = "default_gradient_center")]
+    pub center: Point,
+    #[serde(default = "default_gradient_radius")]
+    pub radius: f64,
+    pub stops: Vec<GradientStop>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    Hatch,
+    CrossHatch,
+    Dots,
+}
+
+// lines or dots repeated every `spacing` document units, drawn over an optional background
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub color: Color,
+    #[serde(default)]
+    pub background: Option<Color>,
+    #[serde(default = "default_pattern_spacing")]
+    pub spacing: f64,
+    #[serde(default = "default_pattern_angle")]
+    pub angle: f64,
+    // stroke width for hatching, dot radius for dots
+    #[serde(default = "default_pattern_line_width")]
+    pub line_width: f64,
+}
+
+fn default_gradient_center() -> Point {
+    Point::new(0.5, 0.5)
+}
+
+fn default_gradient_radius() -> f64 {
+    0.5
+}
+
+fn default_pattern_spacing() -> f64 {
+    DEFAULT_PATTERN_SPACING
+}
+
+fn default_pattern_angle() -> f64 {
+    FRAC_PI_4
+}
+
+fn default_pattern_line_width() -> f64 {
+    DEFAULT_PATTERN_LINE_WIDTH
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind, color: Color) -> Self {
+        Self {
+            kind,
+            color,
+            background: None,
+            spacing: default_pattern_spacing(),
+            angle: default_pattern_angle(),
+            line_width: default_pattern_line_width(),
+        }
+    }
+}
+
+impl Fill {
+    // the color to use where only one can be shown, such as a thumbnail or a frame background
+    pub fn base_color(&self) -> Color {
+        match self {
+            Fill::Solid(color) => *color,
+            Fill::LinearGradient(LinearGradient { stops, .. }) | Fill::RadialGradient(RadialGradient { stops, .. }) => {
+                stops.first().map_or(Color::BLACK, |stop| stop.color)
+            }
+            Fill::Pattern(pattern) => pattern.background.unwrap_or(pattern.color),
+        }
+    }
+
+    fn validate(self) -> Result<Self, StyleError> {
+        let invalid = |reason: &str| Err(StyleError::InvalidFill(reason.to_string()));
+        match &self {
+            Fill::Solid(_) => {}
+            Fill::LinearGradient(LinearGradient { angle, stops }) => {
+                if !angle.is_finite() {
+                    return invalid("gradient angle must be a finite number");
+                }
+                validate_stops(stops)?;
+            }
+            Fill::RadialGradient(RadialGradient { center, radius, stops }) => {
+                if !center.x.is_finite() || !center.y.is_finite() {
+                    return invalid("gradient center must be finite");
+                }
+                if radius.is_nan() || *radius <= 0.0 {
+                    return invalid("gradient radius must be positive");
+                }
+                validate_stops(stops)?;
+            }
+            Fill::Pattern(pattern) => {
+                if pattern.spacing.is_nan() || pattern.spacing <= 0.0 {
+                    return invalid("pattern spacing must be positive");
+                }
+                if pattern.line_width.is_nan() || pattern.line_width <= 0.0 {
+                    return invalid("pattern line width must be positive");
+                }
+                if !pattern.angle.is_finite() {
+                    return invalid("pattern angle must be a finite number");
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn validate_stops(stops: &[GradientStop]) -> Result<(), StyleError> {
+    if stops.len() < 2 {
+        return Err(StyleError::InvalidFill("a gradient needs at least two stops".to_string()));
+    }
+    if stops.iter().any(|stop| !(0.0..=1.0).contains(&stop.offset)) {
+        return Err(StyleError::InvalidFill("gradient stop offsets must be between 0 and 1".to_string()));
+    }
+    if stops.windows(2).any(|pair| pair[1].offset < pair[0].offset) {
+        return Err(StyleError::InvalidFill("gradient stops must be in increasing offset order".to_string()));
+    }
+    Ok(())
+}
+
+impl From<Color> for Fill {
+    fn from(color: Color) -> Self {
+        Fill::Solid(color)
+    }
+}
+
+// the object form of every fill, tagged by `type`
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TaggedFill {
+    Solid { color: Color },
+    LinearGradient(LinearGradient),
+    RadialGradient(RadialGradient),
+    Pattern(Pattern),
+}
+
+impl Serialize for Fill {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Fill::Solid(color) => color.serialize(serializer),
+            Fill::LinearGradient(gradient) => TaggedFill::LinearGradient(gradient.clone()).serialize(serializer),
+            Fill::RadialGradient(gradient) => TaggedFill::RadialGradient(gradient.clone()).serialize(serializer),
+            Fill::Pattern(pattern) => TaggedFill::Pattern(pattern.clone()).serialize(serializer),
+        }
+    }
+}
+
+struct FillVisitor;
+
+impl<'de> de::Visitor<'de> for FillVisitor {
+    type Value = Fill;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a color or a fill object with a `type` of solid, linear_gradient, radial_gradient or pattern")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map(Fill::Solid).map_err(E::custom)
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        let fill = match TaggedFill::deserialize(MapAccessDeserializer::new(map))? {
+            TaggedFill::Solid { color } => Fill::Solid(color),
+            TaggedFill::LinearGradient(gradient) => Fill::LinearGradient(gradient),
+            TaggedFill::RadialGradient(gradient) => Fill::RadialGradient(gradient),
+            TaggedFill::Pattern(pattern) => Fill::Pattern(pattern),
+        };
+        fill.validate().map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fill {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(FillVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(offset: f64, color: Color) -> GradientStop {
+        GradientStop { offset, color }
+    }
+
+    fn round_trip(fill: &Fill) -> Fill {
+        serde_json::from_str(&serde_json::to_string(fill).unwrap()).unwrap()
+    }
+
+    fn rejection(json: &str) -> String {
+        serde_json::from_str::<Fill>(json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn a_plain_color_string_loads_as_a_solid_fill() {
+        let fill: Fill = serde_json::from_str("\"#336699\"").unwrap();
+        assert_eq!(fill, Fill::Solid(Color::rgb(51, 102, 153)));
+        assert_eq!(serde_json::to_string(&fill).unwrap(), "\"#336699\"");
+        let tagged: Fill = serde_json::from_str(r#"{"type": "solid", "color": "red"}"#).unwrap();
+        assert_eq!(tagged, Fill::Solid(Color::rgb(255, 0, 0)));
+        assert!(rejection("\"not a color\"").contains("invalid color"));
+    }
+
+    #[test]
+    fn tagged_fills_round_trip() {
+        let linear = Fill::LinearGradient(LinearGradient {
+            angle: 1.25,
+            stops: vec![stop(0.0, Color::rgb(255, 0, 0)), stop(1.0, Color::rgba(0, 0, 255, 128))],
+        });
+        let radial = Fill::RadialGradient(RadialGradient {
+            center: Point::new(0.25, 0.75),
+            radius: 0.4,
+            stops: vec![stop(0.0, Color::BLACK), stop(0.5, Color::BLACK), stop(1.0, Color::rgb(255, 255, 255))],
+        });
+        let mut pattern = Pattern::new(PatternKind::CrossHatch, Color::rgb(0, 128, 0));
+        pattern.background = Some(Color::rgb(255, 255, 0));
+        pattern.spacing = 12.0;
+        let pattern = Fill::Pattern(pattern);
+        for fill in [linear, radial, pattern] {
+            assert_eq!(round_trip(&fill), fill);
+        }
+        let json = serde_json::to_value(Fill::Pattern(Pattern::new(PatternKind::Dots, Color::BLACK))).unwrap();
+        assert_eq!(json["type"], "pattern");
+        assert_eq!(json["kind"], "dots");
+    }
+
+    #[test]
+    fn omitted_fields_take_their_defaults() {
+        let stops = r#"[{"offset": 0, "color": "black"}, {"offset": 1, "color": "white"}]"#;
+        let json = format!(r#"{{"type": "radial_gradient", "stops": {}}}"#, stops);
+        let fill: Fill = serde_json::from_str(&json).unwrap();
+        let Fill::RadialGradient(gradient) = fill else { panic!("expected a radial gradient") };
+        assert_eq!(gradient.center, Point::new(0.5, 0.5));
+        assert_eq!(gradient.radius, 0.5);
+        let fill: Fill = serde_json::from_str(r#"{"type": "pattern", "kind": "hatch", "color": "navy"}"#).unwrap();
+        assert_eq!(fill, Fill::Pattern(Pattern::new(PatternKind::Hatch, Color::rgb(0, 0, 128))));
+    }
+
+    #[test]
+    fn bad_gradient_stops_are_rejected() {
+        let single = r#"{"type": "linear_gradient", "stops": [{"offset": 0, "color": "red"}]}"#;
+        assert!(rejection(single).contains("a gradient needs at least two stops"));
+        let out_of_range = r#"{"type": "linear_gradient", "stops": [
+            {"offset": 0, "color": "red"}, {"offset": 1.5, "color": "blue"}
+        ]}"#;
+        assert!(rejection(out_of_range).contains("offsets must be between 0 and 1"));
+        let unordered = r#"{"type": "radial_gradient", "stops": [
+            {"offset": 0.8, "color": "red"}, {"offset": 0.2, "color": "blue"}
+        ]}"#;
+        assert!(rejection(unordered).contains("increasing offset order"));
+    }
+
+    #[test]
+    fn bad_shape_parameters_are_rejected() {
+        let stops = r#"[{"offset": 0, "color": "red"}, {"offset": 1, "color": "blue"}]"#;
+        let radius = format!(r#"{{"type": "radial_gradient", "radius": 0, "stops": {}}}"#, stops);
+        assert!(rejection(&radius).contains("gradient radius must be positive"));
+        let spacing = r#"{"type": "pattern", "kind": "dots", "color": "red", "spacing": -4}"#;
+        assert!(rejection(spacing).contains("pattern spacing must be positive"));
+        let width = r#"{"type": "pattern", "kind": "hatch", "color": "red", "line_width": 0}"#;
+        assert!(rejection(width).contains("pattern line width must be positive"));
+        assert!(rejection(r#"{"type": "mesh"}"#).contains("unknown variant"));
+    }
+}
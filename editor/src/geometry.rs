@@ -10,4 +10,649 @@ impl Point {
     pub fn new(x: f64, y: f64) -> Self {
         Self { x, y }
     }
-}
\ No newline at end of file
+
+    pub fn rotate_around(&self, center: Point, angle: f64) -> Point {
+        if angle == 0.0 {
+            return *self;
+        }
+        let (sin, cos) = angle.sin_cos();
+        let dx = self.x - center.x;
+        let dy = self.y - center.y;
+        Point::new(center.x + dx * cos - dy * sin, center.y + dx * sin + dy * cos)
+    }
+
+    pub fn distance(&self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
+    pub fn closest_on_segment(&self, start: Point, end: Point) -> Point {
+        let dx = end.x - start.x;
+        let dy = end.y - start.y;
+        let length_squared = dx * dx + dy * dy;
+        if length_squared == 0.0 {
+            return start;
+        }
+        let t = (((self.x - start.x) * dx + (self.y - start.y) * dy) / length_squared).clamp(0.0, 1.0);
+        Point::new(start.x + t * dx, start.y + t * dy)
+    }
+
+    pub fn distance_to_segment(&self, start: Point, end: Point) -> f64 {
+        self.distance(self.closest_on_segment(start, end))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_points<I: IntoIterator<Item = Point>>(points: I) -> Option<Self> {
+        let mut iter = points.into_iter();
+        let first = iter.next()?;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (first.x, first.y, first.x, first.y);
+        for point in iter {
+            min_x = min_x.min(point.x);
+            min_y = min_y.min(point.y);
+            max_x = max_x.max(point.x);
+            max_y = max_y.max(point.y);
+        }
+        Some(Self::new(min_x, min_y, max_x - min_x, max_y - min_y))
+    }
+
+    pub fn normalized(&self) -> Rect {
+        Rect::new(
+            self.x.min(self.x + self.width),
+            self.y.min(self.y + self.height),
+            self.width.abs(),
+            self.height.abs(),
+        )
+    }
+
+    pub fn left(&self) -> f64 {
+        self.x
+    }
+
+    pub fn top(&self) -> f64 {
+        self.y
+    }
+
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+
+    pub fn expand(&self, amount: f64) -> Rect {
+        Rect::new(
+            self.x - amount,
+            self.y - amount,
+            self.width + amount * 2.0,
+            self.height + amount * 2.0,
+        )
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        point.x >= self.left()
+            && point.x <= self.right()
+            && point.y >= self.top()
+            && point.y <= self.bottom()
+    }
+
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.left() >= self.left()
+            && other.right() <= self.right()
+            && other.top() >= self.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.left() <= other.right()
+            && other.left() <= self.right()
+            && self.top() <= other.bottom()
+            && other.top() <= self.bottom()
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        let (a, b) = (self.normalized(), other.normalized());
+        let left = a.left().min(b.left());
+        let top = a.top().min(b.top());
+        Rect::new(left, top, a.right().max(b.right()) - left, a.bottom().max(b.bottom()) - top)
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn corners(&self) -> [Point; 4] {
+        [
+            Point::new(self.x, self.y),
+            Point::new(self.x + self.width, self.y),
+            Point::new(self.x + self.width, self.y + self.height),
+            Point::new(self.x, self.y + self.height),
+        ]
+    }
+
+    pub fn rotated_bounds(&self, angle: f64) -> Rect {
+        if angle == 0.0 {
+            return self.normalized();
+        }
+        let center = self.center();
+        Rect::from_points(self.corners().iter().map(|p| p.rotate_around(center, angle)))
+            .unwrap_or(*self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vector {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vector {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn between(from: Point, to: Point) -> Self {
+        Self::new(to.x - from.x, to.y - from.y)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn normalized(&self) -> Vector {
+        let length = self.length();
+        if length == 0.0 {
+            return *self;
+        }
+        Vector::new(self.x / length, self.y / length)
+    }
+
+    pub fn scale(&self, factor: f64) -> Vector {
+        Vector::new(self.x * factor, self.y * factor)
+    }
+
+    pub fn dot(&self, other: Vector) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn cross(&self, other: Vector) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn perpendicular(&self) -> Vector {
+        Vector::new(-self.y, self.x)
+    }
+}
+
+impl std::ops::Add<Vector> for Point {
+    type Output = Point;
+
+    fn add(self, vector: Vector) -> Point {
+        Point::new(self.x + vector.x, self.y + vector.y)
+    }
+}
+
+impl std::ops::Sub for Point {
+    type Output = Vector;
+
+    fn sub(self, other: Point) -> Vector {
+        Vector::between(other, self)
+    }
+}
+
+impl std::ops::Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl std::ops::Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl std::ops::Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y)
+    }
+}
+
+// | a c e |
+// | b d f |
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Affine {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Affine {
+    pub const IDENTITY: Affine = Affine {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn translate(x: f64, y: f64) -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    pub fn scale(x: f64, y: f64) -> Self {
+        Self::new(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    pub fn rotate(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    // shears by the tangents of the angles; `x` slants vertical lines, `y` slants horizontal ones
+    pub fn skew(x: f64, y: f64) -> Self {
+        Self::new(1.0, y.tan(), x.tan(), 1.0, 0.0, 0.0)
+    }
+
+    // the same map with `origin` as its fixed point
+    pub fn around(&self, origin: Point) -> Self {
+        Self::translate(-origin.x, -origin.y)
+            .then(self)
+            .then(&Self::translate(origin.x, origin.y))
+    }
+
+    pub fn rotate_around(center: Point, angle: f64) -> Self {
+        Self::translate(-center.x, -center.y)
+            .then(&Self::rotate(angle))
+            .then(&Self::translate(center.x, center.y))
+    }
+
+    // applies `self` first, then `next`
+    pub fn then(&self, next: &Affine) -> Affine {
+        Affine::new(
+            next.a * self.a + next.c * self.b,
+            next.b * self.a + next.d * self.b,
+            next.a * self.c + next.c * self.d,
+            next.b * self.c + next.d * self.d,
+            next.a * self.e + next.c * self.f + next.e,
+            next.b * self.e + next.d * self.f + next.f,
+        )
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    pub fn inverse(&self) -> Option<Affine> {
+        let det = self.determinant();
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+        Some(Affine::new(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.c * self.f - self.d * self.e) / det,
+            (self.b * self.e - self.a * self.f) / det,
+        ))
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        Point::new(
+            self.a * point.x + self.c * point.y + self.e,
+            self.b * point.x + self.d * point.y + self.f,
+        )
+    }
+
+    pub fn apply_vector(&self, vector: Vector) -> Vector {
+        Vector::new(
+            self.a * vector.x + self.c * vector.y,
+            self.b * vector.x + self.d * vector.y,
+        )
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+}
+
+impl Default for Affine {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+fn orientation(a: Point, b: Point, c: Point) -> f64 {
+    (b - a).cross(c - a)
+}
+
+fn on_segment(point: Point, start: Point, end: Point) -> bool {
+    point.x >= start.x.min(end.x)
+        && point.x <= start.x.max(end.x)
+        && point.y >= start.y.min(end.y)
+        && point.y <= start.y.max(end.y)
+}
+
+pub fn segments_intersect(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    let d1 = orientation(b1, b2, a1);
+    let d2 = orientation(b1, b2, a2);
+    let d3 = orientation(a1, a2, b1);
+    let d4 = orientation(a1, a2, b2);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    (d1 == 0.0 && on_segment(a1, b1, b2))
+        || (d2 == 0.0 && on_segment(a2, b1, b2))
+        || (d3 == 0.0 && on_segment(b1, a1, a2))
+        || (d4 == 0.0 && on_segment(b2, a1, a2))
+}
+
+pub fn segment_intersection(a1: Point, a2: Point, b1: Point, b2: Point) -> Option<Point> {
+    let r = a2 - a1;
+    let s = b2 - b1;
+    let denominator = r.cross(s);
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+    let t = (b1 - a1).cross(s) / denominator;
+    let u = (b1 - a1).cross(r) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then(|| a1 + r.scale(t))
+}
+
+pub fn segment_intersects_rect(start: Point, end: Point, rect: &Rect) -> bool {
+    if rect.contains_point(start) || rect.contains_point(end) {
+        return true;
+    }
+    let corners = rect.corners();
+    (0..4).any(|i| segments_intersect(start, end, corners[i], corners[(i + 1) % 4]))
+}
+
+pub fn point_in_polygon(point: Point, polygon: &[Point]) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+pub fn polygon_edges(polygon: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    (0..polygon.len()).map(move |i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+}
+
+pub fn distance_to_polygon_edge(point: Point, polygon: &[Point]) -> f64 {
+    polygon_edges(polygon)
+        .map(|(start, end)| point.distance_to_segment(start, end))
+        .fold(f64::INFINITY, f64::min)
+}
+
+pub fn closest_on_polygon_edge(point: Point, polygon: &[Point]) -> Option<Point> {
+    polygon_edges(polygon)
+        .map(|(start, end)| point.closest_on_segment(start, end))
+        .min_by(|a, b| point.distance(*a).total_cmp(&point.distance(*b)))
+}
+
+pub fn ellipse_polygon(center: Point, radius_x: f64, radius_y: f64, steps: usize) -> Vec<Point> {
+    (0..steps)
+        .map(|step| {
+            let angle = std::f64::consts::TAU * step as f64 / steps as f64;
+            Point::new(center.x + radius_x * angle.cos(), center.y + radius_y * angle.sin())
+        })
+        .collect()
+}
+
+pub fn polygons_intersect(first: &[Point], second: &[Point]) -> bool {
+    if first.is_empty() || second.is_empty() {
+        return false;
+    }
+    if point_in_polygon(first[0], second) || point_in_polygon(second[0], first) {
+        return true;
+    }
+    polygon_edges(first).any(|(a1, a2)| {
+        polygon_edges(second).any(|(b1, b2)| segments_intersect(a1, a2, b1, b2))
+    })
+}
+
+pub fn polygon_intersects_rect(polygon: &[Point], rect: &Rect) -> bool {
+    polygons_intersect(polygon, &rect.corners())
+}
+
+pub fn point_in_ellipse(point: Point, center: Point, radius_x: f64, radius_y: f64) -> bool {
+    if radius_x <= 0.0 || radius_y <= 0.0 {
+        return false;
+    }
+    ((point.x - center.x) / radius_x).powi(2) + ((point.y - center.y) / radius_y).powi(2) <= 1.0
+}
+
+// scales space so the axis-aligned ellipse becomes the unit circle
+fn to_unit_circle(point: Point, center: Point, radius_x: f64, radius_y: f64) -> Point {
+    Point::new((point.x - center.x) / radius_x, (point.y - center.y) / radius_y)
+}
+
+pub fn segment_intersects_ellipse(
+    start: Point,
+    end: Point,
+    center: Point,
+    radius_x: f64,
+    radius_y: f64,
+) -> bool {
+    if radius_x <= 0.0 || radius_y <= 0.0 {
+        return false;
+    }
+    let origin = Point::new(0.0, 0.0);
+    let start = to_unit_circle(start, center, radius_x, radius_y);
+    let end = to_unit_circle(end, center, radius_x, radius_y);
+    origin.distance_to_segment(start, end) <= 1.0
+}
+
+pub fn ellipse_intersects_polygon(
+    center: Point,
+    radius_x: f64,
+    radius_y: f64,
+    polygon: &[Point],
+) -> bool {
+    if polygon.is_empty() || radius_x <= 0.0 || radius_y <= 0.0 {
+        return false;
+    }
+    point_in_polygon(center, polygon)
+        || polygon_edges(polygon)
+            .any(|(start, end)| segment_intersects_ellipse(start, end, center, radius_x, radius_y))
+}
+
+pub fn distance_to_rounded_rect(point: Point, rect: &Rect, radius: f64) -> f64 {
+    let rect = rect.normalized();
+    let center = rect.center();
+    let radius = radius.clamp(0.0, rect.width.min(rect.height) / 2.0);
+    let qx = (point.x - center.x).abs() - (rect.width / 2.0 - radius);
+    let qy = (point.y - center.y).abs() - (rect.height / 2.0 - radius);
+    let outside = qx.max(0.0).hypot(qy.max(0.0));
+    outside + qx.max(qy).min(0.0) - radius
+}
+
+pub fn rounded_rect_polygon(rect: &Rect, radius: f64) -> Vec<Point> {
+    let rect = rect.normalized();
+    let radius = radius.clamp(0.0, rect.width.min(rect.height) / 2.0);
+    if radius == 0.0 {
+        return rect.corners().to_vec();
+    }
+    const ARC_STEPS: usize = 8;
+    let arcs = [
+        (Point::new(rect.right() - radius, rect.top() + radius), -std::f64::consts::FRAC_PI_2),
+        (Point::new(rect.right() - radius, rect.bottom() - radius), 0.0),
+        (Point::new(rect.left() + radius, rect.bottom() - radius), std::f64::consts::FRAC_PI_2),
+        (Point::new(rect.left() + radius, rect.top() + radius), std::f64::consts::PI),
+    ];
+    arcs.iter()
+        .flat_map(|(center, start_angle)| {
+            (0..=ARC_STEPS).map(move |step| {
+                let angle = start_angle + std::f64::consts::FRAC_PI_2 * step as f64 / ARC_STEPS as f64;
+                Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+            })
+        })
+        .collect()
+}
+
+// vertices on the ellipse inscribed in `frame`, starting at the top; an inner ratio
+// adds a vertex between each pair pulled toward the center, which makes a star
+pub fn regular_polygon_points(frame: &Rect, sides: u32, inner_ratio: Option<f64>) -> Vec<Point> {
+    let frame = frame.normalized();
+    let center = frame.center();
+    let (radius_x, radius_y) = (frame.width / 2.0, frame.height / 2.0);
+    let count = match inner_ratio {
+        Some(_) => sides as usize * 2,
+        None => sides as usize,
+    };
+    (0..count)
+        .map(|index| {
+            let angle = -std::f64::consts::FRAC_PI_2 + std::f64::consts::TAU * index as f64 / count as f64;
+            let scale = match inner_ratio {
+                Some(ratio) if index % 2 == 1 => ratio,
+                _ => 1.0,
+            };
+            Point::new(
+                center.x + radius_x * scale * angle.cos(),
+                center.y + radius_y * scale * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+// replaces every corner with a curve that starts `radius` along each adjacent edge
+pub fn rounded_polygon(vertices: &[Point], radius: f64) -> Vec<Point> {
+    if radius <= 0.0 || vertices.len() < 3 {
+        return vertices.to_vec();
+    }
+    const CORNER_STEPS: usize = 6;
+    let count = vertices.len();
+    let mut result = Vec::with_capacity(count * (CORNER_STEPS + 1));
+    for index in 0..count {
+        let vertex = vertices[index];
+        let previous = vertices[(index + count - 1) % count];
+        let next = vertices[(index + 1) % count];
+        let cut = radius
+            .min(vertex.distance(previous) / 2.0)
+            .min(vertex.distance(next) / 2.0);
+        let entry = vertex + (previous - vertex).normalized().scale(cut);
+        let exit = vertex + (next - vertex).normalized().scale(cut);
+        result.extend((0..=CORNER_STEPS).map(|step| {
+            bezier_point(entry, &[vertex], exit, step as f64 / CORNER_STEPS as f64)
+        }));
+    }
+    result
+}
+
+pub const MAX_BEZIER_CONTROL_POINTS: usize = 2;
+
+// quadratic for one control point, cubic for two, a straight segment otherwise
+pub fn bezier_point(start: Point, controls: &[Point], end: Point, t: f64) -> Point {
+    let u = 1.0 - t;
+    match controls {
+        [control] => Point::new(
+            u * u * start.x + 2.0 * u * t * control.x + t * t * end.x,
+            u * u * start.y + 2.0 * u * t * control.y + t * t * end.y,
+        ),
+        [first, second, ..] => Point::new(
+            u * u * u * start.x + 3.0 * u * u * t * first.x + 3.0 * u * t * t * second.x + t * t * t * end.x,
+            u * u * u * start.y + 3.0 * u * u * t * first.y + 3.0 * u * t * t * second.y + t * t * t * end.y,
+        ),
+        [] => Point::new(start.x + (end.x - start.x) * t, start.y + (end.y - start.y) * t),
+    }
+}
+
+pub fn flatten_bezier(start: Point, controls: &[Point], end: Point, steps: usize) -> Vec<Point> {
+    if controls.is_empty() {
+        return vec![start, end];
+    }
+    let steps = steps.max(1);
+    (0..=steps)
+        .map(|step| bezier_point(start, controls, end, step as f64 / steps as f64))
+        .collect()
+}
+
+// parameters in (0, 1) where one coordinate of the curve reaches an extremum
+fn bezier_extrema(start: f64, controls: &[f64], end: f64) -> Vec<f64> {
+    let roots = match controls {
+        [control] => {
+            let denominator = start - 2.0 * control + end;
+            if denominator.abs() < f64::EPSILON {
+                Vec::new()
+            } else {
+                vec![(start - control) / denominator]
+            }
+        }
+        [first, second, ..] => {
+            let a = -start + 3.0 * first - 3.0 * second + end;
+            let b = 2.0 * (start - 2.0 * first + second);
+            let c = first - start;
+            if a.abs() < f64::EPSILON {
+                if b.abs() < f64::EPSILON {
+                    Vec::new()
+                } else {
+                    vec![-c / b]
+                }
+            } else {
+                let discriminant = b * b - 4.0 * a * c;
+                if discriminant < 0.0 {
+                    Vec::new()
+                } else {
+                    let root = discriminant.sqrt();
+                    vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+                }
+            }
+        }
+        [] => Vec::new(),
+    };
+    roots.into_iter().filter(|t| *t > 0.0 && *t < 1.0).collect()
+}
+
+pub fn bezier_bounds(start: Point, controls: &[Point], end: Point) -> Rect {
+    let xs: Vec<f64> = controls.iter().map(|point| point.x).collect();
+    let ys: Vec<f64> = controls.iter().map(|point| point.y).collect();
+    let extrema = bezier_extrema(start.x, &xs, end.x)
+        .into_iter()
+        .chain(bezier_extrema(start.y, &ys, end.y));
+    let points = [start, end]
+        .into_iter()
+        .chain(extrema.map(|t| bezier_point(start, controls, end, t)));
+    Rect::from_points(points).unwrap_or(Rect::new(start.x, start.y, 0.0, 0.0))
+}
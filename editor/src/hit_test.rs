@@ -0,0 +1,203 @@
+use crate::elements::Element;
+use crate::geometry::{
+    distance_to_polygon_edge, distance_to_rounded_rect, ellipse_intersects_polygon, ellipse_polygon,
+    point_in_ellipse, point_in_polygon, polygon_intersects_rect, regular_polygon_points,
+    rounded_polygon, rounded_rect_polygon, segment_intersects_rect, Affine, Point, Rect,
+};
+
+const ELLIPSE_OUTLINE_STEPS: usize = 64;
+
+impl Element {
+    // the untransformed box and the rotation applied about its center
+    pub fn local_frame(&self) -> Option<(Rect, f64)> {
+        match self {
+            Element::Rectangle(rect) => Some((
+                Rect::new(rect.position.x, rect.position.y, rect.width, rect.height).normalized(),
+                rect.rotation_angle,
+            )),
+            Element::Diamond(diamond) => Some((
+                Rect::new(diamond.position.x, diamond.position.y, diamond.width, diamond.height)
+                    .normalized(),
+                diamond.rotation_angle,
+            )),
+            Element::Image(image) => Some((
+                Rect::new(image.position.x, image.position.y, image.width, image.height).normalized(),
+                image.rotation_angle,
+            )),
+            Element::Text(text) => Some((
+                Rect::new(text.position.x, text.position.y, text.width, text.height).normalized(),
+                text.rotation_angle,
+            )),
+            Element::RegularPolygon(polygon) => Some((
+                Rect::new(polygon.position.x, polygon.position.y, polygon.width, polygon.height)
+                    .normalized(),
+                polygon.rotation_angle,
+            )),
+            Element::Star(star) => Some((
+                Rect::new(star.position.x, star.position.y, star.width, star.height).normalized(),
+                star.rotation_angle,
+            )),
+            Element::Polygon(polygon) => Rect::from_points(polygon.points.iter().copied())
+                .map(|bounds| (bounds, polygon.rotation_angle)),
+            Element::Frame(frame) => Some((frame.rect(), 0.0)),
+            Element::Ellipse(ellipse) => Some((
+                Rect::new(
+                    ellipse.position.x - ellipse.radius_x.abs(),
+                    ellipse.position.y - ellipse.radius_y.abs(),
+                    ellipse.radius_x.abs() * 2.0,
+                    ellipse.radius_y.abs() * 2.0,
+                ),
+                ellipse.rotation_angle,
+            )),
+            Element::Path(path) => {
+                Rect::from_points(path.points.iter().copied()).map(|bounds| (bounds, path.rotation_angle))
+            }
+            Element::Line(_) | Element::Arrow(_) | Element::Group(_) | Element::Layer(_) => None,
+        }
+    }
+
+    // flips, skew and rotation, all about the center of the local frame
+    pub fn transform(&self) -> Affine {
+        let Some((frame, angle)) = self.local_frame() else {
+            return Affine::IDENTITY;
+        };
+        let skew = self.skew_transform();
+        match (skew.is_identity(), angle == 0.0) {
+            (true, true) => Affine::IDENTITY,
+            (true, false) => Affine::rotate_around(frame.center(), angle),
+            _ => skew.then(&Affine::rotate(angle)).around(frame.center()),
+        }
+    }
+
+    // closed outline in world space, for shapes that have an interior
+    pub fn outline(&self) -> Option<Vec<Point>> {
+        let (frame, _) = self.local_frame()?;
+        let local = match self {
+            Element::Rectangle(rect) => rounded_rect_polygon(&frame, rect.border_radius),
+            Element::Image(_) | Element::Text(_) | Element::Frame(_) => frame.corners().to_vec(),
+            Element::Ellipse(ellipse) => ellipse_polygon(
+                ellipse.position,
+                ellipse.radius_x.abs(),
+                ellipse.radius_y.abs(),
+                ELLIPSE_OUTLINE_STEPS,
+            ),
+            Element::Diamond(_) => {
+                let center = frame.center();
+                vec![
+                    Point::new(center.x, frame.top()),
+                    Point::new(frame.right(), center.y),
+                    Point::new(center.x, frame.bottom()),
+                    Point::new(frame.left(), center.y),
+                ]
+            }
+            Element::Polygon(polygon) => rounded_polygon(&polygon.points, polygon.border_radius),
+            Element::RegularPolygon(polygon) => rounded_polygon(
+                &regular_polygon_points(&frame, polygon.sides, None),
+                polygon.border_radius,
+            ),
+            Element::Star(star) => rounded_polygon(
+                &regular_polygon_points(&frame, star.sides, Some(star.inner_radius)),
+                star.border_radius,
+            ),
+            _ => return None,
+        };
+        let transform = self.transform();
+        Some(local.into_iter().map(|point| transform.apply(point)).collect())
+    }
+
+    fn world_points(&self) -> Vec<Point> {
+        match self {
+            Element::Line(_) | Element::Arrow(_) => {
+                let transform = self.connector_transform();
+                self.connector_points().into_iter().map(|point| transform.apply(point)).collect()
+            }
+            Element::Path(path) => {
+                let transform = self.transform();
+                path.points.iter().map(|point| transform.apply(*point)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn contains_point(&self, point: Point, tolerance: f64) -> bool {
+        let stroke = self.line_width().unwrap_or(0.0) / 2.0 + tolerance;
+        let local = || match self.transform().inverse() {
+            Some(inverse) => inverse.apply(point),
+            None => point,
+        };
+        match self {
+            Element::Rectangle(rect) => {
+                let (frame, _) = self.local_frame().unwrap();
+                distance_to_rounded_rect(local(), &frame, rect.border_radius) <= stroke
+            }
+            Element::Image(_) | Element::Text(_) => {
+                let (frame, _) = self.local_frame().unwrap();
+                frame.expand(tolerance).contains_point(local())
+            }
+            Element::Frame(frame) => frame.rect().expand(stroke).contains_point(point),
+            Element::Diamond(_) | Element::Polygon(_) | Element::RegularPolygon(_) | Element::Star(_) => {
+                let outline = self.outline().unwrap_or_default();
+                point_in_polygon(point, &outline) || distance_to_polygon_edge(point, &outline) <= stroke
+            }
+            Element::Ellipse(ellipse) => point_in_ellipse(
+                local(),
+                ellipse.position,
+                ellipse.radius_x.abs() + stroke,
+                ellipse.radius_y.abs() + stroke,
+            ),
+            Element::Path(path) if path.has_pressure() => {
+                let outline = path.outline();
+                let point = local();
+                point_in_polygon(point, &outline) || distance_to_polygon_edge(point, &outline) <= tolerance
+            }
+            Element::Line(_) | Element::Arrow(_) | Element::Path(_) => {
+                match self.world_points().as_slice() {
+                    [] => false,
+                    [single] => point.distance(*single) <= stroke,
+                    points => points
+                        .windows(2)
+                        .any(|segment| point.distance_to_segment(segment[0], segment[1]) <= stroke),
+                }
+            }
+            Element::Group(_) | Element::Layer(_) => false,
+        }
+    }
+
+    pub fn intersects_rect(&self, rect: &Rect) -> bool {
+        let rect = rect.normalized();
+        let stroke = self.line_width().unwrap_or(0.0) / 2.0;
+        match self {
+            Element::Rectangle(_)
+            | Element::Diamond(_)
+            | Element::Image(_)
+            | Element::Text(_)
+            | Element::Frame(_)
+            | Element::Polygon(_)
+            | Element::RegularPolygon(_)
+            | Element::Star(_) => self
+                .outline()
+                .is_some_and(|outline| polygon_intersects_rect(&outline, &rect.expand(stroke))),
+            Element::Ellipse(ellipse) => {
+                let inverse = self.transform().inverse().unwrap_or_default();
+                let local: Vec<Point> = rect.corners().iter().map(|corner| inverse.apply(*corner)).collect();
+                ellipse_intersects_polygon(
+                    ellipse.position,
+                    ellipse.radius_x.abs() + stroke,
+                    ellipse.radius_y.abs() + stroke,
+                    &local,
+                )
+            }
+            Element::Line(_) | Element::Arrow(_) | Element::Path(_) => {
+                let area = rect.expand(stroke);
+                match self.world_points().as_slice() {
+                    [] => false,
+                    [single] => area.contains_point(*single),
+                    points => points
+                        .windows(2)
+                        .any(|segment| segment_intersects_rect(segment[0], segment[1], &area)),
+                }
+            }
+            Element::Group(_) | Element::Layer(_) => false,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::elements::Element;
+use crate::geometry::{Point, Rect};
+use std::f64::consts::FRAC_1_SQRT_2;
+
+// space kept between a rectangle's outline and its label
+const LABEL_PADDING: f64 = 8.0;
+
+impl Element {
+    pub fn can_have_label(&self) -> bool {
+        matches!(
+            self,
+            Element::Rectangle(_) | Element::Ellipse(_) | Element::Diamond(_) | Element::Line(_) | Element::Arrow(_)
+        )
+    }
+
+    // the unrotated box a label wraps inside, centered on the shape, and the rotation about its
+    // center; ellipses and diamonds use the largest box that fits inside their outline
+    pub fn label_bounds(&self) -> Option<(Rect, f64)> {
+        let (frame, angle) = self.local_frame()?;
+        let (width, height) = match self {
+            Element::Rectangle(_) => (frame.width - LABEL_PADDING * 2.0, frame.height - LABEL_PADDING * 2.0),
+            Element::Ellipse(_) => (frame.width * FRAC_1_SQRT_2, frame.height * FRAC_1_SQRT_2),
+            Element::Diamond(_) => (frame.width / 2.0, frame.height / 2.0),
+            _ => return None,
+        };
+        let (width, height) = (width.max(0.0), height.max(0.0));
+        let center = self.anchor_center()?;
+        Some((Rect::new(center.x - width / 2.0, center.y - height / 2.0, width, height), angle))
+    }
+
+    // halfway along the drawn path of a connector, where its label sits
+    pub fn label_anchor(&self) -> Option<Point> {
+        let points = self.connector_points();
+        let total: f64 = points.windows(2).map(|pair| pair[0].distance(pair[1])).sum();
+        let mut remaining = total / 2.0;
+        for pair in points.windows(2) {
+            let length = pair[0].distance(pair[1]);
+            if remaining <= length && length > 0.0 {
+                let t = remaining / length;
+                return Some(Point::new(
+                    pair[0].x + (pair[1].x - pair[0].x) * t,
+                    pair[0].y + (pair[1].y - pair[0].y) * t,
+                ));
+            }
+            remaining -= length;
+        }
+        points.first().copied()
+    }
+}
@@ -1,7 +1,35 @@
 pub mod geometry;
 pub mod elements;
+pub mod hit_test;
+pub mod connector;
+pub mod routing;
+pub mod svg;
 pub mod document;
+pub mod spatial;
+pub mod stroke;
+pub mod align;
+pub mod snap;
+pub mod style;
+pub mod fill;
+pub mod rough;
+pub mod rich_text;
+pub mod text_layout;
+pub mod label;
 
-pub use geometry::Point;
-pub use elements::{Ellipse, Rectangle, Line, Arrow, Diamond, Path};
+pub use geometry::{Affine, Point, Rect, Vector};
+pub use elements::{
+    Ellipse, Rectangle, Line, Arrow, Diamond, Path, Polygon, RegularPolygon, Star, Frame, Layer, Element,
+    ElementKind, ElementPatch, BlendMode,
+};
+pub use align::{Alignment, Axis, Spacing};
+pub use connector::{Anchor, Binding, ConnectorEnd, Marker, Side};
 pub use document::Document;
+pub use fill::{Fill, GradientStop, LinearGradient, Pattern, PatternKind, RadialGradient};
+pub use rich_text::{ListKind, ParagraphStyle, TextRun, TextStyle, TextStylePatch};
+pub use rough::Sketch;
+pub use routing::Routing;
+pub use snap::{Guide, GuideKind, Snap, SnapHandle, SnapOptions};
+pub use spatial::{SelectionMode, SpatialIndex};
+pub use stroke::{PathOptions, Smoothing};
+pub use style::{Color, DashPattern, FontWeight, StyleError, TextAlign};
+pub use text_layout::{FallbackMetrics, Font, FontMetrics, LayoutFragment, LayoutLine, MeasuredMetrics, TextLayout, TextSizing, VerticalAlign};
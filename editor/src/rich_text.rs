@@ -0,0 +1,472 @@
+use crate::elements::Text;
+use crate::style::{lenient_option, Color};
+use serde::{Deserialize, Deserializer, Serialize};
+
+// Offsets into text are counted in characters (Unicode scalar values), not bytes. A Text with
+// no runs is plain: all of `content` in the element's own style. Once styled, the runs cover
+// `content` exactly, in order.
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    // None falls back to the element's color and font size
+    #[serde(deserialize_with = "lenient_option")]
+    pub color: Option<Color>,
+    pub font_size: Option<f64>,
+    pub link: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextRun {
+    pub text: String,
+    #[serde(flatten)]
+    pub style: TextStyle,
+}
+
+// unset fields are left alone; an explicit null for color, font_size or link clears them
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextStylePatch {
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub strikethrough: Option<bool>,
+    #[serde(deserialize_with = "present")]
+    pub color: Option<Option<Color>>,
+    #[serde(deserialize_with = "present")]
+    pub font_size: Option<Option<f64>>,
+    #[serde(deserialize_with = "present")]
+    pub link: Option<Option<String>>,
+}
+
+// only called for fields that appear, so a null still counts as set
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListKind {
+    #[default]
+    None,
+    Bullet,
+    Numbered,
+}
+
+// one per line of `content`; lines past the end of the list are plain paragraphs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParagraphStyle {
+    pub list: ListKind,
+}
+
+const MIN_FONT_SIZE: f64 = 1.0;
+const LINK_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+// links end up in exported files, so anything that could run script there is refused
+pub fn is_safe_link(link: &str) -> bool {
+    let link = link.trim_start().to_ascii_lowercase();
+    LINK_SCHEMES
+        .iter()
+        .any(|scheme| link.starts_with(scheme) && link.len() > scheme.len())
+}
+
+impl TextStyle {
+    fn apply(&mut self, patch: &TextStylePatch) {
+        if let Some(bold) = patch.bold {
+            self.bold = bold;
+        }
+        if let Some(italic) = patch.italic {
+            self.italic = italic;
+        }
+        if let Some(underline) = patch.underline {
+            self.underline = underline;
+        }
+        if let Some(strikethrough) = patch.strikethrough {
+            self.strikethrough = strikethrough;
+        }
+        if let Some(color) = patch.color {
+            self.color = color;
+        }
+        if let Some(font_size) = patch.font_size {
+            self.font_size = font_size.map(|size| size.max(MIN_FONT_SIZE));
+        }
+        if let Some(link) = &patch.link {
+            self.link = link.clone().filter(|link| is_safe_link(link));
+        }
+    }
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn byte_offset(text: &str, offset: usize) -> usize {
+    text.char_indices().nth(offset).map_or(text.len(), |(index, _)| index)
+}
+
+impl Text {
+    pub fn char_len(&self) -> usize {
+        char_len(&self.content)
+    }
+
+    // the styled runs, with plain text reported as a single unstyled run
+    pub fn runs(&self) -> Vec<TextRun> {
+        if !self.runs.is_empty() || self.content.is_empty() {
+            return self.runs.clone();
+        }
+        vec![TextRun {
+            text: self.content.clone(),
+            style: TextStyle::default(),
+        }]
+    }
+
+    pub fn paragraph(&self, line: usize) -> ParagraphStyle {
+        self.paragraphs.get(line).copied().unwrap_or_default()
+    }
+
+    // the runs of each line with the newlines taken out
+    pub fn lines(&self) -> Vec<Vec<TextRun>> {
+        let mut lines = vec![Vec::new()];
+        for run in self.runs() {
+            for (index, piece) in run.text.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(Vec::new());
+                }
+                if !piece.is_empty() {
+                    lines.last_mut().unwrap().push(TextRun {
+                        text: piece.to_string(),
+                        style: run.style.clone(),
+                    });
+                }
+            }
+        }
+        lines
+    }
+
+    // "•" for bullets and a running number for numbered lists; numbering restarts after any
+    // line that is not numbered
+    pub fn list_marker(&self, line: usize) -> Option<String> {
+        match self.paragraph(line).list {
+            ListKind::None => None,
+            ListKind::Bullet => Some("•".to_string()),
+            ListKind::Numbered => {
+                let number = (0..=line)
+                    .rev()
+                    .take_while(|line| self.paragraph(*line).list == ListKind::Numbered)
+                    .count();
+                Some(format!("{}.", number))
+            }
+        }
+    }
+
+    pub fn style_at(&self, offset: usize) -> TextStyle {
+        let mut position = 0;
+        for run in &self.runs {
+            position += char_len(&run.text);
+            if offset < position {
+                return run.style.clone();
+            }
+        }
+        self.runs.last().map(|run| run.style.clone()).unwrap_or_default()
+    }
+
+    // typed text continues the style of the character before it unless one is given
+    pub fn insert_text(&mut self, offset: usize, text: &str, style: Option<&TextStyle>) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+        let offset = offset.min(self.char_len());
+        let style = style
+            .cloned()
+            .unwrap_or_else(|| self.style_at(offset.saturating_sub(1)));
+        let line = self.line_of(offset);
+        self.materialize_runs();
+        let index = self.split_runs(offset);
+        self.runs.insert(
+            index,
+            TextRun {
+                text: text.to_string(),
+                style,
+            },
+        );
+        self.content.insert_str(byte_offset(&self.content, offset), text);
+
+        // new lines carry on the list of the line they were typed in
+        let new_lines = text.matches('\n').count();
+        if new_lines > 0 && line < self.paragraphs.len() {
+            let paragraph = self.paragraphs[line];
+            self.paragraphs.splice(line + 1..line + 1, std::iter::repeat_n(paragraph, new_lines));
+        }
+        self.normalize();
+        true
+    }
+
+    pub fn delete_range(&mut self, start: usize, end: usize) -> bool {
+        let end = end.min(self.char_len());
+        if start >= end {
+            return false;
+        }
+        let (first_line, last_line) = (self.line_of(start), self.line_of(end));
+        self.materialize_runs();
+        let from = self.split_runs(start);
+        let to = self.split_runs(end);
+        self.runs.drain(from..to);
+        let (byte_start, byte_end) = (byte_offset(&self.content, start), byte_offset(&self.content, end));
+        self.content.replace_range(byte_start..byte_end, "");
+
+        // joined lines keep the style of the first one
+        if first_line + 1 < self.paragraphs.len() {
+            let last = (last_line + 1).min(self.paragraphs.len());
+            self.paragraphs.drain(first_line + 1..last);
+        }
+        self.normalize();
+        true
+    }
+
+    pub fn set_style_range(&mut self, start: usize, end: usize, patch: &TextStylePatch) -> bool {
+        let end = end.min(self.char_len());
+        if start >= end {
+            return false;
+        }
+        let before = self.runs.clone();
+        self.materialize_runs();
+        let from = self.split_runs(start);
+        let to = self.split_runs(end);
+        for run in &mut self.runs[from..to] {
+            run.style.apply(patch);
+        }
+        self.normalize();
+        self.runs != before
+    }
+
+    // every line touched by the range becomes part of the list
+    pub fn set_list_range(&mut self, start: usize, end: usize, list: ListKind) -> bool {
+        let (first_line, last_line) = (self.line_of(start), self.line_of(end.max(start)));
+        let before = self.paragraphs.clone();
+        if self.paragraphs.len() <= last_line {
+            self.paragraphs.resize(last_line + 1, ParagraphStyle::default());
+        }
+        for paragraph in &mut self.paragraphs[first_line..=last_line] {
+            paragraph.list = list;
+        }
+        self.normalize();
+        self.paragraphs != before
+    }
+
+    // a whole-content replacement drops the styling, which no longer lines up with the text
+    pub(crate) fn reset_rich_text(&mut self) {
+        self.runs.clear();
+        let lines = self.content.matches('\n').count() + 1;
+        self.paragraphs.truncate(lines);
+        self.normalize();
+    }
+
+    pub(crate) fn scale_font_sizes(&mut self, factor: f64) {
+        self.font_size = (self.font_size * factor).max(MIN_FONT_SIZE);
+        for run in &mut self.runs {
+            run.style.font_size = run.style.font_size.map(|size| (size * factor).max(MIN_FONT_SIZE));
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.content.chars().take(offset).filter(|c| *c == '\n').count()
+    }
+
+    fn materialize_runs(&mut self) {
+        if self.runs.is_empty() && !self.content.is_empty() {
+            self.runs = self.runs();
+        }
+    }
+
+    // makes a run start at `offset` and returns its index, or the run count at the very end
+    fn split_runs(&mut self, offset: usize) -> usize {
+        let mut position = 0;
+        for index in 0..self.runs.len() {
+            let length = char_len(&self.runs[index].text);
+            if offset == position {
+                return index;
+            }
+            if offset < position + length {
+                let split = byte_offset(&self.runs[index].text, offset - position);
+                let tail = self.runs[index].text.split_off(split);
+                let style = self.runs[index].style.clone();
+                self.runs.insert(index + 1, TextRun { text: tail, style });
+                return index + 1;
+            }
+            position += length;
+        }
+        self.runs.len()
+    }
+
+    // merges neighbours with the same style and goes back to plain text when nothing is styled
+    fn normalize(&mut self) {
+        let mut merged: Vec<TextRun> = Vec::with_capacity(self.runs.len());
+        for run in self.runs.drain(..).filter(|run| !run.text.is_empty()) {
+            match merged.last_mut() {
+                Some(last) if last.style == run.style => last.text.push_str(&run.text),
+                _ => merged.push(run),
+            }
+        }
+        if merged.iter().all(|run| run.style == TextStyle::default()) {
+            merged.clear();
+        }
+        self.runs = merged;
+        while self.paragraphs.last().is_some_and(|paragraph| *paragraph == ParagraphStyle::default()) {
+            self.paragraphs.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+
+    fn plain(content: &str) -> Text {
+        Text::new(1, Point::new(0.0, 0.0), 200.0, 40.0, content.to_string())
+    }
+
+    fn bold() -> TextStylePatch {
+        TextStylePatch {
+            bold: Some(true),
+            ..TextStylePatch::default()
+        }
+    }
+
+    // each run as its text and whether it is bold
+    fn spans(text: &Text) -> Vec<(String, bool)> {
+        text.runs.iter().map(|run| (run.text.clone(), run.style.bold)).collect()
+    }
+
+    fn lists(text: &Text) -> Vec<ListKind> {
+        text.paragraphs.iter().map(|paragraph| paragraph.list).collect()
+    }
+
+    #[test]
+    fn offsets_count_characters_not_bytes() {
+        let mut text = plain("héllo 世界");
+        assert_eq!(text.char_len(), 8);
+        assert!(text.set_style_range(6, 8, &bold()));
+        assert_eq!(spans(&text), vec![("héllo ".to_string(), false), ("世界".to_string(), true)]);
+        assert!(text.style_at(7).bold);
+        assert!(!text.style_at(1).bold);
+    }
+
+    #[test]
+    fn inserted_text_continues_the_style_before_it() {
+        let mut text = plain("héllo 世界");
+        text.set_style_range(6, 8, &bold());
+        assert!(text.insert_text(7, "🎉", None));
+        assert_eq!(text.content, "héllo 世🎉界");
+        assert_eq!(spans(&text), vec![("héllo ".to_string(), false), ("世🎉界".to_string(), true)]);
+
+        assert!(text.insert_text(1, "ü", Some(&TextStyle::default())));
+        assert_eq!(text.content, "hüéllo 世🎉界");
+        assert!(text.insert_text(100, "!", None));
+        assert_eq!(text.content, "hüéllo 世🎉界!");
+        assert_eq!(spans(&text).last(), Some(&("世🎉界!".to_string(), true)));
+        assert!(!text.insert_text(0, "", None));
+    }
+
+    #[test]
+    fn deleting_a_range_trims_and_merges_runs() {
+        let mut text = plain("héllo 世界");
+        text.set_style_range(6, 8, &bold());
+        assert!(text.delete_range(4, 7));
+        assert_eq!(text.content, "héll界");
+        assert_eq!(spans(&text), vec![("héll".to_string(), false), ("界".to_string(), true)]);
+
+        assert!(text.delete_range(4, 99));
+        assert_eq!(text.content, "héll");
+        assert!(text.runs.is_empty(), "unstyled text goes back to plain");
+        assert!(!text.delete_range(2, 2));
+        assert!(!text.delete_range(3, 1));
+    }
+
+    #[test]
+    fn style_patches_only_report_real_changes() {
+        let mut text = plain("naïve café");
+        assert!(!text.set_style_range(0, 0, &bold()));
+        assert!(text.set_style_range(0, 5, &bold()));
+        assert!(!text.set_style_range(1, 4, &bold()), "already bold");
+
+        let patch = TextStylePatch {
+            bold: Some(false),
+            font_size: Some(Some(0.2)),
+            link: Some(Some("javascript:alert(1)".to_string())),
+            ..TextStylePatch::default()
+        };
+        assert!(text.set_style_range(6, 10, &patch));
+        let style = text.style_at(8);
+        assert_eq!(style.font_size, Some(MIN_FONT_SIZE));
+        assert_eq!(style.link, None);
+
+        let clear = TextStylePatch {
+            bold: Some(false),
+            font_size: Some(None),
+            ..TextStylePatch::default()
+        };
+        assert!(text.set_style_range(0, 10, &clear));
+        assert!(text.runs.is_empty());
+    }
+
+    #[test]
+    fn list_ranges_cover_every_touched_line() {
+        let mut text = plain("één\ntwee\ndrie");
+        assert!(text.set_list_range(2, 5, ListKind::Numbered));
+        assert_eq!(lists(&text), vec![ListKind::Numbered, ListKind::Numbered]);
+        assert_eq!(text.list_marker(1), Some("2.".to_string()));
+        assert_eq!(text.list_marker(2), None);
+        assert!(!text.set_list_range(0, 0, ListKind::Numbered));
+
+        assert!(text.set_list_range(9, 9, ListKind::Bullet));
+        assert_eq!(text.list_marker(2), Some("•".to_string()));
+        assert!(text.set_list_range(0, 13, ListKind::None));
+        assert!(text.paragraphs.is_empty());
+    }
+
+    #[test]
+    fn newlines_typed_in_a_list_extend_it() {
+        let mut text = plain("één\ntwee\ndrie");
+        text.set_list_range(0, 5, ListKind::Numbered);
+        assert!(text.insert_text(3, "\nvier\n", None));
+        assert_eq!(text.content, "één\nvier\n\ntwee\ndrie");
+        assert_eq!(lists(&text), vec![ListKind::Numbered; 4]);
+        assert_eq!(text.list_marker(3), Some("4.".to_string()));
+        assert_eq!(text.list_marker(4), None);
+
+        // a newline typed in the plain last line adds no list entries
+        assert!(text.insert_text(text.char_len(), "\nvijf", None));
+        assert_eq!(lists(&text), vec![ListKind::Numbered; 4]);
+        assert_eq!(text.lines().len(), 6);
+    }
+
+    #[test]
+    fn deleting_newlines_joins_lines_into_the_first() {
+        let mut text = plain("één\ntwee\ndrie");
+        text.set_list_range(5, 5, ListKind::Bullet);
+        assert_eq!(lists(&text), vec![ListKind::None, ListKind::Bullet]);
+
+        // joining the bullet onto the plain first line drops the bullet
+        assert!(text.delete_range(3, 4));
+        assert_eq!(text.content, "ééntwee\ndrie");
+        assert!(text.paragraphs.is_empty());
+
+        text.set_list_range(0, 0, ListKind::Numbered);
+        assert!(text.delete_range(7, 8));
+        assert_eq!(text.content, "ééntweedrie");
+        assert_eq!(lists(&text), vec![ListKind::Numbered]);
+
+        let mut text = plain("a\nb\nc\nd");
+        text.set_list_range(0, 7, ListKind::Bullet);
+        text.set_list_range(6, 6, ListKind::Numbered);
+        assert!(text.delete_range(1, 5));
+        assert_eq!(text.content, "a\nd");
+        assert_eq!(lists(&text), vec![ListKind::Bullet, ListKind::Numbered]);
+    }
+}
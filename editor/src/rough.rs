@@ -0,0 +1,357 @@
+use crate::elements::Element;
+use crate::fill::Fill;
+use crate::geometry::{ellipse_polygon, flatten_bezier, rounded_rect_polygon, Point, Rect};
+use crate::routing::Routing;
+use crate::svg::num;
+use serde::Serialize;
+use std::f64::consts::{PI, TAU};
+use std::fmt::Write;
+
+// how far, before roughness scaling, a stroke may stray from the true outline
+const MAX_OFFSET: f64 = 2.0;
+const BOWING: f64 = 1.0;
+const HACHURE_ANGLE: f64 = -41.0 * PI / 180.0;
+// hachure lines are this many stroke widths apart
+const HACHURE_GAP: f64 = 4.0;
+const MIN_HACHURE_GAP: f64 = 2.0;
+const CURVE_STEPS: f64 = 9.0;
+const CONNECTOR_CURVE_STEPS: usize = 16;
+
+// plain SVG path data: `stroke` is drawn with the element's stroke, `hachure` with its fill
+// color at half the stroke width. An element's `roughness` scales the wobble, with 0 drawing
+// clean geometry, and its `seed` drives it so every client draws the same strokes
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Sketch {
+    pub stroke: String,
+    pub hachure: Option<String>,
+}
+
+// Park-Miller generator, small and identical on every platform
+struct Random {
+    state: u32,
+}
+
+impl Random {
+    fn new(seed: u32) -> Self {
+        Self { state: seed % 2_147_483_646 + 1 }
+    }
+
+    fn next(&mut self) -> f64 {
+        self.state = ((self.state as u64 * 48_271) % 2_147_483_647) as u32;
+        self.state as f64 / 2_147_483_647.0
+    }
+}
+
+struct Pen {
+    random: Random,
+    roughness: f64,
+    data: String,
+}
+
+impl Pen {
+    fn new(seed: u32, roughness: f64) -> Self {
+        Self {
+            random: Random::new(seed),
+            roughness,
+            data: String::new(),
+        }
+    }
+
+    // a value in -range..range scaled by roughness and the length gain
+    fn offset(&mut self, range: f64, gain: f64) -> f64 {
+        self.roughness * gain * (self.random.next() * 2.0 * range - range)
+    }
+
+    fn point(&mut self, point: Point, range: f64, gain: f64) -> Point {
+        Point::new(point.x + self.offset(range, gain), point.y + self.offset(range, gain))
+    }
+
+    // two passes over the same segment, the second one tighter
+    fn double_line(&mut self, start: Point, end: Point) {
+        self.line(start, end, false);
+        self.line(start, end, true);
+    }
+
+    // a slightly bowed cubic from start to end
+    fn line(&mut self, start: Point, end: Point, overlay: bool) {
+        let length = start.distance(end);
+        // long lines wobble proportionally less
+        let gain = if length < 200.0 {
+            1.0
+        } else if length > 500.0 {
+            0.4
+        } else {
+            -0.0016668 * length + 1.233334
+        };
+        let mut range = MAX_OFFSET;
+        if range * range * 100.0 > length * length {
+            range = length / 10.0;
+        }
+        if overlay {
+            range /= 2.0;
+        }
+        let diverge = 0.2 + self.random.next() * 0.2;
+        let bow_x = BOWING * MAX_OFFSET * (end.y - start.y) / 200.0;
+        let bow_y = BOWING * MAX_OFFSET * (start.x - end.x) / 200.0;
+        let bow = Point::new(self.offset(bow_x, gain), self.offset(bow_y, gain));
+        let along = |t: f64| Point::new(start.x + (end.x - start.x) * t + bow.x, start.y + (end.y - start.y) * t + bow.y);
+
+        let from = self.point(start, range, gain);
+        let first = self.point(along(diverge), range, gain);
+        let second = self.point(along(diverge * 2.0), range, gain);
+        let to = self.point(end, range, gain);
+        self.move_to(from);
+        self.cubic_to(first, second, to);
+    }
+
+    // passes through every point with a Catmull-Rom spline; the ends are repeated so the curve
+    // reaches them
+    fn curve(&mut self, points: &[Point]) {
+        if points.len() < 2 {
+            return;
+        }
+        let mut padded = Vec::with_capacity(points.len() + 2);
+        padded.push(points[0]);
+        padded.extend_from_slice(points);
+        padded.push(points[points.len() - 1]);
+        self.move_to(padded[1]);
+        for window in padded.windows(4) {
+            let [before, from, to, after] = [window[0], window[1], window[2], window[3]];
+            let first = Point::new(from.x + (to.x - before.x) / 6.0, from.y + (to.y - before.y) / 6.0);
+            let second = Point::new(to.x - (after.x - from.x) / 6.0, to.y - (after.y - from.y) / 6.0);
+            self.cubic_to(first, second, to);
+        }
+    }
+
+    // a closed outline drawn twice through jittered copies of its points, starting at a random
+    // vertex and running a little past it like a quick pen stroke
+    fn closed_curve(&mut self, points: &[Point], range: f64) {
+        if points.len() < 3 {
+            return;
+        }
+        for pass in 0..2 {
+            let start = (self.random.next() * points.len() as f64) as usize % points.len();
+            let scale = if pass == 0 { 1.0 } else { 1.5 };
+            let overlap = 1 + (self.random.next() * 2.0) as usize;
+            let jittered: Vec<Point> = (0..=points.len() + overlap)
+                .map(|index| points[(start + index) % points.len()])
+                .map(|point| self.point(point, range * scale, 1.0))
+                .collect();
+            self.curve(&jittered);
+        }
+    }
+
+    fn move_to(&mut self, point: Point) {
+        if !self.data.is_empty() {
+            self.data.push(' ');
+        }
+        let _ = write!(self.data, "M {} {}", num(point.x), num(point.y));
+    }
+
+    fn cubic_to(&mut self, first: Point, second: Point, end: Point) {
+        let _ = write!(
+            self.data,
+            " C {} {} {} {} {} {}",
+            num(first.x),
+            num(first.y),
+            num(second.x),
+            num(second.y),
+            num(end.x),
+            num(end.y)
+        );
+    }
+
+    fn finish(self) -> String {
+        self.data
+    }
+}
+
+// the roughened drawing of an element in its own unrotated frame, or None when the element is
+// drawn clean or cannot be sketched
+pub fn sketch(element: &Element) -> Option<Sketch> {
+    let roughness = element.roughness();
+    if roughness <= 0.0 {
+        return None;
+    }
+    let seed = element.seed()?;
+    let mut pen = Pen::new(seed, roughness);
+    let (outline, line_width, fill) = match element {
+        Element::Rectangle(rect) => {
+            let bounds = Rect::new(rect.position.x, rect.position.y, rect.width, rect.height).normalized();
+            let radius = rect.border_radius.min(bounds.width / 2.0).min(bounds.height / 2.0);
+            let outline = if radius > 0.0 {
+                let outline = rounded_rect_polygon(&bounds, radius);
+                pen.closed_curve(&outline, MAX_OFFSET / 2.0);
+                outline
+            } else {
+                let corners = bounds.corners();
+                draw_polygon(&mut pen, &corners);
+                corners.to_vec()
+            };
+            (outline, rect.line_width, rect.fill.as_ref())
+        }
+        Element::Diamond(diamond) => {
+            let bounds =
+                Rect::new(diamond.position.x, diamond.position.y, diamond.width, diamond.height).normalized();
+            let center = bounds.center();
+            let corners = [
+                Point::new(center.x, bounds.top()),
+                Point::new(bounds.right(), center.y),
+                Point::new(center.x, bounds.bottom()),
+                Point::new(bounds.left(), center.y),
+            ];
+            draw_polygon(&mut pen, &corners);
+            (corners.to_vec(), diamond.line_width, diamond.fill.as_ref())
+        }
+        Element::Ellipse(ellipse) => {
+            let (radius_x, radius_y) = (ellipse.radius_x.abs(), ellipse.radius_y.abs());
+            let size = ((radius_x * radius_x + radius_y * radius_y) * 2.0).sqrt();
+            let steps = CURVE_STEPS.max(CURVE_STEPS / 200f64.sqrt() * (TAU * size).sqrt()).ceil() as usize;
+            let outline = ellipse_polygon(ellipse.position, radius_x, radius_y, steps);
+            pen.closed_curve(&outline, MAX_OFFSET / 2.0);
+            (outline, ellipse.line_width, ellipse.fill.as_ref())
+        }
+        Element::Line(_) | Element::Arrow(_) => {
+            draw_connector(&mut pen, element);
+            return Some(Sketch {
+                stroke: pen.finish(),
+                hachure: None,
+            });
+        }
+        _ => return None,
+    };
+    let stroke = std::mem::take(&mut pen.data);
+    // gradients and patterns keep their own paint; only flat fills are hatched
+    let hachure = match fill {
+        Some(Fill::Solid(_)) => {
+            for (start, end) in hachure_lines(&outline, (line_width * HACHURE_GAP).max(MIN_HACHURE_GAP)) {
+                pen.line(start, end, false);
+            }
+            Some(pen.finish())
+        }
+        _ => None,
+    };
+    Some(Sketch { stroke, hachure })
+}
+
+fn draw_polygon(pen: &mut Pen, corners: &[Point]) {
+    for (index, start) in corners.iter().enumerate() {
+        pen.double_line(*start, corners[(index + 1) % corners.len()]);
+    }
+}
+
+fn draw_connector(pen: &mut Pen, element: &Element) {
+    let points = element.connector_points();
+    if points.len() < 2 {
+        return;
+    }
+    let controls = element.control_points();
+    let curved = if !controls.is_empty() {
+        Some(flatten_bezier(points[0], controls, points[points.len() - 1], CONNECTOR_CURVE_STEPS))
+    } else if points.len() > 2 && element.routing() == Some(Routing::Curved) {
+        Some(points.clone())
+    } else {
+        None
+    };
+    match curved {
+        Some(curve) => {
+            for pass in 0..2 {
+                let range = if pass == 0 { MAX_OFFSET / 2.0 } else { MAX_OFFSET / 4.0 };
+                let jittered: Vec<Point> = curve.iter().map(|point| pen.point(*point, range, 1.0)).collect();
+                pen.curve(&jittered);
+            }
+        }
+        None => {
+            for pair in points.windows(2) {
+                pen.double_line(pair[0], pair[1]);
+            }
+        }
+    }
+}
+
+// parallel lines across the polygon at the hachure angle: the polygon is turned so the lines
+// run horizontally, scanned, and the pieces turned back
+fn hachure_lines(polygon: &[Point], gap: f64) -> Vec<(Point, Point)> {
+    let (sin, cos) = HACHURE_ANGLE.sin_cos();
+    let rotate = |point: Point, sin: f64| Point::new(point.x * cos - point.y * sin, point.x * sin + point.y * cos);
+    let turned: Vec<Point> = polygon.iter().map(|point| rotate(*point, -sin)).collect();
+    let Some(bounds) = Rect::from_points(turned.iter().copied()) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    let mut y = bounds.top() + gap / 2.0;
+    while y < bounds.bottom() {
+        let mut crossings: Vec<f64> = turned
+            .iter()
+            .zip(turned.iter().cycle().skip(1))
+            .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+            .map(|(a, b)| a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x))
+            .collect();
+        crossings.sort_by(f64::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            lines.push((rotate(Point::new(pair[0], y), sin), rotate(Point::new(pair[1], y), sin)));
+        }
+        y += gap;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::{Ellipse, Line, Rectangle};
+    use crate::fill::{GradientStop, LinearGradient};
+    use crate::style::Color;
+
+    fn rough_rectangle(id: u64, roughness: f64, seed: u32) -> Element {
+        let mut rect = Rectangle::new(id, Point::new(10.0, 20.0), 120.0, 80.0);
+        rect.fill = Some(Fill::Solid(Color::rgb(255, 200, 0)));
+        rect.roughness = roughness;
+        rect.seed = seed;
+        Element::Rectangle(rect)
+    }
+
+    #[test]
+    fn the_same_seed_draws_the_same_strokes() {
+        let first = sketch(&rough_rectangle(1, 1.5, 42)).unwrap();
+        let second = sketch(&rough_rectangle(2, 1.5, 42)).unwrap();
+        assert_eq!(first, second);
+        assert!(first.stroke.starts_with("M "));
+        assert!(first.hachure.is_some());
+
+        let other = sketch(&rough_rectangle(1, 1.5, 43)).unwrap();
+        assert_ne!(first.stroke, other.stroke);
+
+        let mut line = Line::new(3, Point::new(0.0, 0.0), Point::new(300.0, 40.0));
+        line.roughness = 1.0;
+        line.seed = 7;
+        let line = Element::Line(line);
+        assert_eq!(sketch(&line), sketch(&line.clone()));
+        assert_eq!(sketch(&line).unwrap().hachure, None);
+    }
+
+    #[test]
+    fn zero_roughness_draws_clean_geometry() {
+        assert_eq!(sketch(&rough_rectangle(1, 0.0, 42)), None);
+        assert_eq!(sketch(&rough_rectangle(1, -1.0, 42)), None);
+        let ellipse = Element::Ellipse(Ellipse::new(2, Point::new(0.0, 0.0), 30.0, 20.0));
+        assert_eq!(sketch(&ellipse), None);
+        let line = Element::Line(Line::new(3, Point::new(0.0, 0.0), Point::new(50.0, 50.0)));
+        assert_eq!(sketch(&line), None);
+    }
+
+    #[test]
+    fn only_solid_fills_are_hatched() {
+        let Element::Rectangle(mut rect) = rough_rectangle(1, 1.0, 9) else { unreachable!() };
+        rect.fill = Some(Fill::LinearGradient(LinearGradient {
+            angle: 0.0,
+            stops: vec![
+                GradientStop { offset: 0.0, color: Color::BLACK },
+                GradientStop { offset: 1.0, color: Color::rgb(255, 255, 255) },
+            ],
+        }));
+        assert_eq!(sketch(&Element::Rectangle(rect.clone())).unwrap().hachure, None);
+        rect.fill = None;
+        assert_eq!(sketch(&Element::Rectangle(rect)).unwrap().hachure, None);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::geometry::{Point, Rect};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+pub const ROUTE_MARGIN: f64 = 20.0;
+const BEND_PENALTY: f64 = 40.0;
+const EPSILON: f64 = 1e-6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Routing {
+    #[default]
+    Straight,
+    Elbow,
+    Curved,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Heading {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Heading {
+    pub fn from_vector(dx: f64, dy: f64) -> Heading {
+        if dx.abs() >= dy.abs() {
+            if dx >= 0.0 {
+                Heading::Right
+            } else {
+                Heading::Left
+            }
+        } else if dy >= 0.0 {
+            Heading::Down
+        } else {
+            Heading::Up
+        }
+    }
+
+    pub fn offset(&self, point: Point, distance: f64) -> Point {
+        match self {
+            Heading::Up => Point::new(point.x, point.y - distance),
+            Heading::Right => Point::new(point.x + distance, point.y),
+            Heading::Down => Point::new(point.x, point.y + distance),
+            Heading::Left => Point::new(point.x - distance, point.y),
+        }
+    }
+
+    pub fn opposite(&self) -> Heading {
+        match self {
+            Heading::Up => Heading::Down,
+            Heading::Right => Heading::Left,
+            Heading::Down => Heading::Up,
+            Heading::Left => Heading::Right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RouteEnd {
+    pub point: Point,
+    pub heading: Heading,
+}
+
+// full polyline from start to end, made only of horizontal and vertical segments
+pub fn route_elbow(start: RouteEnd, end: RouteEnd, obstacles: &[Rect]) -> Vec<Point> {
+    let start_stub = start.heading.offset(start.point, ROUTE_MARGIN);
+    let end_stub = end.heading.offset(end.point, ROUTE_MARGIN);
+    let inflated: Vec<Rect> = obstacles
+        .iter()
+        .map(|obstacle| obstacle.normalized().expand(ROUTE_MARGIN / 2.0))
+        .collect();
+
+    let middle = search(start_stub, start.heading, end_stub, end.heading.opposite(), &inflated)
+        .unwrap_or_else(|| fallback(start_stub, start.heading, end_stub));
+
+    let mut points = vec![start.point];
+    points.extend(middle);
+    points.push(end.point);
+    simplify(points)
+}
+
+fn fallback(start_stub: Point, start_heading: Heading, end_stub: Point) -> Vec<Point> {
+    match start_heading {
+        Heading::Left | Heading::Right => {
+            let middle_x = (start_stub.x + end_stub.x) / 2.0;
+            vec![
+                start_stub,
+                Point::new(middle_x, start_stub.y),
+                Point::new(middle_x, end_stub.y),
+                end_stub,
+            ]
+        }
+        Heading::Up | Heading::Down => {
+            let middle_y = (start_stub.y + end_stub.y) / 2.0;
+            vec![
+                start_stub,
+                Point::new(start_stub.x, middle_y),
+                Point::new(end_stub.x, middle_y),
+                end_stub,
+            ]
+        }
+    }
+}
+
+fn strictly_inside(point: Point, obstacle: &Rect) -> bool {
+    point.x > obstacle.left() + EPSILON
+        && point.x < obstacle.right() - EPSILON
+        && point.y > obstacle.top() + EPSILON
+        && point.y < obstacle.bottom() - EPSILON
+}
+
+fn crosses(from: Point, to: Point, obstacle: &Rect) -> bool {
+    let (min_x, max_x) = (from.x.min(to.x), from.x.max(to.x));
+    let (min_y, max_y) = (from.y.min(to.y), from.y.max(to.y));
+    min_x < obstacle.right() - EPSILON
+        && max_x > obstacle.left() + EPSILON
+        && min_y < obstacle.bottom() - EPSILON
+        && max_y > obstacle.top() + EPSILON
+}
+
+fn coordinates(mut values: Vec<f64>) -> Vec<f64> {
+    values.retain(|value| value.is_finite());
+    values.sort_by(f64::total_cmp);
+    values.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+    values
+}
+
+type SearchKey = ((usize, usize), Heading);
+
+#[derive(Clone, Copy, PartialEq)]
+struct State {
+    estimate: f64,
+    cost: f64,
+    node: (usize, usize),
+    heading: Heading,
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn search(
+    from: Point,
+    from_heading: Heading,
+    to: Point,
+    arrival_heading: Heading,
+    obstacles: &[Rect],
+) -> Option<Vec<Point>> {
+    if obstacles
+        .iter()
+        .any(|obstacle| strictly_inside(from, obstacle) || strictly_inside(to, obstacle))
+    {
+        return None;
+    }
+
+    let mut xs = vec![from.x, to.x, (from.x + to.x) / 2.0];
+    let mut ys = vec![from.y, to.y, (from.y + to.y) / 2.0];
+    for obstacle in obstacles {
+        xs.extend([obstacle.left(), obstacle.right()]);
+        ys.extend([obstacle.top(), obstacle.bottom()]);
+    }
+    let xs = coordinates(xs);
+    let ys = coordinates(ys);
+    let index_of = |values: &[f64], value: f64| values.iter().position(|v| (v - value).abs() < EPSILON);
+    let start = (index_of(&xs, from.x)?, index_of(&ys, from.y)?);
+    let goal = (index_of(&xs, to.x)?, index_of(&ys, to.y)?);
+    let point_at = |(i, j): (usize, usize)| Point::new(xs[i], ys[j]);
+    let heuristic = |point: Point| (point.x - to.x).abs() + (point.y - to.y).abs();
+
+    let mut best: HashMap<SearchKey, f64> = HashMap::new();
+    let mut previous: HashMap<SearchKey, SearchKey> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    best.insert((start, from_heading), 0.0);
+    queue.push(State {
+        estimate: heuristic(from),
+        cost: 0.0,
+        node: start,
+        heading: from_heading,
+    });
+
+    while let Some(State { cost, node, heading, .. }) = queue.pop() {
+        if best.get(&(node, heading)).is_some_and(|known| cost > *known) {
+            continue;
+        }
+        if node == goal {
+            let mut path = vec![point_at(node)];
+            let mut key = (node, heading);
+            while let Some(prior) = previous.get(&key) {
+                path.push(point_at(prior.0));
+                key = *prior;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let (i, j) = node;
+        let neighbours = [
+            (Heading::Right, (i + 1 < xs.len()).then(|| (i + 1, j))),
+            (Heading::Left, i.checked_sub(1).map(|i| (i, j))),
+            (Heading::Down, (j + 1 < ys.len()).then(|| (i, j + 1))),
+            (Heading::Up, j.checked_sub(1).map(|j| (i, j))),
+        ];
+        for (direction, next) in neighbours {
+            let Some(next) = next else {
+                continue;
+            };
+            if direction == heading.opposite() {
+                continue;
+            }
+            let (here, there) = (point_at(node), point_at(next));
+            if obstacles
+                .iter()
+                .any(|obstacle| strictly_inside(there, obstacle) || crosses(here, there, obstacle))
+            {
+                continue;
+            }
+            let mut next_cost = cost + here.distance(there);
+            if direction != heading {
+                next_cost += BEND_PENALTY;
+            }
+            if next == goal && direction != arrival_heading {
+                next_cost += BEND_PENALTY;
+            }
+            if best.get(&(next, direction)).is_none_or(|known| next_cost < *known) {
+                best.insert((next, direction), next_cost);
+                previous.insert((next, direction), (node, heading));
+                queue.push(State {
+                    estimate: next_cost + heuristic(there),
+                    cost: next_cost,
+                    node: next,
+                    heading: direction,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn simplify(points: Vec<Point>) -> Vec<Point> {
+    let mut result: Vec<Point> = Vec::with_capacity(points.len());
+    for point in points {
+        if result
+            .last()
+            .is_some_and(|last| last.distance(point) < EPSILON)
+        {
+            continue;
+        }
+        if result.len() >= 2 {
+            let a = result[result.len() - 2];
+            let b = result[result.len() - 1];
+            let collinear = ((a.x - b.x).abs() < EPSILON && (b.x - point.x).abs() < EPSILON)
+                || ((a.y - b.y).abs() < EPSILON && (b.y - point.y).abs() < EPSILON);
+            if collinear {
+                result.pop();
+            }
+        }
+        result.push(point);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::{Anchor, Binding, ConnectorEnd, Side};
+    use crate::document::Document;
+
+    fn end(x: f64, y: f64, heading: Heading) -> RouteEnd {
+        RouteEnd { point: Point::new(x, y), heading }
+    }
+
+    fn assert_axis_aligned(route: &[Point]) {
+        for segment in route.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            assert!(
+                (a.x - b.x).abs() < EPSILON || (a.y - b.y).abs() < EPSILON,
+                "{:?} -> {:?} is diagonal",
+                a,
+                b
+            );
+        }
+    }
+
+    fn crosses_any(route: &[Point], obstacle: &Rect) -> bool {
+        route.windows(2).any(|segment| crosses(segment[0], segment[1], obstacle))
+    }
+
+    #[test]
+    fn every_segment_is_axis_aligned() {
+        let cases = [
+            (end(0.0, 0.0, Heading::Right), end(200.0, 120.0, Heading::Left)),
+            (end(0.0, 0.0, Heading::Down), end(-150.0, 90.0, Heading::Up)),
+            (end(0.0, 0.0, Heading::Right), end(-80.0, 60.0, Heading::Right)),
+            (end(0.0, 0.0, Heading::Up), end(130.0, 40.0, Heading::Left)),
+        ];
+        for (start, finish) in cases {
+            let route = route_elbow(start, finish, &[]);
+            assert_eq!(route.first(), Some(&start.point));
+            assert_eq!(route.last(), Some(&finish.point));
+            assert_axis_aligned(&route);
+        }
+    }
+
+    #[test]
+    fn route_goes_around_an_obstacle_between_the_ends() {
+        let obstacle = Rect::new(80.0, -40.0, 40.0, 80.0);
+        let (start, finish) = (end(0.0, 0.0, Heading::Right), end(200.0, 0.0, Heading::Left));
+        assert!(crosses(start.point, finish.point, &obstacle));
+
+        let route = route_elbow(start, finish, &[obstacle]);
+        assert_axis_aligned(&route);
+        assert!(!crosses_any(&route, &obstacle), "{:?} crosses {:?}", route, obstacle);
+    }
+
+    #[test]
+    fn moving_an_obstacle_into_the_way_reroutes_the_connector() {
+        let mut doc = Document::new();
+        let first = doc.add_rectangle(Point::new(0.0, 0.0), 40.0, 40.0);
+        let second = doc.add_rectangle(Point::new(300.0, 0.0), 40.0, 40.0);
+        let arrow = doc.add_arrow(Point::new(40.0, 20.0), Point::new(300.0, 20.0));
+        let side = |side| Anchor::Side { side };
+        doc.bind_connector(arrow, ConnectorEnd::Start, Some(Binding::new(first, side(Side::Right))));
+        doc.bind_connector(arrow, ConnectorEnd::End, Some(Binding::new(second, side(Side::Left))));
+        doc.set_connector_routing(arrow, Routing::Elbow);
+        let route = |doc: &Document| doc.get(arrow).map(|element| element.connector_points()).unwrap_or_default();
+        assert_eq!(route(&doc), vec![Point::new(40.0, 20.0), Point::new(300.0, 20.0)]);
+
+        let blocker = doc.add_rectangle(Point::new(500.0, 500.0), 60.0, 60.0);
+        doc.move_rectangle(blocker, Point::new(140.0, -10.0), true);
+        let detour = route(&doc);
+        assert!(detour.len() > 2);
+        assert_eq!(detour.first(), Some(&Point::new(40.0, 20.0)));
+        assert_eq!(detour.last(), Some(&Point::new(300.0, 20.0)));
+        assert_axis_aligned(&detour);
+        assert!(!crosses_any(&detour, &Rect::new(140.0, -10.0, 60.0, 60.0)));
+    }
+}
@@ -0,0 +1,284 @@
+use crate::align::Axis;
+use crate::geometry::{Point, Rect};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_SNAP_THRESHOLD: f64 = 8.0;
+// lines this close count as lined up when collecting guides
+const GUIDE_EPSILON: f64 = 1e-6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapOptions {
+    // 0 turns grid snapping off
+    pub grid_size: f64,
+    // how far, in document units, an edge or gap may be pulled
+    pub threshold: f64,
+    pub objects: bool,
+    pub gaps: bool,
+}
+
+impl Default for SnapOptions {
+    fn default() -> Self {
+        Self {
+            grid_size: 0.0,
+            threshold: DEFAULT_SNAP_THRESHOLD,
+            objects: true,
+            gaps: true,
+        }
+    }
+}
+
+// what is being dragged: the whole box or one of its resize handles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapHandle {
+    #[default]
+    Move,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuideKind {
+    Edge,
+    Center,
+    Gap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Guide {
+    pub kind: GuideKind,
+    pub start: Point,
+    pub end: Point,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snap {
+    pub bounds: Rect,
+    pub guides: Vec<Guide>,
+}
+
+impl SnapHandle {
+    // whether the start and end edges along the axis follow the pointer
+    fn edges(&self, axis: Axis) -> (bool, bool) {
+        match (self, axis) {
+            (SnapHandle::Move, _) => (true, true),
+            (SnapHandle::Left | SnapHandle::TopLeft | SnapHandle::BottomLeft, Axis::Horizontal) => (true, false),
+            (SnapHandle::Right | SnapHandle::TopRight | SnapHandle::BottomRight, Axis::Horizontal) => (false, true),
+            (SnapHandle::Top | SnapHandle::TopLeft | SnapHandle::TopRight, Axis::Vertical) => (true, false),
+            (SnapHandle::Bottom | SnapHandle::BottomLeft | SnapHandle::BottomRight, Axis::Vertical) => (false, true),
+            _ => (false, false),
+        }
+    }
+}
+
+// `others` are the bounds of the shapes to line up with
+pub fn snap_bounds(bounds: Rect, handle: SnapHandle, others: &[Rect], options: &SnapOptions) -> Snap {
+    let bounds = bounds.normalized();
+    let mut snapped = bounds;
+    for axis in [Axis::Horizontal, Axis::Vertical] {
+        let delta = snap_axis(&bounds, handle, axis, others, options);
+        let (start, end) = handle.edges(axis);
+        let (position, size) = match axis {
+            Axis::Horizontal => (&mut snapped.x, &mut snapped.width),
+            Axis::Vertical => (&mut snapped.y, &mut snapped.height),
+        };
+        match (start, end) {
+            (true, true) => *position += delta,
+            (true, false) => {
+                *position += delta;
+                *size -= delta;
+            }
+            (false, true) => *size += delta,
+            (false, false) => {}
+        }
+    }
+
+    let mut guides = Vec::new();
+    for axis in [Axis::Horizontal, Axis::Vertical] {
+        if options.objects {
+            alignment_guides(&snapped, handle, axis, others, &mut guides);
+        }
+        if options.gaps && handle == SnapHandle::Move {
+            gap_guides(&snapped, axis, others, &mut guides);
+        }
+    }
+    Snap {
+        bounds: snapped.normalized(),
+        guides,
+    }
+}
+
+// the dragged lines along an axis, flagged when they are the center
+fn moving_lines(bounds: &Rect, handle: SnapHandle, axis: Axis) -> Vec<(f64, bool)> {
+    let (start, end) = handle.edges(axis);
+    let mut lines = Vec::new();
+    if start {
+        lines.push((axis.start(bounds), false));
+    }
+    if handle == SnapHandle::Move {
+        lines.push((axis.center(bounds), true));
+    }
+    if end {
+        lines.push((axis.end(bounds), false));
+    }
+    lines
+}
+
+fn target_lines(bounds: &Rect, axis: Axis) -> [f64; 3] {
+    [axis.start(bounds), axis.center(bounds), axis.end(bounds)]
+}
+
+// the grid sets a baseline; the closest object line or equal gap within the threshold wins over it
+fn snap_axis(bounds: &Rect, handle: SnapHandle, axis: Axis, others: &[Rect], options: &SnapOptions) -> f64 {
+    let lines = moving_lines(bounds, handle, axis);
+    let Some((first, _)) = lines.first().copied() else {
+        return 0.0;
+    };
+    let mut offset = 0.0;
+    if options.grid_size > 0.0 {
+        offset = (first / options.grid_size).round() * options.grid_size - first;
+    }
+
+    let mut candidates = Vec::new();
+    if options.objects {
+        for other in others {
+            for target in target_lines(other, axis) {
+                candidates.extend(lines.iter().map(|(line, _)| target - line));
+            }
+        }
+    }
+    if options.gaps && handle == SnapHandle::Move {
+        candidates.extend(gap_offsets(bounds, axis, others));
+    }
+    let mut closest = options.threshold;
+    for delta in candidates {
+        if delta.abs() <= closest {
+            closest = delta.abs();
+            offset = delta;
+        }
+    }
+    offset
+}
+
+// shapes sharing some of the box's extent across the axis, ordered along it
+fn row_neighbours(bounds: &Rect, axis: Axis, others: &[Rect]) -> Vec<Rect> {
+    let cross = axis.cross();
+    let mut row: Vec<Rect> = others
+        .iter()
+        .filter(|other| cross.start(other) < cross.end(bounds) && cross.end(other) > cross.start(bounds))
+        .copied()
+        .collect();
+    row.sort_by(|a, b| axis.start(a).total_cmp(&axis.start(b)));
+    row
+}
+
+// centering between the nearest neighbours, or repeating a gap already used in the row
+fn gap_offsets(bounds: &Rect, axis: Axis, others: &[Rect]) -> Vec<f64> {
+    let row = row_neighbours(bounds, axis, others);
+    let center = axis.center(bounds);
+    let before = row
+        .iter()
+        .filter(|other| axis.end(other) <= center)
+        .max_by(|a, b| axis.end(a).total_cmp(&axis.end(b)));
+    let after = row
+        .iter()
+        .filter(|other| axis.start(other) >= center)
+        .min_by(|a, b| axis.start(a).total_cmp(&axis.start(b)));
+
+    let mut offsets = Vec::new();
+    if let (Some(before), Some(after)) = (before, after) {
+        let free = axis.start(after) - axis.end(before) - axis.size(bounds);
+        if free >= 0.0 {
+            offsets.push(axis.end(before) + free / 2.0 - axis.start(bounds));
+        }
+    }
+    let gaps = row
+        .windows(2)
+        .map(|pair| axis.start(&pair[1]) - axis.end(&pair[0]))
+        .filter(|gap| *gap > 0.0);
+    for gap in gaps {
+        if let Some(before) = before {
+            offsets.push(axis.end(before) + gap - axis.start(bounds));
+        }
+        if let Some(after) = after {
+            offsets.push(axis.start(after) - gap - axis.end(bounds));
+        }
+    }
+    offsets
+}
+
+// one guide per dragged line that meets a line of another shape, spanning all of them
+fn alignment_guides(bounds: &Rect, handle: SnapHandle, axis: Axis, others: &[Rect], guides: &mut Vec<Guide>) {
+    let cross = axis.cross();
+    for (line, center) in moving_lines(bounds, handle, axis) {
+        let mut extent = None;
+        for other in others {
+            if target_lines(other, axis)
+                .iter()
+                .any(|target| (target - line).abs() <= GUIDE_EPSILON)
+            {
+                let (low, high) = extent.unwrap_or((cross.start(bounds), cross.end(bounds)));
+                extent = Some((low.min(cross.start(other)), high.max(cross.end(other))));
+            }
+        }
+        if let Some((low, high)) = extent {
+            guides.push(Guide {
+                kind: if center { GuideKind::Center } else { GuideKind::Edge },
+                start: axis.point(line, low),
+                end: axis.point(line, high),
+            });
+        }
+    }
+}
+
+// marks every gap in the row that matches a gap next to the box, when there are at least two
+fn gap_guides(bounds: &Rect, axis: Axis, others: &[Rect], guides: &mut Vec<Guide>) {
+    let cross = axis.cross();
+    let mut row: Vec<(Rect, bool)> = row_neighbours(bounds, axis, others)
+        .into_iter()
+        .map(|other| (other, false))
+        .collect();
+    row.push((*bounds, true));
+    row.sort_by(|a, b| axis.start(&a.0).total_cmp(&axis.start(&b.0)));
+
+    let pairs: Vec<(Rect, Rect, f64, bool)> = row
+        .windows(2)
+        .map(|pair| {
+            let gap = axis.start(&pair[1].0) - axis.end(&pair[0].0);
+            (pair[0].0, pair[1].0, gap, pair[0].1 || pair[1].1)
+        })
+        .filter(|(_, _, gap, _)| *gap > 0.0)
+        .collect();
+    let mut adjacent: Vec<f64> = pairs
+        .iter()
+        .filter(|(_, _, _, touches_box)| *touches_box)
+        .map(|(_, _, gap, _)| *gap)
+        .collect();
+    adjacent.dedup_by(|a, b| (*a - *b).abs() <= GUIDE_EPSILON);
+
+    for gap in adjacent {
+        let matching: Vec<&(Rect, Rect, f64, bool)> = pairs
+            .iter()
+            .filter(|(_, _, other, _)| (other - gap).abs() <= GUIDE_EPSILON)
+            .collect();
+        if matching.len() < 2 {
+            continue;
+        }
+        for (first, second, _, _) in matching {
+            let across = (cross.start(first).max(cross.start(second)) + cross.end(first).min(cross.end(second))) / 2.0;
+            guides.push(Guide {
+                kind: GuideKind::Gap,
+                start: axis.point(axis.end(first), across),
+                end: axis.point(axis.start(second), across),
+            });
+        }
+    }
+}
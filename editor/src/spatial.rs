@@ -0,0 +1,125 @@
+use crate::geometry::Rect;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+const DEFAULT_CELL_SIZE: f64 = 256.0;
+const MAX_CELLS_PER_ENTRY: i64 = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionMode {
+    Contain,
+    Intersect,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpatialIndex {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<u64>>,
+    oversized: BTreeSet<u64>,
+    bounds: HashMap<u64, Rect>,
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            oversized: BTreeSet::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, id: u64, bounds: Rect) {
+        self.remove(id);
+        let bounds = bounds.normalized();
+        match self.cell_range(&bounds) {
+            Some((min_x, min_y, max_x, max_y)) => {
+                for cell_x in min_x..=max_x {
+                    for cell_y in min_y..=max_y {
+                        self.cells.entry((cell_x, cell_y)).or_default().push(id);
+                    }
+                }
+            }
+            None => {
+                self.oversized.insert(id);
+            }
+        }
+        self.bounds.insert(id, bounds);
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        let Some(bounds) = self.bounds.remove(&id) else {
+            return false;
+        };
+        if self.oversized.remove(&id) {
+            return true;
+        }
+        if let Some((min_x, min_y, max_x, max_y)) = self.cell_range(&bounds) {
+            for cell_x in min_x..=max_x {
+                for cell_y in min_y..=max_y {
+                    if let Some(ids) = self.cells.get_mut(&(cell_x, cell_y)) {
+                        ids.retain(|other| *other != id);
+                        if ids.is_empty() {
+                            self.cells.remove(&(cell_x, cell_y));
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.oversized.clear();
+        self.bounds.clear();
+    }
+
+    pub fn bounds(&self, id: u64) -> Option<Rect> {
+        self.bounds.get(&id).copied()
+    }
+
+    pub fn query(&self, area: Rect) -> Vec<u64> {
+        let area = area.normalized();
+        let mut found: BTreeSet<u64> = self.oversized.iter().copied().collect();
+        match self.cell_range(&area) {
+            Some((min_x, min_y, max_x, max_y)) => {
+                for cell_x in min_x..=max_x {
+                    for cell_y in min_y..=max_y {
+                        if let Some(ids) = self.cells.get(&(cell_x, cell_y)) {
+                            found.extend(ids.iter().copied());
+                        }
+                    }
+                }
+            }
+            None => found.extend(self.bounds.keys().copied()),
+        }
+        found
+            .into_iter()
+            .filter(|id| self.bounds[id].intersects(&area))
+            .collect()
+    }
+
+    fn cell_range(&self, bounds: &Rect) -> Option<(i64, i64, i64, i64)> {
+        if !bounds.x.is_finite()
+            || !bounds.y.is_finite()
+            || !bounds.width.is_finite()
+            || !bounds.height.is_finite()
+        {
+            return None;
+        }
+        let min_x = (bounds.left() / self.cell_size).floor() as i64;
+        let min_y = (bounds.top() / self.cell_size).floor() as i64;
+        let max_x = (bounds.right() / self.cell_size).floor() as i64;
+        let max_y = (bounds.bottom() / self.cell_size).floor() as i64;
+        let cell_count = (max_x - min_x + 1).saturating_mul(max_y - min_y + 1);
+        (cell_count <= MAX_CELLS_PER_ENTRY).then_some((min_x, min_y, max_x, max_y))
+    }
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
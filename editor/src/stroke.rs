@@ -0,0 +1,266 @@
+use crate::geometry::{ellipse_polygon, Point, Vector};
+use serde::{Deserialize, Serialize};
+
+const CATMULL_ROM_SPACING: f64 = 4.0;
+const MAX_CATMULL_ROM_STEPS: usize = 16;
+const CHAIKIN_ITERATIONS: usize = 2;
+const DEFAULT_PRESSURE: f64 = 0.5;
+// sample distance at which simulated pressure bottoms out
+const SIMULATED_MAX_SPEED: f64 = 40.0;
+const SIMULATED_EASING: f64 = 0.35;
+const MIN_PRESSURE: f64 = 0.1;
+const CAP_STEPS: usize = 8;
+const DOT_STEPS: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Smoothing {
+    #[default]
+    None,
+    CatmullRom,
+    Chaikin,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathOptions {
+    // Ramer-Douglas-Peucker tolerance, 0 keeps every sample
+    pub tolerance: f64,
+    pub smoothing: Smoothing,
+    // distance between resampled points, 0 skips resampling
+    pub spacing: f64,
+    // derive pressure from drawing speed when the input device has none
+    pub simulate_pressure: bool,
+}
+
+// anything the pipeline can carry; extra channels are interpolated linearly
+pub trait StrokeSample: Copy {
+    fn position(&self) -> Point;
+    fn interpolate(&self, other: &Self, t: f64, position: Point) -> Self;
+}
+
+impl StrokeSample for Point {
+    fn position(&self) -> Point {
+        *self
+    }
+
+    fn interpolate(&self, _other: &Self, _t: f64, position: Point) -> Self {
+        position
+    }
+}
+
+// a point with its pressure
+impl StrokeSample for (Point, f64) {
+    fn position(&self) -> Point {
+        self.0
+    }
+
+    fn interpolate(&self, other: &Self, t: f64, position: Point) -> Self {
+        (position, self.1 + (other.1 - self.1) * t)
+    }
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1.0,
+            smoothing: Smoothing::None,
+            spacing: 0.0,
+            simulate_pressure: false,
+        }
+    }
+}
+
+pub fn process_path<S: StrokeSample>(points: &[S], options: &PathOptions) -> Vec<S> {
+    let simplified = simplify_path(points, options.tolerance);
+    let smoothed = match options.smoothing {
+        Smoothing::None => simplified,
+        Smoothing::CatmullRom => catmull_rom(&simplified),
+        Smoothing::Chaikin => chaikin(&simplified, CHAIKIN_ITERATIONS),
+    };
+    resample_path(&smoothed, options.spacing)
+}
+
+pub fn simplify_path<S: StrokeSample>(points: &[S], tolerance: f64) -> Vec<S> {
+    if points.len() < 3 || tolerance.is_nan() || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut spans = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = spans.pop() {
+        let mut farthest = None;
+        let mut max_distance = tolerance;
+        for (index, point) in points.iter().enumerate().take(last).skip(first + 1) {
+            let distance = point
+                .position()
+                .distance_to_segment(points[first].position(), points[last].position());
+            if distance > max_distance {
+                max_distance = distance;
+                farthest = Some(index);
+            }
+        }
+        if let Some(index) = farthest {
+            keep[index] = true;
+            spans.push((first, index));
+            spans.push((index, last));
+        }
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(point, keep)| keep.then_some(*point))
+        .collect()
+}
+
+// passes through every input point; steps per span grow with the span length
+pub fn catmull_rom<S: StrokeSample>(points: &[S]) -> Vec<S> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut result = vec![points[0]];
+    for index in 0..points.len() - 1 {
+        let p0 = points[index.saturating_sub(1)].position();
+        let p1 = points[index].position();
+        let p2 = points[index + 1].position();
+        let p3 = points[(index + 2).min(points.len() - 1)].position();
+        let steps = ((p1.distance(p2) / CATMULL_ROM_SPACING).ceil() as usize).clamp(1, MAX_CATMULL_ROM_STEPS);
+        for step in 1..=steps {
+            let t = step as f64 / steps as f64;
+            let t2 = t * t;
+            let t3 = t2 * t;
+            let blend = |a: f64, b: f64, c: f64, d: f64| {
+                0.5 * (2.0 * b + (c - a) * t + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2 + (3.0 * b - a - 3.0 * c + d) * t3)
+            };
+            let position = Point::new(blend(p0.x, p1.x, p2.x, p3.x), blend(p0.y, p1.y, p2.y, p3.y));
+            result.push(points[index].interpolate(&points[index + 1], t, position));
+        }
+    }
+    result
+}
+
+// corner cutting; the first and last points stay in place
+pub fn chaikin<S: StrokeSample>(points: &[S], iterations: usize) -> Vec<S> {
+    let mut current = points.to_vec();
+    for _ in 0..iterations {
+        if current.len() < 3 {
+            break;
+        }
+        let mut next = Vec::with_capacity(current.len() * 2);
+        next.push(current[0]);
+        for pair in current.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            for t in [0.25, 0.75] {
+                next.push(a.interpolate(&b, t, lerp(a.position(), b.position(), t)));
+            }
+        }
+        next.push(current[current.len() - 1]);
+        current = next;
+    }
+    current
+}
+
+pub fn resample_path<S: StrokeSample>(points: &[S], spacing: f64) -> Vec<S> {
+    if points.len() < 2 || spacing.is_nan() || spacing <= 0.0 {
+        return points.to_vec();
+    }
+    let mut result = vec![points[0]];
+    let mut carried = 0.0;
+    for pair in points.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let length = start.position().distance(end.position());
+        let mut travelled = spacing - carried;
+        while travelled <= length {
+            let t = travelled / length;
+            result.push(start.interpolate(&end, t, lerp(start.position(), end.position(), t)));
+            travelled += spacing;
+        }
+        carried = length - (travelled - spacing);
+    }
+    let last = points[points.len() - 1];
+    if result
+        .last()
+        .is_some_and(|point| point.position().distance(last.position()) > f64::EPSILON)
+    {
+        result.push(last);
+    }
+    result
+}
+
+// faster movement between samples gives lighter pressure
+pub fn simulate_pressure(points: &[Point]) -> Vec<f64> {
+    let mut pressure = DEFAULT_PRESSURE;
+    let mut previous = None;
+    points
+        .iter()
+        .map(|point| {
+            if let Some(previous) = previous.replace(*point) {
+                let speed = point.distance(previous) / SIMULATED_MAX_SPEED;
+                let target = (1.0 - speed.min(1.0)).max(MIN_PRESSURE);
+                pressure += (target - pressure) * SIMULATED_EASING;
+            }
+            pressure
+        })
+        .collect()
+}
+
+// full width at pressure 0.5, half at 0 and one and a half at 1
+pub fn pressure_width(line_width: f64, pressure: f64) -> f64 {
+    line_width * (0.5 + pressure.clamp(0.0, 1.0))
+}
+
+// closed polygon around the centerline with round caps; pressures may be empty for a uniform width
+pub fn stroke_outline(points: &[Point], pressures: &[f64], line_width: f64) -> Vec<Point> {
+    let mut samples: Vec<(Point, f64)> = Vec::with_capacity(points.len());
+    for (index, point) in points.iter().enumerate() {
+        let pressure = pressures.get(index).copied().unwrap_or(DEFAULT_PRESSURE);
+        let radius = pressure_width(line_width, pressure) / 2.0;
+        match samples.last_mut() {
+            Some(last) if last.0.distance(*point) <= f64::EPSILON => last.1 = last.1.max(radius),
+            _ => samples.push((*point, radius)),
+        }
+    }
+    match samples.as_slice() {
+        [] => return Vec::new(),
+        [(center, radius)] => return ellipse_polygon(*center, *radius, *radius, DOT_STEPS),
+        _ => {}
+    }
+
+    let normals: Vec<Vector> = (0..samples.len())
+        .map(|index| {
+            let before = samples[index.saturating_sub(1)].0;
+            let after = samples[(index + 1).min(samples.len() - 1)].0;
+            (after - before).normalized().perpendicular()
+        })
+        .collect();
+    let mut left = Vec::with_capacity(samples.len());
+    let mut right = Vec::with_capacity(samples.len());
+    for ((center, radius), normal) in samples.iter().zip(&normals) {
+        left.push(*center + normal.scale(*radius));
+        right.push(*center + normal.scale(-*radius));
+    }
+
+    let (end, end_radius) = samples[samples.len() - 1];
+    let (start, start_radius) = samples[0];
+    let mut outline = left;
+    outline.extend(cap(end, end_radius, normals[normals.len() - 1]));
+    outline.extend(right.into_iter().rev());
+    outline.extend(cap(start, start_radius, -normals[0]));
+    outline
+}
+
+// half circle swept clockwise from the side the normal points to
+fn cap(center: Point, radius: f64, normal: Vector) -> Vec<Point> {
+    let start = normal.y.atan2(normal.x);
+    (1..CAP_STEPS)
+        .map(|step| {
+            let angle = start - std::f64::consts::PI * step as f64 / CAP_STEPS as f64;
+            Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+        })
+        .collect()
+}
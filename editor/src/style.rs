@@ -0,0 +1,563 @@
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StyleError {
+    InvalidColor(String),
+    InvalidDashPattern(String),
+    InvalidFontWeight(String),
+    InvalidTextAlign(String),
+    InvalidFill(String),
+}
+
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StyleError::InvalidColor(value) => write!(
+                f,
+                "invalid color `{}`: expected #rgb, #rgba, #rrggbb, #rrggbbaa, rgb(r, g, b), rgba(r, g, b, a) or a color name",
+                value
+            ),
+            StyleError::InvalidDashPattern(value) => write!(
+                f,
+                "invalid dash pattern `{}`: expected solid, dashed, dotted or a list of non-negative dash and gap lengths",
+                value
+            ),
+            StyleError::InvalidFontWeight(value) => write!(
+                f,
+                "invalid font weight `{}`: expected normal, bold or a multiple of 100 from 100 to 900",
+                value
+            ),
+            StyleError::InvalidTextAlign(value) => {
+                write!(f, "invalid text alignment `{}`: expected left, center or right", value)
+            }
+            StyleError::InvalidFill(reason) => write!(f, "invalid fill: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for StyleError {}
+
+// Stored documents read their style fields leniently: a value written by an older client that
+// the parsers below reject falls back to the field default, so one bad value cannot empty a
+// whole board. API and operation input still goes through the strict parsers.
+pub(crate) fn lenient<'de, D, T>(deserializer: D, fallback: fn() -> T) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(T::deserialize(&value).unwrap_or_else(|err| {
+        tracing::warn!("ignoring stored style value {}: {}", value, err);
+        fallback()
+    }))
+}
+
+// like `lenient` for optional values; null, "" and "none" all mean unset
+pub(crate) fn lenient_option<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    let unset = match &value {
+        serde_json::Value::Null => true,
+        serde_json::Value::String(text) => text.trim().is_empty() || text.trim().eq_ignore_ascii_case("none"),
+        _ => false,
+    };
+    if unset {
+        return Ok(None);
+    }
+    Ok(T::deserialize(&value).map(Some).unwrap_or_else(|err| {
+        tracing::warn!("ignoring stored style value {}: {}", value, err);
+        None
+    }))
+}
+
+// serialized as lowercase hex, with the alpha pair only when it is not opaque
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+const NAMED_COLORS: [(&str, Color); 21] = [
+    ("transparent", Color::rgba(0, 0, 0, 0)),
+    ("black", Color::rgb(0, 0, 0)),
+    ("white", Color::rgb(255, 255, 255)),
+    ("red", Color::rgb(255, 0, 0)),
+    ("green", Color::rgb(0, 128, 0)),
+    ("blue", Color::rgb(0, 0, 255)),
+    ("yellow", Color::rgb(255, 255, 0)),
+    ("cyan", Color::rgb(0, 255, 255)),
+    ("aqua", Color::rgb(0, 255, 255)),
+    ("magenta", Color::rgb(255, 0, 255)),
+    ("fuchsia", Color::rgb(255, 0, 255)),
+    ("gray", Color::rgb(128, 128, 128)),
+    ("grey", Color::rgb(128, 128, 128)),
+    ("silver", Color::rgb(192, 192, 192)),
+    ("maroon", Color::rgb(128, 0, 0)),
+    ("olive", Color::rgb(128, 128, 0)),
+    ("lime", Color::rgb(0, 255, 0)),
+    ("navy", Color::rgb(0, 0, 128)),
+    ("purple", Color::rgb(128, 0, 128)),
+    ("teal", Color::rgb(0, 128, 128)),
+    ("orange", Color::rgb(255, 165, 0)),
+];
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    // hex without the alpha pair, for formats that carry opacity separately
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.a as f64 / 255.0
+    }
+
+    fn parse_hex(digits: &str) -> Option<Self> {
+        if !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+        let short = |index: usize| u8::from_str_radix(&digits[index..index + 1], 16).ok().map(|value| value * 17);
+        let long = |index: usize| u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).ok();
+        match digits.len() {
+            3 => Some(Color::rgb(short(0)?, short(1)?, short(2)?)),
+            4 => Some(Color::rgba(short(0)?, short(1)?, short(2)?, short(3)?)),
+            6 => Some(Color::rgb(long(0)?, long(1)?, long(2)?)),
+            8 => Some(Color::rgba(long(0)?, long(1)?, long(2)?, long(3)?)),
+            _ => None,
+        }
+    }
+
+    // rgb(r, g, b) or rgba(r, g, b, a) with channels from 0 to 255 and alpha from 0 to 1
+    fn parse_function(value: &str) -> Option<Self> {
+        let (name, rest) = value.split_once('(')?;
+        let arguments = rest.strip_suffix(')')?;
+        let parts: Vec<f64> = arguments
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse::<f64>().ok().filter(|number| number.is_finite()))
+            .collect::<Option<_>>()?;
+        let channel = |value: f64| (0.0..=255.0).contains(&value).then(|| value.round() as u8);
+        let alpha = match (name.trim(), parts.len()) {
+            ("rgb", 3) => 255,
+            ("rgba", 4) if (0.0..=1.0).contains(&parts[3]) => (parts[3] * 255.0).round() as u8,
+            _ => return None,
+        };
+        Some(Color::rgba(channel(parts[0])?, channel(parts[1])?, channel(parts[2])?, alpha))
+    }
+}
+
+impl FromStr for Color {
+    type Err = StyleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim().to_ascii_lowercase();
+        let parsed = if let Some(digits) = trimmed.strip_prefix('#') {
+            Color::parse_hex(digits)
+        } else if trimmed.starts_with("rgb") {
+            Color::parse_function(&trimmed)
+        } else {
+            NAMED_COLORS
+                .iter()
+                .find(|(name, _)| *name == trimmed)
+                .map(|(_, color)| *color)
+        };
+        parsed.ok_or_else(|| StyleError::InvalidColor(value.to_string()))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hex())?;
+        if self.a != 255 {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+// named patterns are stored by name; anything else as its dash and gap lengths
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum DashPattern {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    Custom(Vec<f64>),
+}
+
+impl DashPattern {
+    // alternating dash and gap lengths; empty when solid
+    pub fn segments(&self) -> Vec<f64> {
+        match self {
+            DashPattern::Solid => Vec::new(),
+            DashPattern::Dashed => vec![8.0, 4.0],
+            DashPattern::Dotted => vec![2.0, 2.0],
+            DashPattern::Custom(segments) => segments.clone(),
+        }
+    }
+
+    fn custom(segments: Vec<f64>) -> Option<Self> {
+        let valid = !segments.is_empty()
+            && segments.iter().all(|length| length.is_finite() && *length >= 0.0)
+            && segments.iter().any(|length| *length > 0.0);
+        valid.then_some(DashPattern::Custom(segments))
+    }
+}
+
+impl FromStr for DashPattern {
+    type Err = StyleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || StyleError::InvalidDashPattern(value.to_string());
+        match value.trim().to_ascii_lowercase().as_str() {
+            "solid" => Ok(DashPattern::Solid),
+            "dashed" => Ok(DashPattern::Dashed),
+            "dotted" => Ok(DashPattern::Dotted),
+            other => {
+                let segments = other
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|part| !part.is_empty())
+                    .map(|part| part.parse::<f64>().map_err(|_| error()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                DashPattern::custom(segments).ok_or_else(error)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DashPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DashPattern::Solid => write!(f, "solid"),
+            DashPattern::Dashed => write!(f, "dashed"),
+            DashPattern::Dotted => write!(f, "dotted"),
+            DashPattern::Custom(segments) => {
+                let parts: Vec<String> = segments.iter().map(|length| length.to_string()).collect();
+                write!(f, "{}", parts.join(","))
+            }
+        }
+    }
+}
+
+impl Serialize for DashPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DashPattern::Custom(segments) => segments.serialize(serializer),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+struct DashPatternVisitor;
+
+impl<'de> de::Visitor<'de> for DashPatternVisitor {
+    type Value = DashPattern;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "solid, dashed, dotted or a list of dash and gap lengths")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut segments = Vec::new();
+        while let Some(length) = seq.next_element::<f64>()? {
+            segments.push(length);
+        }
+        let description = format!("{:?}", segments);
+        DashPattern::custom(segments).ok_or_else(|| de::Error::custom(StyleError::InvalidDashPattern(description)))
+    }
+}
+
+impl<'de> Deserialize<'de> for DashPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DashPatternVisitor)
+    }
+}
+
+// the CSS numeric weight; normal and bold keep their names when serialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FontWeight(u16);
+
+impl FontWeight {
+    pub const NORMAL: FontWeight = FontWeight(400);
+    pub const BOLD: FontWeight = FontWeight(700);
+
+    pub fn new(weight: u16) -> Result<Self, StyleError> {
+        if (100..=900).contains(&weight) && weight.is_multiple_of(100) {
+            Ok(FontWeight(weight))
+        } else {
+            Err(StyleError::InvalidFontWeight(weight.to_string()))
+        }
+    }
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        FontWeight::NORMAL
+    }
+}
+
+impl FromStr for FontWeight {
+    type Err = StyleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "normal" => Ok(FontWeight::NORMAL),
+            "bold" => Ok(FontWeight::BOLD),
+            other => other
+                .parse::<u16>()
+                .ok()
+                .and_then(|weight| FontWeight::new(weight).ok())
+                .ok_or_else(|| StyleError::InvalidFontWeight(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for FontWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            FontWeight::NORMAL => write!(f, "normal"),
+            FontWeight::BOLD => write!(f, "bold"),
+            FontWeight(weight) => write!(f, "{}", weight),
+        }
+    }
+}
+
+impl Serialize for FontWeight {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct FontWeightVisitor;
+
+impl<'de> de::Visitor<'de> for FontWeightVisitor {
+    type Value = FontWeight;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "normal, bold or a weight from 100 to 900")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        u16::try_from(value)
+            .ok()
+            .and_then(|weight| FontWeight::new(weight).ok())
+            .ok_or_else(|| E::custom(StyleError::InvalidFontWeight(value.to_string())))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        u64::try_from(value)
+            .map_err(|_| E::custom(StyleError::InvalidFontWeight(value.to_string())))
+            .and_then(|weight| self.visit_u64(weight))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        if value.fract() == 0.0 && value >= 0.0 {
+            self.visit_u64(value as u64)
+        } else {
+            Err(E::custom(StyleError::InvalidFontWeight(value.to_string())))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FontWeight {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(FontWeightVisitor)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl FromStr for TextAlign {
+    type Err = StyleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "left" => Ok(TextAlign::Left),
+            "center" => Ok(TextAlign::Center),
+            "right" => Ok(TextAlign::Right),
+            _ => Err(StyleError::InvalidTextAlign(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for TextAlign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextAlign::Left => write!(f, "left"),
+            TextAlign::Center => write!(f, "center"),
+            TextAlign::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl Serialize for TextAlign {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextAlign {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_parse_every_accepted_form() {
+        assert_eq!("#f80".parse(), Ok(Color::rgb(255, 136, 0)));
+        assert_eq!("#f808".parse(), Ok(Color::rgba(255, 136, 0, 136)));
+        assert_eq!(" #FF8800 ".parse(), Ok(Color::rgb(255, 136, 0)));
+        assert_eq!("#ff880080".parse(), Ok(Color::rgba(255, 136, 0, 128)));
+        assert_eq!("rgb(255, 136, 0)".parse(), Ok(Color::rgb(255, 136, 0)));
+        assert_eq!("rgba(255 136 0 0.5)".parse(), Ok(Color::rgba(255, 136, 0, 128)));
+        assert_eq!("Orange".parse(), Ok(Color::rgb(255, 165, 0)));
+        assert_eq!("transparent".parse(), Ok(Color::rgba(0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn colors_reject_malformed_values() {
+        for value in ["", "#ff880", "#ggg", "rgb(256, 0, 0)", "rgba(0, 0, 0, 2)", "rgb(0, 0)", "chartreuse"] {
+            assert_eq!(value.parse::<Color>(), Err(StyleError::InvalidColor(value.to_string())));
+        }
+        let message = "#12".parse::<Color>().unwrap_err().to_string();
+        assert!(message.starts_with("invalid color `#12`: expected #rgb"), "{}", message);
+    }
+
+    #[test]
+    fn colors_round_trip_as_lowercase_hex() {
+        let opaque = Color::rgb(171, 205, 239);
+        assert_eq!(opaque.to_string(), "#abcdef");
+        assert_eq!(opaque.to_string().parse(), Ok(opaque));
+        let translucent = Color::rgba(1, 2, 3, 64);
+        assert_eq!(serde_json::to_string(&translucent).unwrap(), "\"#01020340\"");
+        assert_eq!(serde_json::from_str::<Color>("\"#01020340\"").unwrap(), translucent);
+        let err = serde_json::from_str::<Color>("\"nope\"").unwrap_err();
+        assert!(err.to_string().contains("invalid color `nope`"), "{}", err);
+    }
+
+    #[test]
+    fn dash_patterns_parse_names_and_lengths() {
+        assert_eq!("solid".parse(), Ok(DashPattern::Solid));
+        assert_eq!(" Dashed".parse(), Ok(DashPattern::Dashed));
+        assert_eq!("dotted".parse(), Ok(DashPattern::Dotted));
+        assert_eq!("6, 3 1.5".parse(), Ok(DashPattern::Custom(vec![6.0, 3.0, 1.5])));
+        assert_eq!(DashPattern::Dashed.segments(), vec![8.0, 4.0]);
+        assert!(DashPattern::Solid.segments().is_empty());
+    }
+
+    #[test]
+    fn dash_patterns_reject_empty_negative_and_all_zero_lengths() {
+        for value in ["", "wavy", "4,-2", "0,0", "4,x"] {
+            assert_eq!(value.parse::<DashPattern>(), Err(StyleError::InvalidDashPattern(value.to_string())));
+        }
+        let message = "0,0".parse::<DashPattern>().unwrap_err().to_string();
+        assert!(message.starts_with("invalid dash pattern `0,0`: expected solid"), "{}", message);
+        let err = serde_json::from_str::<DashPattern>("[]").unwrap_err();
+        assert!(err.to_string().contains("invalid dash pattern `[]`"), "{}", err);
+    }
+
+    #[test]
+    fn dash_patterns_round_trip_by_name_or_as_a_list() {
+        assert_eq!(serde_json::to_string(&DashPattern::Dotted).unwrap(), "\"dotted\"");
+        let custom = DashPattern::Custom(vec![5.0, 2.5]);
+        assert_eq!(serde_json::to_string(&custom).unwrap(), "[5.0,2.5]");
+        assert_eq!(serde_json::from_str::<DashPattern>("[5.0,2.5]").unwrap(), custom);
+        assert_eq!(serde_json::from_str::<DashPattern>("\"5,2.5\"").unwrap(), custom);
+        assert_eq!(custom.to_string().parse(), Ok(custom));
+    }
+
+    #[test]
+    fn font_weights_accept_keywords_and_hundreds() {
+        assert_eq!("normal".parse(), Ok(FontWeight::NORMAL));
+        assert_eq!("BOLD".parse(), Ok(FontWeight::BOLD));
+        assert_eq!("300".parse::<FontWeight>().map(|weight| weight.value()), Ok(300));
+        assert_eq!(FontWeight::new(900).map(|weight| weight.value()), Ok(900));
+        assert_eq!(FontWeight::new(450), Err(StyleError::InvalidFontWeight("450".to_string())));
+        for value in ["0", "1000", "250", "heavy", "-100"] {
+            assert_eq!(value.parse::<FontWeight>(), Err(StyleError::InvalidFontWeight(value.to_string())));
+        }
+        let message = "950".parse::<FontWeight>().unwrap_err().to_string();
+        assert_eq!(message, "invalid font weight `950`: expected normal, bold or a multiple of 100 from 100 to 900");
+    }
+
+    #[test]
+    fn font_weights_round_trip_through_names_and_numbers() {
+        assert_eq!(serde_json::to_string(&FontWeight::BOLD).unwrap(), "\"bold\"");
+        let light = FontWeight::new(300).unwrap();
+        assert_eq!(serde_json::to_string(&light).unwrap(), "\"300\"");
+        assert_eq!(serde_json::from_str::<FontWeight>("\"300\"").unwrap(), light);
+        assert_eq!(serde_json::from_str::<FontWeight>("300").unwrap(), light);
+        assert_eq!(serde_json::from_str::<FontWeight>("700.0").unwrap(), FontWeight::BOLD);
+        for value in ["350", "-400", "400.5", "70000"] {
+            let err = serde_json::from_str::<FontWeight>(value).unwrap_err();
+            assert!(err.to_string().contains("invalid font weight"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn text_alignment_round_trips_and_names_the_bad_value() {
+        for align in [TextAlign::Left, TextAlign::Center, TextAlign::Right] {
+            assert_eq!(align.to_string().parse(), Ok(align));
+            let json = serde_json::to_string(&align).unwrap();
+            assert_eq!(serde_json::from_str::<TextAlign>(&json).unwrap(), align);
+        }
+        assert_eq!(" Center ".parse(), Ok(TextAlign::Center));
+        assert_eq!(
+            "justify".parse::<TextAlign>().unwrap_err().to_string(),
+            "invalid text alignment `justify`: expected left, center or right"
+        );
+    }
+}
@@ -0,0 +1,756 @@
+use crate::connector::{ConnectorEnd, Marker};
+use crate::document::Document;
+use crate::elements::{BlendMode, Element, Frame};
+use crate::fill::{Fill, GradientStop, PatternKind};
+use crate::rich_text::is_safe_link;
+use crate::style::{Color, DashPattern};
+use crate::text_layout::{FallbackMetrics, FontMetrics, LayoutFragment};
+use crate::geometry::{Point, Rect};
+use crate::rough::sketch;
+use crate::routing::Routing;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const PADDING: f64 = 20.0;
+const FRAME_TITLE_SIZE: f64 = 14.0;
+const FRAME_TITLE_GAP: f64 = 6.0;
+
+impl Document {
+    // hidden layers are left out and layer opacity is applied
+    pub fn export_svg(&self) -> String {
+        render(self.layered_elements(|_| true), None, self.font_metrics())
+    }
+
+    // the frame and its children, cropped to the frame
+    pub fn export_frame_svg(&self, id: u64) -> Option<String> {
+        let Some(Element::Frame(frame)) = self.get(id) else {
+            return None;
+        };
+        let elements = self.layered_elements(|element| element.id() == id || frame.element_ids.contains(&element.id()));
+        Some(render(elements, Some(frame.rect()), self.font_metrics()))
+    }
+
+    fn layered_elements<F>(&self, include: F) -> Vec<(&Element, f64)>
+    where
+        F: Fn(&Element) -> bool,
+    {
+        self.render_order()
+            .into_iter()
+            .filter_map(|id| self.get(id))
+            .filter(|element| include(element))
+            .map(|element| (element, self.element_layer_opacity(element.id())))
+            .collect()
+    }
+}
+
+pub fn render_svg<'a, I>(elements: I) -> String
+where
+    I: IntoIterator<Item = &'a Element>,
+{
+    let mut elements: Vec<&Element> = elements
+        .into_iter()
+        .filter(|element| element.z_index().is_some())
+        .collect();
+    elements.sort_by_key(|element| element.z_index());
+    render(elements.into_iter().map(|element| (element, 1.0)).collect(), None, &FallbackMetrics)
+}
+
+// elements come in drawing order with their opacity; without an area the drawing is fitted
+// to its content with some padding
+fn render(elements: Vec<(&Element, f64)>, area: Option<Rect>, metrics: &dyn FontMetrics) -> String {
+    let (bounds, padding) = match area {
+        Some(area) => (area, 0.0),
+        None => (
+            elements
+                .iter()
+                .filter_map(|(element, _)| element.bounds())
+                .reduce(|a, b| a.union(&b))
+                .unwrap_or(Rect::new(0.0, 0.0, 100.0, 100.0)),
+            PADDING,
+        ),
+    };
+    let width = bounds.width + padding * 2.0;
+    let height = bounds.height + padding * 2.0;
+
+    // children of clipping frames are drawn through the frame's clip path
+    let mut clips = HashMap::new();
+    for (element, _) in &elements {
+        if let Element::Frame(frame) = element {
+            if frame.clip {
+                for child in &frame.element_ids {
+                    clips.insert(*child, frame.id);
+                }
+            }
+        }
+    }
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = num(width),
+        h = num(height),
+    );
+    let _ = writeln!(svg, r#"<rect width="{}" height="{}" fill="white"/>"#, num(width), num(height));
+    let _ = writeln!(
+        svg,
+        r#"<g transform="translate({},{})">"#,
+        num(padding - bounds.x),
+        num(padding - bounds.y)
+    );
+    let frames: Vec<&Frame> = elements
+        .iter()
+        .filter_map(|(element, _)| match element {
+            Element::Frame(frame) if frame.clip => Some(frame),
+            _ => None,
+        })
+        .collect();
+    let mut defs = String::new();
+    for frame in frames {
+        let rect = frame.rect();
+        let _ = writeln!(
+            defs,
+            r#"<clipPath id="frame-{}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+            frame.id,
+            num(rect.x),
+            num(rect.y),
+            num(rect.width),
+            num(rect.height),
+        );
+    }
+    for (element, _) in &elements {
+        if let Some(fill) = element.fill() {
+            write_fill_def(&mut defs, element.id(), fill);
+        }
+    }
+    if !defs.is_empty() {
+        svg.push_str("<defs>\n");
+        svg.push_str(&defs);
+        svg.push_str("</defs>\n");
+    }
+    for (element, opacity) in elements {
+        let clip = clips.get(&element.id());
+        if let Some(frame) = clip {
+            let _ = writeln!(svg, r#"<g clip-path="url(#frame-{})">"#, frame);
+        }
+        // the layer's opacity and the element's own multiply
+        let opacity = opacity * element.opacity();
+        let blend = match element.blend_mode() {
+            BlendMode::Normal => None,
+            BlendMode::Multiply => Some("multiply"),
+        };
+        let wrapped = opacity < 1.0 || blend.is_some();
+        if wrapped {
+            let mut attrs = String::new();
+            if opacity < 1.0 {
+                let _ = write!(attrs, r#" opacity="{}""#, num(opacity));
+            }
+            if let Some(blend) = blend {
+                let _ = write!(attrs, r#" style="mix-blend-mode:{}""#, blend);
+            }
+            let _ = writeln!(svg, "<g{}>", attrs);
+        }
+        write_element(&mut svg, element, metrics);
+        if wrapped {
+            svg.push_str("</g>\n");
+        }
+        if clip.is_some() {
+            svg.push_str("</g>\n");
+        }
+    }
+    svg.push_str("</g>\n</svg>");
+    svg
+}
+
+fn write_element(svg: &mut String, element: &Element, metrics: &dyn FontMetrics) {
+    match element {
+        Element::Rectangle(rect) => {
+            let bounds = Rect::new(rect.position.x, rect.position.y, rect.width, rect.height).normalized();
+            let radius = rect.border_radius.min(bounds.width / 2.0).min(bounds.height / 2.0);
+            let radius_attr = if radius > 0.0 {
+                format!(r#" rx="{r}" ry="{r}""#, r = num(radius))
+            } else {
+                String::new()
+            };
+            let shape = format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}"{}"#,
+                num(bounds.x),
+                num(bounds.y),
+                num(bounds.width),
+                num(bounds.height),
+                radius_attr,
+            );
+            write_shape(svg, element, &shape, &rect.stroke_color, rect.fill.as_ref(), rect.line_width, &rect.dash_pattern);
+        }
+        Element::Ellipse(ellipse) => {
+            let shape = format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}""#,
+                num(ellipse.position.x),
+                num(ellipse.position.y),
+                num(ellipse.radius_x.abs()),
+                num(ellipse.radius_y.abs()),
+            );
+            write_shape(
+                svg,
+                element,
+                &shape,
+                &ellipse.stroke_color,
+                ellipse.fill.as_ref(),
+                ellipse.line_width,
+                &ellipse.dash_pattern,
+            );
+        }
+        Element::Diamond(diamond) => {
+            let bounds =
+                Rect::new(diamond.position.x, diamond.position.y, diamond.width, diamond.height).normalized();
+            let center = bounds.center();
+            let points = [
+                Point::new(center.x, bounds.top()),
+                Point::new(bounds.right(), center.y),
+                Point::new(center.x, bounds.bottom()),
+                Point::new(bounds.left(), center.y),
+            ];
+            let shape = format!(r#"<polygon points="{}""#, points_attr(&points));
+            write_shape(
+                svg,
+                element,
+                &shape,
+                &diamond.stroke_color,
+                diamond.fill.as_ref(),
+                diamond.line_width,
+                &diamond.dash_pattern,
+            );
+        }
+        Element::Line(line) => write_connector(svg, element, &line.stroke_color, line.line_width, &line.dash_pattern),
+        Element::Arrow(arrow) => {
+            write_connector(svg, element, &arrow.stroke_color, arrow.line_width, &arrow.dash_pattern)
+        }
+        Element::Path(path) => {
+            if path.points.is_empty() {
+                return;
+            }
+            if path.has_pressure() {
+                let outline = path.outline();
+                let _ = writeln!(
+                    svg,
+                    r#"<polygon points="{}"{} stroke="none"{}/>"#,
+                    points_attr(&outline),
+                    paint("fill", &path.stroke_color),
+                    placement(element),
+                );
+                return;
+            }
+            let mut data = String::new();
+            for (index, point) in path.points.iter().enumerate() {
+                let command = if index == 0 { "M" } else { " L" };
+                let _ = write!(data, "{} {} {}", command, num(point.x), num(point.y));
+            }
+            let _ = writeln!(
+                svg,
+                r#"<path d="{}" fill="none"{} stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"{}{}/>"#,
+                data,
+                paint("stroke", &path.stroke_color),
+                num(path.line_width),
+                dash_attr(&path.dash_pattern),
+                placement(element),
+            );
+        }
+        Element::Image(image) => {
+            let bounds = Rect::new(image.position.x, image.position.y, image.width, image.height).normalized();
+            let _ = writeln!(
+                svg,
+                r#"<image x="{}" y="{}" width="{}" height="{}" href="{}"{}/>"#,
+                num(bounds.x),
+                num(bounds.y),
+                num(bounds.width),
+                num(bounds.height),
+                escape_xml(&image.image_data),
+                placement(element),
+            );
+        }
+        Element::Text(text) => {
+            // every fragment is placed where the layout put it, so wrapping and alignment match
+            // the canvas exactly
+            let layout = text.layout(metrics);
+            let _ = writeln!(
+                svg,
+                r#"<text xml:space="preserve" font-family="{}" font-size="{}" font-weight="{}"{}{}>"#,
+                escape_xml(&text.font_family),
+                num(text.font_size),
+                text.font_weight,
+                paint("fill", &text.color),
+                placement(element),
+            );
+            for line in &layout.lines {
+                if let Some(marker) = &line.marker {
+                    let _ = writeln!(
+                        svg,
+                        r#"  <tspan x="{}" y="{}">{}</tspan>"#,
+                        num(line.x - line.marker_width),
+                        num(line.baseline),
+                        escape_xml(marker)
+                    );
+                }
+                for fragment in &line.fragments {
+                    write_fragment(svg, Point::new(line.x + fragment.x, line.baseline), fragment);
+                }
+            }
+            svg.push_str("</text>\n");
+        }
+        Element::Polygon(polygon) => write_polygon(
+            svg,
+            element,
+            shape_style(
+                &polygon.stroke_color,
+                &fill_attr(polygon.id, polygon.fill.as_ref()),
+                polygon.line_width,
+                &polygon.dash_pattern,
+            ),
+        ),
+        Element::RegularPolygon(polygon) => write_polygon(
+            svg,
+            element,
+            shape_style(
+                &polygon.stroke_color,
+                &fill_attr(polygon.id, polygon.fill.as_ref()),
+                polygon.line_width,
+                &polygon.dash_pattern,
+            ),
+        ),
+        Element::Star(star) => write_polygon(
+            svg,
+            element,
+            shape_style(&star.stroke_color, &fill_attr(star.id, star.fill.as_ref()), star.line_width, &star.dash_pattern),
+        ),
+        Element::Frame(frame) => {
+            let rect = frame.rect();
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}"{}>{}</text>"#,
+                num(rect.x),
+                num(rect.y - FRAME_TITLE_GAP),
+                num(FRAME_TITLE_SIZE),
+                paint("fill", &frame.stroke_color),
+                escape_xml(&frame.name),
+            );
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+                num(rect.x),
+                num(rect.y),
+                num(rect.width),
+                num(rect.height),
+                shape_style(
+                    &frame.stroke_color,
+                    &fill_attr(frame.id, frame.fill_color.map(Fill::Solid).as_ref()),
+                    frame.line_width,
+                    &DashPattern::Solid,
+                ),
+            );
+        }
+        Element::Group(_) | Element::Layer(_) => {}
+    }
+}
+
+// `shape` is an unclosed tag carrying only geometry; sketched shapes swap the outline for their
+// rough strokes and keep the clean shape underneath only to carry a gradient or pattern
+fn write_shape(
+    svg: &mut String,
+    element: &Element,
+    shape: &str,
+    stroke_color: &Color,
+    fill: Option<&Fill>,
+    line_width: f64,
+    dash_pattern: &DashPattern,
+) {
+    let Some(sketch) = sketch(element) else {
+        let _ = writeln!(
+            svg,
+            "{}{}{}/>",
+            shape,
+            shape_style(stroke_color, &fill_attr(element.id(), fill), line_width, dash_pattern),
+            placement(element),
+        );
+        return;
+    };
+    let _ = writeln!(svg, "<g{}>", placement(element));
+    match (fill, &sketch.hachure) {
+        (Some(Fill::Solid(color)), Some(hachure)) => {
+            let _ = writeln!(
+                svg,
+                r#"<path d="{}" fill="none"{} stroke-width="{}" stroke-linecap="round"/>"#,
+                hachure,
+                paint("stroke", color),
+                num(line_width / 2.0),
+            );
+        }
+        (Some(fill), _) => {
+            let _ = writeln!(svg, r#"{}{} stroke="none"/>"#, shape, fill_attr(element.id(), Some(fill)));
+        }
+        (None, _) => {}
+    }
+    let _ = writeln!(
+        svg,
+        r#"<path d="{}" fill="none"{} stroke-width="{}" stroke-linecap="round"{}/>"#,
+        sketch.stroke,
+        paint("stroke", stroke_color),
+        num(line_width),
+        dash_attr(dash_pattern),
+    );
+    svg.push_str("</g>\n");
+}
+
+fn write_fragment(svg: &mut String, at: Point, fragment: &LayoutFragment) {
+    let style = &fragment.style;
+    let mut attrs = String::new();
+    if style.bold {
+        attrs.push_str(r#" font-weight="bold""#);
+    }
+    if style.italic {
+        attrs.push_str(r#" font-style="italic""#);
+    }
+    let decorations: Vec<&str> = [(style.underline, "underline"), (style.strikethrough, "line-through")]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+    if !decorations.is_empty() {
+        let _ = write!(attrs, r#" text-decoration="{}""#, decorations.join(" "));
+    }
+    if let Some(color) = &style.color {
+        attrs.push_str(&paint("fill", color));
+    }
+    if let Some(size) = style.font_size {
+        let _ = write!(attrs, r#" font-size="{}""#, num(size));
+    }
+    let tspan = format!(
+        r#"<tspan x="{}" y="{}"{}>{}</tspan>"#,
+        num(at.x),
+        num(at.y),
+        attrs,
+        escape_xml(&fragment.text)
+    );
+    // documents can arrive with links that never went through a style patch
+    match &style.link {
+        Some(link) if is_safe_link(link) => {
+            let _ = writeln!(svg, r#"  <a href="{}">{}</a>"#, escape_xml(link), tspan);
+        }
+        _ => {
+            let _ = writeln!(svg, "  {}", tspan);
+        }
+    }
+}
+
+// the outline is already rotated, so no transform attribute is needed
+fn write_polygon(svg: &mut String, element: &Element, style: String) {
+    let Some(outline) = element.outline() else {
+        return;
+    };
+    let _ = writeln!(svg, r#"<polygon points="{}"{}/>"#, points_attr(&outline), style);
+}
+
+fn write_connector(
+    svg: &mut String,
+    element: &Element,
+    stroke_color: &Color,
+    line_width: f64,
+    dash_pattern: &DashPattern,
+) {
+    let points = element.connector_points();
+    if points.len() < 2 {
+        return;
+    }
+    let stroke = format!(
+        r#"{} stroke-width="{}"{}"#,
+        paint("stroke", stroke_color),
+        num(line_width),
+        dash_attr(dash_pattern)
+    );
+    let controls = element.control_points();
+    match (element.routing(), points.as_slice()) {
+        _ if element.roughness() > 0.0 => {
+            if let Some(sketch) = sketch(element) {
+                let _ = writeln!(svg, r#"<path d="{}" fill="none" stroke-linecap="round"{}/>"#, sketch.stroke, stroke);
+            }
+        }
+        _ if !controls.is_empty() => {
+            let (start, end) = (points[0], points[points.len() - 1]);
+            let command = if controls.len() == 1 { "Q" } else { "C" };
+            let mut data = format!("M {} {} {}", num(start.x), num(start.y), command);
+            for point in controls.iter().chain([&end]) {
+                let _ = write!(data, " {} {}", num(point.x), num(point.y));
+            }
+            let _ = writeln!(svg, r#"<path d="{}" fill="none"{}/>"#, data, stroke);
+        }
+        (_, [start, end]) => {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}/>"#,
+                num(start.x),
+                num(start.y),
+                num(end.x),
+                num(end.y),
+                stroke
+            );
+        }
+        (Some(Routing::Curved), _) => {
+            let mut data = format!("M {} {}", num(points[0].x), num(points[0].y));
+            for pair in points[1..].windows(2) {
+                let middle = Point::new((pair[0].x + pair[1].x) / 2.0, (pair[0].y + pair[1].y) / 2.0);
+                let _ = write!(
+                    data,
+                    " Q {} {} {} {}",
+                    num(pair[0].x),
+                    num(pair[0].y),
+                    num(middle.x),
+                    num(middle.y)
+                );
+            }
+            let last = points[points.len() - 1];
+            let _ = write!(data, " L {} {}", num(last.x), num(last.y));
+            let _ = writeln!(svg, r#"<path d="{}" fill="none"{}/>"#, data, stroke);
+        }
+        _ => {
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke-linejoin="round"{}/>"#,
+                points_attr(&points),
+                stroke
+            );
+        }
+    }
+
+    let size = element.marker_size().unwrap_or(10.0);
+    let last = points.len() - 1;
+    write_marker(svg, element.marker(ConnectorEnd::Start), points[0], points[1], size, stroke_color, line_width);
+    write_marker(svg, element.marker(ConnectorEnd::End), points[last], points[last - 1], size, stroke_color, line_width);
+}
+
+// draws `marker` with its tip at `tip`, pointing away from `from`
+fn write_marker(svg: &mut String, marker: Marker, tip: Point, from: Point, size: f64, color: &Color, line_width: f64) {
+    if marker == Marker::None {
+        return;
+    }
+    let angle = (tip.y - from.y).atan2(tip.x - from.x);
+    let (fill, stroke) = (paint("fill", color), paint("stroke", color));
+    let (back, spread) = (size * (std::f64::consts::PI / 6.0).cos(), size * 0.5);
+    let shape = match marker {
+        Marker::None => return,
+        Marker::Triangle => format!(
+            r#"<polygon points="0,0 {b},{s} {b},{n}"{fill}{stroke} stroke-width="{w}" stroke-linejoin="round"/>"#,
+            b = num(-back),
+            s = num(-spread),
+            n = num(spread),
+            w = num(line_width),
+        ),
+        Marker::OpenTriangle => format!(
+            r#"<polyline points="{b},{s} 0,0 {b},{n}" fill="none"{stroke} stroke-width="{w}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+            b = num(-back),
+            s = num(-spread),
+            n = num(spread),
+            w = num(line_width),
+        ),
+        Marker::Circle => format!(
+            r#"<circle cx="{x}" cy="0" r="{r}"{fill}{stroke} stroke-width="{w}"/>"#,
+            x = num(-size / 2.0),
+            r = num(size / 2.0),
+            w = num(line_width),
+        ),
+        Marker::Diamond => format!(
+            r#"<polygon points="0,0 {h},{s} {f},0 {h},{n}"{fill}{stroke} stroke-width="{w}" stroke-linejoin="round"/>"#,
+            h = num(-size / 2.0),
+            f = num(-size),
+            s = num(-size / 3.0),
+            n = num(size / 3.0),
+            w = num(line_width),
+        ),
+        Marker::Bar => format!(
+            r#"<line x1="{x}" y1="{s}" x2="{x}" y2="{n}"{stroke} stroke-width="{w}" stroke-linecap="round"/>"#,
+            x = num(-size / 2.0),
+            s = num(-spread),
+            n = num(spread),
+            w = num(line_width),
+        ),
+        Marker::CrowsFoot => format!(
+            r#"<path d="M {f} 0 L 0 {s} M {f} 0 L 0 0 M {f} 0 L 0 {n}" fill="none"{stroke} stroke-width="{w}" stroke-linecap="round"/>"#,
+            f = num(-size),
+            s = num(-spread),
+            n = num(spread),
+            w = num(line_width),
+        ),
+    };
+    let _ = writeln!(
+        svg,
+        r#"<g transform="translate({},{}) rotate({})">{}</g>"#,
+        num(tip.x),
+        num(tip.y),
+        num(angle.to_degrees()),
+        shape
+    );
+}
+
+fn shape_style(stroke_color: &Color, fill: &str, line_width: f64, dash_pattern: &DashPattern) -> String {
+    format!(
+        r#"{}{} stroke-width="{}"{}"#,
+        fill,
+        paint("stroke", stroke_color),
+        num(line_width),
+        dash_attr(dash_pattern)
+    )
+}
+
+// gradients and patterns point at the definition written for the element by write_fill_def
+fn fill_attr(id: u64, fill: Option<&Fill>) -> String {
+    match fill {
+        None => r#" fill="none""#.to_string(),
+        Some(Fill::Solid(color)) => paint("fill", color),
+        Some(_) => format!(r#" fill="url(#fill-{})""#, id),
+    }
+}
+
+// gradients are laid out in the shape's own box; patterns repeat in document units so the
+// spacing stays the same on every shape
+fn write_fill_def(defs: &mut String, id: u64, fill: &Fill) {
+    match fill {
+        Fill::Solid(_) => {}
+        Fill::LinearGradient(gradient) => {
+            let (dy, dx) = gradient.angle.sin_cos();
+            let _ = writeln!(
+                defs,
+                r#"<linearGradient id="fill-{}" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+                id,
+                num(0.5 - dx / 2.0),
+                num(0.5 - dy / 2.0),
+                num(0.5 + dx / 2.0),
+                num(0.5 + dy / 2.0),
+            );
+            write_stops(defs, &gradient.stops);
+            defs.push_str("</linearGradient>\n");
+        }
+        Fill::RadialGradient(gradient) => {
+            let _ = writeln!(
+                defs,
+                r#"<radialGradient id="fill-{}" cx="{}" cy="{}" r="{}">"#,
+                id,
+                num(gradient.center.x),
+                num(gradient.center.y),
+                num(gradient.radius),
+            );
+            write_stops(defs, &gradient.stops);
+            defs.push_str("</radialGradient>\n");
+        }
+        Fill::Pattern(pattern) => {
+            let size = pattern.spacing;
+            let half = num(size / 2.0);
+            let _ = write!(
+                defs,
+                r#"<pattern id="fill-{}" patternUnits="userSpaceOnUse" width="{s}" height="{s}" patternTransform="rotate({})">"#,
+                id,
+                num(pattern.angle.to_degrees()),
+                s = num(size),
+            );
+            if let Some(background) = &pattern.background {
+                let _ = write!(defs, r#"<rect width="{s}" height="{s}"{}/>"#, paint("fill", background), s = num(size));
+            }
+            let stroke = format!(r#"{} stroke-width="{}""#, paint("stroke", &pattern.color), num(pattern.line_width));
+            match pattern.kind {
+                PatternKind::Hatch | PatternKind::CrossHatch => {
+                    let _ = write!(defs, r#"<line x1="{half}" y1="0" x2="{half}" y2="{}"{stroke}/>"#, num(size));
+                    if pattern.kind == PatternKind::CrossHatch {
+                        let _ = write!(defs, r#"<line x1="0" y1="{half}" x2="{}" y2="{half}"{stroke}/>"#, num(size));
+                    }
+                }
+                PatternKind::Dots => {
+                    let _ = write!(
+                        defs,
+                        r#"<circle cx="{half}" cy="{half}" r="{}"{}/>"#,
+                        num(pattern.line_width),
+                        paint("fill", &pattern.color),
+                    );
+                }
+            }
+            defs.push_str("</pattern>\n");
+        }
+    }
+}
+
+fn write_stops(defs: &mut String, stops: &[GradientStop]) {
+    for stop in stops {
+        let color = if stop.color.a == 255 {
+            format!(r#" stop-color="{}""#, stop.color.hex())
+        } else {
+            format!(r#" stop-color="{}" stop-opacity="{}""#, stop.color.hex(), num(stop.color.alpha()))
+        };
+        let _ = writeln!(defs, r#"<stop offset="{}"{}/>"#, num(stop.offset), color);
+    }
+}
+
+// translucent colors get a separate opacity attribute, which SVG 1.1 readers understand
+fn paint(attribute: &str, color: &Color) -> String {
+    if color.a == 255 {
+        format!(r#" {}="{}""#, attribute, color.hex())
+    } else {
+        format!(r#" {a}="{}" {a}-opacity="{}""#, color.hex(), num(color.alpha()), a = attribute)
+    }
+}
+
+fn dash_attr(dash_pattern: &DashPattern) -> String {
+    let segments = dash_pattern.segments();
+    if segments.is_empty() {
+        return String::new();
+    }
+    let lengths: Vec<String> = segments.into_iter().map(num).collect();
+    format!(r#" stroke-dasharray="{}""#, lengths.join(" "))
+}
+
+// plain rotations keep the short form
+fn placement(element: &Element) -> String {
+    match element.local_frame() {
+        Some((frame, angle)) if element.skew_transform().is_identity() => rotation(angle, frame.center()),
+        Some(_) => {
+            let transform = element.transform();
+            format!(
+                r#" transform="matrix({} {} {} {} {} {})""#,
+                num(transform.a),
+                num(transform.b),
+                num(transform.c),
+                num(transform.d),
+                num(transform.e),
+                num(transform.f)
+            )
+        }
+        None => String::new(),
+    }
+}
+
+fn rotation(angle: f64, center: Point) -> String {
+    if angle == 0.0 {
+        return String::new();
+    }
+    format!(
+        r#" transform="rotate({} {} {})""#,
+        num(angle.to_degrees()),
+        num(center.x),
+        num(center.y)
+    )
+}
+
+fn points_attr(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{},{}", num(point.x), num(point.y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub(crate) fn num(value: f64) -> String {
+    let rounded = (value * 1000.0).round() / 1000.0 + 0.0;
+    format!("{}", rounded)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
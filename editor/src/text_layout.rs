@@ -0,0 +1,556 @@
+use crate::elements::Text;
+use crate::geometry::{Point, Rect};
+use crate::rich_text::{TextRun, TextStyle};
+use crate::style::TextAlign;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// line height as a multiple of the font size
+pub const LINE_HEIGHT: f64 = 1.1;
+// room beside the widest line so the caret is never clipped
+pub const TEXT_PADDING: f64 = 4.0;
+// distance from the top of a line box to the baseline, as a multiple of the font size
+const ASCENT: f64 = 0.9;
+const BOLD_WEIGHT: u16 = 600;
+
+const ASCII_COUNT: usize = 95;
+
+// advance widths in thousandths of an em for the printable ASCII range, from the standard
+// Helvetica, Helvetica Bold and Times metrics; Arial and most system sans fonts match the first
+const SANS_WIDTHS: [u16; ASCII_COUNT] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+const SANS_BOLD_WIDTHS: [u16; ASCII_COUNT] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+const SERIF_WIDTHS: [u16; ASCII_COUNT] = [
+    250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444,
+    921, 722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889, 722, 722,
+    556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611, 333, 278, 333, 469, 500,
+    333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500,
+    500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541,
+];
+
+const MONO_WIDTH: f64 = 0.6;
+const WIDE_WIDTH: f64 = 1.0;
+// bold serif faces run a little wider than the regular table
+const SERIF_BOLD_SCALE: f64 = 1.05;
+
+// how the box of a text follows its content
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextSizing {
+    // one line per paragraph, the box fits the widest line
+    #[default]
+    AutoWidth,
+    // wraps at the box width, the height follows the content
+    AutoHeight,
+    // wraps at the box width and keeps the box height, placing the content by vertical_align
+    Fixed,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Font<'a> {
+    pub family: &'a str,
+    pub size: f64,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+// where text widths come from; the browser measures with its real fonts, everything else can
+// use FallbackMetrics
+pub trait FontMetrics: Send + Sync {
+    // advance width of `text` set on a single line
+    fn measure(&self, text: &str, font: &Font) -> f64;
+}
+
+// fixed per-character widths for the common font families, used where no real font is at hand;
+// unknown families are measured as sans
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FallbackMetrics;
+
+// widths measured elsewhere, such as by the browser, for the printable ASCII range of each
+// font; fonts and characters that were not measured fall back to FallbackMetrics
+#[derive(Clone, Debug, Default)]
+pub struct MeasuredMetrics {
+    fonts: HashMap<(String, bool, bool), Vec<f64>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FontClass {
+    Sans,
+    Serif,
+    Mono,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TextLayout {
+    // the box the lines were placed in
+    pub frame: Rect,
+    // size of the content itself, which may overflow a fixed frame
+    pub content_width: f64,
+    pub content_height: f64,
+    pub lines: Vec<LayoutLine>,
+}
+
+// positions are in document space, in the element's unrotated frame
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LayoutLine {
+    // characters of `content` on this line; a wrapped line keeps its trailing spaces, a broken
+    // one stops before the newline
+    pub start: usize,
+    pub end: usize,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub baseline: f64,
+    // list marker drawn left of `x`
+    pub marker: Option<String>,
+    pub marker_width: f64,
+    pub fragments: Vec<LayoutFragment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LayoutFragment {
+    pub text: String,
+    pub start: usize,
+    pub x: f64,
+    pub width: f64,
+    pub font_size: f64,
+    pub bold: bool,
+    #[serde(flatten)]
+    pub style: TextStyle,
+}
+
+// a word, a run of spaces, or part of either where the style changes
+#[derive(Clone, Debug)]
+struct Piece {
+    text: String,
+    start: usize,
+    width: f64,
+    space: bool,
+    style: TextStyle,
+}
+
+impl Text {
+    pub fn font<'a>(&'a self, style: &TextStyle) -> Font<'a> {
+        Font {
+            family: &self.font_family,
+            size: style.font_size.unwrap_or(self.font_size),
+            bold: style.bold || self.font_weight.value() >= BOLD_WEIGHT,
+            italic: style.italic,
+        }
+    }
+
+    // the layout of the text in its own box
+    pub fn layout(&self, metrics: &dyn FontMetrics) -> TextLayout {
+        let frame = Rect::new(self.position.x, self.position.y, self.width, self.height).normalized();
+        let wrap_width = (self.sizing != TextSizing::AutoWidth).then_some(frame.width);
+        let (lines, content_width, content_height) = break_text(self, metrics, wrap_width);
+        let frame = match self.sizing {
+            TextSizing::AutoWidth => Rect::new(frame.x, frame.y, content_width + TEXT_PADDING, content_height),
+            TextSizing::AutoHeight => Rect::new(frame.x, frame.y, frame.width, content_height),
+            TextSizing::Fixed => frame,
+        };
+        position(self.text_align, self.vertical_align, lines, content_width, content_height, frame)
+    }
+
+    // resizes the box to the content as the sizing mode asks; true when anything changed
+    pub fn reflow(&mut self, metrics: &dyn FontMetrics) -> bool {
+        if self.sizing == TextSizing::Fixed {
+            return false;
+        }
+        let frame = self.layout(metrics).frame;
+        let changed = frame.width != self.width || frame.height != self.height;
+        self.width = frame.width;
+        self.height = frame.height;
+        changed
+    }
+
+    // the caret offset nearest to a document point in the unrotated frame
+    pub fn offset_at(&self, layout: &TextLayout, point: Point, metrics: &dyn FontMetrics) -> usize {
+        let Some(line) = layout
+            .lines
+            .iter()
+            .find(|line| point.y < line.y + line.height)
+            .or(layout.lines.last())
+        else {
+            return 0;
+        };
+        let mut best = (line.start, (point.x - line.x).abs());
+        for fragment in &line.fragments {
+            let mut prefix = String::new();
+            for (index, ch) in fragment.text.chars().enumerate() {
+                prefix.push(ch);
+                let x = line.x + fragment.x + metrics.measure(&prefix, &self.font(&fragment.style));
+                let distance = (point.x - x).abs();
+                if distance < best.1 {
+                    best = (fragment.start + index + 1, distance);
+                }
+            }
+        }
+        best.0
+    }
+}
+
+impl FontMetrics for FallbackMetrics {
+    fn measure(&self, text: &str, font: &Font) -> f64 {
+        let class = font_class(font.family);
+        let ems: f64 = text.chars().map(|ch| char_width(ch, class, font.bold)).sum();
+        ems * font.size
+    }
+}
+
+impl MeasuredMetrics {
+    // `widths` are in ems, one for each character from space to tilde
+    pub fn insert(&mut self, family: &str, bold: bool, italic: bool, widths: Vec<f64>) -> bool {
+        if widths.len() != ASCII_COUNT || widths.iter().any(|width| !width.is_finite() || *width < 0.0) {
+            return false;
+        }
+        self.fonts.insert((family.to_ascii_lowercase(), bold, italic), widths);
+        true
+    }
+}
+
+impl FontMetrics for MeasuredMetrics {
+    fn measure(&self, text: &str, font: &Font) -> f64 {
+        let Some(widths) = self.fonts.get(&(font.family.to_ascii_lowercase(), font.bold, font.italic)) else {
+            return FallbackMetrics.measure(text, font);
+        };
+        let class = font_class(font.family);
+        let ems: f64 = text
+            .chars()
+            .map(|ch| match ch {
+                ' '..='~' => widths[ch as usize - ' ' as usize],
+                _ => char_width(ch, class, font.bold),
+            })
+            .sum();
+        ems * font.size
+    }
+}
+
+fn font_class(family: &str) -> FontClass {
+    let family = family.split(',').next().unwrap_or_default().trim().trim_matches(['"', '\'']).to_ascii_lowercase();
+    if ["mono", "courier", "consolas", "menlo", "code"].iter().any(|name| family.contains(name)) {
+        FontClass::Mono
+    } else if family == "serif" || ["times", "georgia", "garamond", "cambria", "palatino"].iter().any(|name| family.contains(name)) {
+        FontClass::Serif
+    } else {
+        FontClass::Sans
+    }
+}
+
+// in ems
+fn char_width(ch: char, class: FontClass, bold: bool) -> f64 {
+    if is_wide(ch) {
+        return WIDE_WIDTH;
+    }
+    if class == FontClass::Mono {
+        return MONO_WIDTH;
+    }
+    let table = match (class, bold) {
+        (FontClass::Serif, _) => &SERIF_WIDTHS,
+        (_, true) => &SANS_BOLD_WIDTHS,
+        _ => &SANS_WIDTHS,
+    };
+    let scale = if class == FontClass::Serif && bold { SERIF_BOLD_SCALE } else { 1.0 };
+    // anything outside ASCII is taken to be as wide as a lowercase n
+    let index = match ch {
+        ' '..='~' => ch as usize - ' ' as usize,
+        '\t' => 0,
+        _ => 'n' as usize - ' ' as usize,
+    };
+    table[index] as f64 / 1000.0 * scale
+}
+
+// CJK, Hangul, full-width forms and emoji take a whole em
+fn is_wide(ch: char) -> bool {
+    matches!(ch as u32,
+        0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6 | 0x1F300..=0x1FAFF | 0x20000..=0x3FFFD)
+}
+
+// lays the text out in a frame other than its own box, wrapping at the frame width; used for
+// text carried by another element, such as a label inside a shape
+pub fn layout_text(text: &Text, metrics: &dyn FontMetrics, frame: Rect, vertical_align: VerticalAlign) -> TextLayout {
+    let (lines, content_width, content_height) = break_text(text, metrics, Some(frame.width));
+    position(text.text_align, vertical_align, lines, content_width, content_height, frame)
+}
+
+// every line with its fragments measured, but not yet placed
+fn break_text(text: &Text, metrics: &dyn FontMetrics, wrap_width: Option<f64>) -> (Vec<LayoutLine>, f64, f64) {
+    let wrap_width = wrap_width.map(|width| (width - TEXT_PADDING).max(0.0));
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for (index, runs) in text.lines().into_iter().enumerate() {
+        let length: usize = runs.iter().map(|run| run.text.chars().count()).sum();
+        let marker = text.list_marker(index).map(|marker| format!("{} ", marker));
+        let marker_width = marker
+            .as_ref()
+            .map_or(0.0, |marker| metrics.measure(marker, &text.font(&TextStyle::default())));
+        let pieces = pieces(text, metrics, &runs, offset);
+        let limit = wrap_width.map(|width| (width - marker_width).max(0.0));
+        for (number, (start, end, pieces)) in break_lines(text, metrics, pieces, offset, offset + length, limit)
+            .into_iter()
+            .enumerate()
+        {
+            // wrapped list lines are indented past the marker
+            let marker = marker.clone().filter(|_| number == 0);
+            lines.push(build_line(text, start, end, pieces, marker, marker_width));
+        }
+        offset += length + 1;
+    }
+    let content_width = lines.iter().map(|line| line.width + line.marker_width).fold(0.0, f64::max);
+    let content_height = lines.iter().map(|line| line.height).sum();
+    (lines, content_width, content_height)
+}
+
+fn position(
+    text_align: TextAlign,
+    vertical_align: VerticalAlign,
+    mut lines: Vec<LayoutLine>,
+    content_width: f64,
+    content_height: f64,
+    frame: Rect,
+) -> TextLayout {
+    let mut y = frame.y
+        + match vertical_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => (frame.height - content_height) / 2.0,
+            VerticalAlign::Bottom => frame.height - content_height,
+        };
+    for line in &mut lines {
+        let width = line.width + line.marker_width;
+        let x = match text_align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => (frame.width - width) / 2.0,
+            TextAlign::Right => frame.width - width,
+        };
+        line.x = frame.x + x + line.marker_width;
+        line.y = y;
+        line.baseline += y;
+        y += line.height;
+    }
+    TextLayout {
+        frame,
+        content_width,
+        content_height,
+        lines,
+    }
+}
+
+// splits the runs of one paragraph into words and spaces, measuring each
+fn pieces(text: &Text, metrics: &dyn FontMetrics, runs: &[TextRun], mut offset: usize) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    for run in runs {
+        let mut current = String::new();
+        let mut start = offset;
+        let mut space = false;
+        for ch in run.text.chars() {
+            if !current.is_empty() && ch.is_whitespace() != space {
+                pieces.push(measured(text, metrics, std::mem::take(&mut current), start, space, &run.style));
+                start = offset;
+            }
+            space = ch.is_whitespace();
+            current.push(ch);
+            offset += 1;
+        }
+        if !current.is_empty() {
+            pieces.push(measured(text, metrics, current, start, space, &run.style));
+        }
+    }
+    pieces
+}
+
+fn measured(text: &Text, metrics: &dyn FontMetrics, content: String, start: usize, space: bool, style: &TextStyle) -> Piece {
+    Piece {
+        width: metrics.measure(&content, &text.font(style)),
+        text: content,
+        start,
+        space,
+        style: style.clone(),
+    }
+}
+
+// greedy line filling: a word that does not fit moves to the next line, and a word wider than
+// a whole line is broken between characters
+fn break_lines(
+    text: &Text,
+    metrics: &dyn FontMetrics,
+    pieces: Vec<Piece>,
+    start: usize,
+    end: usize,
+    wrap_width: Option<f64>,
+) -> Vec<(usize, usize, Vec<Piece>)> {
+    let Some(limit) = wrap_width else {
+        return vec![(start, end, pieces)];
+    };
+    let mut lines = Vec::new();
+    let mut line: Vec<Piece> = Vec::new();
+    let mut line_start = start;
+    let mut index = 0;
+    while index < pieces.len() {
+        // a word is every non-space piece up to the next space, whatever their styles
+        let word_end = if pieces[index].space {
+            index + 1
+        } else {
+            (index..pieces.len()).find(|i| pieces[*i].space).unwrap_or(pieces.len())
+        };
+        let word = &pieces[index..word_end];
+        let word_width: f64 = word.iter().map(|piece| piece.width).sum();
+        if !word[0].space && has_word(&line) && visible_width(&line) + trailing_space(&line) + word_width > limit {
+            let next = word[0].start;
+            lines.push((line_start, next, std::mem::take(&mut line)));
+            line_start = next;
+        }
+        if !word[0].space && word_width > limit && !has_word(&line) {
+            for piece in word {
+                for (offset, ch) in piece.text.chars().enumerate().map(|(i, ch)| (piece.start + i, ch)) {
+                    let glyph = measured(text, metrics, ch.to_string(), offset, false, &piece.style);
+                    if has_word(&line) && visible_width(&line) + trailing_space(&line) + glyph.width > limit {
+                        lines.push((line_start, offset, std::mem::take(&mut line)));
+                        line_start = offset;
+                    }
+                    line.push(glyph);
+                }
+            }
+        } else {
+            line.extend_from_slice(word);
+        }
+        index = word_end;
+    }
+    lines.push((line_start, end, line));
+    lines
+}
+
+fn has_word(line: &[Piece]) -> bool {
+    line.iter().any(|piece| !piece.space)
+}
+
+// width without the trailing spaces, which hang past the wrap edge
+fn visible_width(line: &[Piece]) -> f64 {
+    let trailing = line.iter().rev().take_while(|piece| piece.space).count();
+    line[..line.len() - trailing].iter().map(|piece| piece.width).sum()
+}
+
+fn trailing_space(line: &[Piece]) -> f64 {
+    line.iter().rev().take_while(|piece| piece.space).map(|piece| piece.width).sum()
+}
+
+// merges neighbouring pieces of the same style into fragments placed from x = 0
+fn build_line(text: &Text, start: usize, end: usize, pieces: Vec<Piece>, marker: Option<String>, marker_width: f64) -> LayoutLine {
+    let width = visible_width(&pieces);
+    let mut fragments: Vec<LayoutFragment> = Vec::new();
+    let mut x = 0.0;
+    for piece in pieces {
+        match fragments.last_mut() {
+            Some(last) if last.style == piece.style => {
+                last.text.push_str(&piece.text);
+                last.width += piece.width;
+            }
+            _ => {
+                let font = text.font(&piece.style);
+                fragments.push(LayoutFragment {
+                    text: piece.text,
+                    start: piece.start,
+                    x,
+                    width: piece.width,
+                    font_size: font.size,
+                    bold: font.bold,
+                    style: piece.style,
+                });
+            }
+        }
+        x += piece.width;
+    }
+    let size = fragments.iter().map(|fragment| fragment.font_size).fold(text.font_size, f64::max);
+    LayoutLine {
+        start,
+        end,
+        x: 0.0,
+        y: 0.0,
+        width,
+        height: size * LINE_HEIGHT,
+        baseline: size * (LINE_HEIGHT - 1.0) / 2.0 + size * ASCENT,
+        marker,
+        marker_width,
+        fragments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // monospace at size 10 sets every character 6 wide, so a 64 wide box fits ten of them
+    fn mono(content: &str, width: f64) -> Text {
+        let mut text = Text::new(1, Point::new(0.0, 0.0), width, 0.0, content.to_string());
+        text.font_family = "monospace".to_string();
+        text.font_size = 10.0;
+        text.sizing = TextSizing::AutoHeight;
+        text
+    }
+
+    fn lines(text: &Text) -> Vec<String> {
+        let chars: Vec<char> = text.content.chars().collect();
+        text.layout(&FallbackMetrics)
+            .lines
+            .iter()
+            .map(|line| chars[line.start..line.end].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn words_wrap_at_the_frame_width() {
+        let text = mono("one two three four", 64.0);
+        assert_eq!(lines(&text), ["one two ", "three four"]);
+        let layout = text.layout(&FallbackMetrics);
+        assert!(layout.lines.iter().all(|line| line.width <= 64.0 - TEXT_PADDING));
+        assert_eq!(layout.frame.height, layout.content_height);
+    }
+
+    #[test]
+    fn a_word_wider_than_the_line_is_broken_between_characters() {
+        let text = mono("abcdefghijklmnopqrstuvwxy z", 64.0);
+        assert_eq!(lines(&text), ["abcdefghij", "klmnopqrst", "uvwxy z"]);
+    }
+
+    #[test]
+    fn newlines_always_break_and_keep_empty_lines() {
+        let text = mono("ab\n\ncd", 64.0);
+        assert_eq!(lines(&text), ["ab", "", "cd"]);
+        let layout = text.layout(&FallbackMetrics);
+        assert!(layout.lines[1].y > layout.lines[0].y && layout.lines[2].y > layout.lines[1].y);
+    }
+
+    #[test]
+    fn auto_width_text_never_wraps() {
+        let mut text = mono("one two three four", 10.0);
+        text.sizing = TextSizing::AutoWidth;
+        assert_eq!(lines(&text), ["one two three four"]);
+        assert_eq!(text.layout(&FallbackMetrics).frame.width, 18.0 * 6.0 + TEXT_PADDING);
+    }
+}
@@ -1,17 +1,48 @@
 import { writable } from 'svelte/store';
 import type { EditorApi } from '../wasm/pkg/rustboard_wasm';
 
+export type BlendMode = 'normal' | 'multiply';
+// named patterns, or alternating dash and gap lengths
+export type DashPattern = 'solid' | 'dashed' | 'dotted' | number[];
+export interface GradientStop {
+	offset: number;
+	color: string;
+}
+// a plain color string is a solid fill; gradients are laid out in the shape's own box
+export type Fill =
+	| string
+	| { type: 'solid'; color: string }
+	| { type: 'linear_gradient'; angle?: number; stops: GradientStop[] }
+	| { type: 'radial_gradient'; center?: { x: number; y: number }; radius?: number; stops: GradientStop[] }
+	| {
+			type: 'pattern';
+			kind: 'hatch' | 'cross_hatch' | 'dots';
+			color: string;
+			background?: string | null;
+			spacing?: number;
+			angle?: number;
+			line_width?: number;
+	  };
+
 export interface Rectangle {
 	id: number;
 	position: { x: number; y: number };
 	width: number;
 	height: number;
 	stroke_color?: string;
-	fill_color?: string | null;
+	fill_color?: Fill | null;
 	line_width?: number;
-	dash_pattern?: string;
+	dash_pattern?: DashPattern;
 	border_radius?: number;
 	rotation_angle?: number;
+	skew?: number;
+	flip_x?: boolean;
+	flip_y?: boolean;
+	opacity?: number;
+	blend_mode?: BlendMode;
+	// 0 draws clean geometry; the seed keeps the wobble identical for everyone
+	roughness?: number;
+	seed?: number;
 	z_index?: number;
 }
 
@@ -21,21 +52,47 @@ export interface Ellipse {
 	radius_x: number;
 	radius_y: number;
 	stroke_color?: string;
-	fill_color?: string | null;
+	fill_color?: Fill | null;
 	line_width?: number;
-	dash_pattern?: string;
+	dash_pattern?: DashPattern;
 	rotation_angle?: number;
+	skew?: number;
+	flip_x?: boolean;
+	flip_y?: boolean;
+	opacity?: number;
+	blend_mode?: BlendMode;
+	roughness?: number;
+	seed?: number;
 	z_index?: number;
 }
 
+export type ConnectorEnd = 'start' | 'end';
+
+export type Anchor =
+	| { type: 'side'; side: 'top' | 'right' | 'bottom' | 'left' }
+	| { type: 'fixed'; x: number; y: number }
+	| { type: 'nearest_edge' };
+
+export interface Binding {
+	element_id: number;
+	anchor: Anchor;
+}
+
 export interface Line {
 	id: number;
 	start: { x: number; y: number };
 	end: { x: number; y: number };
+	start_binding?: Binding | null;
+	end_binding?: Binding | null;
+	control_points?: { x: number; y: number }[];
 	stroke_color?: string;
 	line_width?: number;
-	dash_pattern?: string;
+	dash_pattern?: DashPattern;
 	rotation_angle?: number;
+	opacity?: number;
+	blend_mode?: BlendMode;
+	roughness?: number;
+	seed?: number;
 	z_index?: number;
 }
 
@@ -43,10 +100,17 @@ export interface Arrow {
 	id: number;
 	start: { x: number; y: number };
 	end: { x: number; y: number };
+	start_binding?: Binding | null;
+	end_binding?: Binding | null;
+	control_points?: { x: number; y: number }[];
 	stroke_color?: string;
 	line_width?: number;
-	dash_pattern?: string;
+	dash_pattern?: DashPattern;
 	rotation_angle?: number;
+	opacity?: number;
+	blend_mode?: BlendMode;
+	roughness?: number;
+	seed?: number;
 	z_index?: number;
 }
 
@@ -56,21 +120,32 @@ export interface Diamond {
 	width: number;
 	height: number;
 	stroke_color?: string;
-	fill_color?: string | null;
+	fill_color?: Fill | null;
 	line_width?: number;
-	dash_pattern?: string;
+	dash_pattern?: DashPattern;
 	border_radius?: number;
 	rotation_angle?: number;
+	skew?: number;
+	flip_x?: boolean;
+	flip_y?: boolean;
+	opacity?: number;
+	blend_mode?: BlendMode;
+	roughness?: number;
+	seed?: number;
 	z_index?: number;
 }
 
 export interface Path {
 	id: number;
 	points: Array<{ x: number; y: number }>;
+	pressures?: number[];
+	smoothing?: 'none' | 'catmull_rom' | 'chaikin';
 	stroke_color?: string;
 	line_width?: number;
-	dash_pattern?: string;
+	dash_pattern?: DashPattern;
 	rotation_angle?: number;
+	opacity?: number;
+	blend_mode?: BlendMode;
 	z_index?: number;
 }
 
@@ -81,29 +156,127 @@ export interface Image {
 	height: number;
 	image_data: string;
 	rotation_angle?: number;
+	skew?: number;
+	flip_x?: boolean;
+	flip_y?: boolean;
+	opacity?: number;
+	blend_mode?: BlendMode;
 	z_index?: number;
 }
 
+export interface TextStyle {
+	bold?: boolean;
+	italic?: boolean;
+	underline?: boolean;
+	strikethrough?: boolean;
+	color?: string | null;
+	font_size?: number | null;
+	link?: string | null;
+}
+
+// offsets are code points; a null field in a patch clears it
+export type TextStylePatch = TextStyle;
+
+export interface TextRun extends TextStyle {
+	text: string;
+}
+
+export type ListKind = 'none' | 'bullet' | 'numbered';
+
+export interface ParagraphStyle {
+	list?: ListKind;
+}
+
+export type TextSizing = 'auto_width' | 'auto_height' | 'fixed';
+export type VerticalAlign = 'top' | 'middle' | 'bottom';
+
+// laid out by the editor in document space, in the text's unrotated frame
+export interface LayoutFragment extends TextStyle {
+	text: string;
+	start: number;
+	x: number;
+	width: number;
+	font_size: number;
+	bold: boolean;
+}
+
+export interface LayoutLine {
+	start: number;
+	end: number;
+	x: number;
+	y: number;
+	width: number;
+	height: number;
+	baseline: number;
+	marker: string | null;
+	marker_width: number;
+	fragments: LayoutFragment[];
+}
+
+export interface TextLayout {
+	frame: { x: number; y: number; width: number; height: number };
+	content_width: number;
+	content_height: number;
+	lines: LayoutLine[];
+}
+
 export interface Text {
 	id: number;
 	position: { x: number; y: number };
 	width: number;
 	height: number;
 	content: string;
+	runs?: TextRun[];
+	paragraphs?: ParagraphStyle[];
+	sizing?: TextSizing;
+	vertical_align?: VerticalAlign;
+	// set on labels, which follow the shape or connector they belong to
+	container_id?: number;
 	font_family?: string;
 	font_size?: number;
 	font_weight?: string;
 	text_align?: 'left' | 'center' | 'right';
 	color?: string;
 	rotation_angle?: number;
+	skew?: number;
+	flip_x?: boolean;
+	flip_y?: boolean;
 	z_index?: number;
 	opacity?: number;
+	blend_mode?: BlendMode;
 }
 
 export interface Group {
 	id: number;
 	element_ids: number[];
 	rotation_angle?: number;
+	locked?: boolean;
+	transform?: { a: number; b: number; c: number; d: number; e: number; f: number };
+}
+
+export interface Frame {
+	id: number;
+	position: { x: number; y: number };
+	width: number;
+	height: number;
+	name: string;
+	element_ids: number[];
+	stroke_color?: string;
+	fill_color?: string | null;
+	line_width?: number;
+	clip?: boolean;
+	z_index?: number;
+	locked?: boolean;
+}
+
+export interface Layer {
+	id: number;
+	name: string;
+	index: number;
+	element_ids: number[];
+	visible: boolean;
+	locked: boolean;
+	opacity: number;
 }
 
 export const wasmLoaded = writable<boolean>(false);
@@ -0,0 +1,74 @@
+import { get } from 'svelte/store';
+import { editorApi, type Binding, type ConnectorEnd } from '$lib/stores/editor';
+import { sendOperation } from '$lib/utils/collaboration';
+import { updateArrows } from './arrow';
+import { updateLines } from './line';
+
+// works for lines and arrows; null detaches the end
+export function bindConnector(id: number, end: ConnectorEnd, binding: Binding | null): boolean {
+    const api = get(editorApi);
+    if (!api) return false;
+
+    if (!api.bind_connector(BigInt(id), end, binding)) return false;
+    updateLines();
+    updateArrows();
+
+    sendOperation({
+        op: 'BindConnector',
+        id,
+        end,
+        binding
+    });
+    return true;
+}
+
+// peers are always sent the whole list, which is what the server stores
+function publishControlPoints(id: number): void {
+    const api = get(editorApi);
+    if (!api) return;
+    updateLines();
+    updateArrows();
+
+    const element = api.get_element(BigInt(id)) as { control_points?: { x: number; y: number }[] } | null;
+    sendOperation({
+        op: 'SetControlPoints',
+        id,
+        control_points: element?.control_points ?? []
+    });
+}
+
+export function setControlPoints(id: number, controlPoints: { x: number; y: number }[]): void {
+    const api = get(editorApi);
+    if (!api) return;
+
+    if (api.set_control_points(BigInt(id), controlPoints)) {
+        publishControlPoints(id);
+    }
+}
+
+export function addControlPoint(id: number, x: number, y: number): void {
+    const api = get(editorApi);
+    if (!api) return;
+
+    if (api.add_control_point(BigInt(id), x, y)) {
+        publishControlPoints(id);
+    }
+}
+
+export function moveControlPoint(id: number, index: number, x: number, y: number): void {
+    const api = get(editorApi);
+    if (!api) return;
+
+    if (api.move_control_point(BigInt(id), index, x, y)) {
+        publishControlPoints(id);
+    }
+}
+
+export function removeControlPoint(id: number, index: number): void {
+    const api = get(editorApi);
+    if (!api) return;
+
+    if (api.remove_control_point(BigInt(id), index)) {
+        publishControlPoints(id);
+    }
+}
@@ -5,4 +5,5 @@ export * from './arrow';
 export * from './diamond';
 export * from './path';
 export * from './image';
-export * from './text';
\ No newline at end of file
+export * from './text';
+export * from './connector';
\ No newline at end of file
@@ -82,6 +82,41 @@ export function setPathPoints(id: number, points: Array<{ x: number; y: number }
     });
 }
 
+export function finishPath(
+    id: number,
+    points: Array<{ x: number; y: number }>,
+    pressures: number[] | null = null,
+    options: { tolerance?: number; smoothing?: Path['smoothing']; spacing?: number; simulate_pressure?: boolean } = {}
+): void {
+    const api = get(editorApi);
+    if (!api) return;
+
+    api.begin_transaction('Draw');
+    api.set_path_points(BigInt(id), points, false);
+    if (pressures && pressures.length === points.length) {
+        api.set_path_pressures(BigInt(id), pressures);
+    }
+    api.process_path(BigInt(id), { simulate_pressure: !pressures, ...options });
+    api.commit_transaction();
+    updatePaths();
+
+    const path = get(paths).find((p: Path) => p.id === id);
+    if (!path) return;
+    sendOperation({
+        op: 'SetPathPoints',
+        id,
+        points: path.points,
+        pressures: path.pressures
+    });
+    if (path.smoothing && path.smoothing !== 'none') {
+        sendOperation({
+            op: 'SetPathStyle',
+            id,
+            smoothing: path.smoothing
+        });
+    }
+}
+
 export function updatePaths(): void {
     const api = get(editorApi);
     if (!api) return;
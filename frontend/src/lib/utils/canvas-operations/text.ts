@@ -1,6 +1,7 @@
 import { get } from 'svelte/store';
-import { editorApi, texts, selectedTexts, type Text } from '$lib/stores/editor';
+import { editorApi, texts, selectedTexts, type Text, type TextStylePatch, type ListKind } from '$lib/stores/editor';
 import { sendOperation } from '$lib/utils/collaboration';
+import { ensureFontMetrics } from '$lib/utils/font-metrics';
 
 function updateTexts(): void {
     const api = get(editorApi);
@@ -60,6 +61,33 @@ export function addText(x: number, y: number, content: string, width: number = 1
     return Number(newId);
 }
 
+// a label's box comes from its container, so only the container and content are sent; null when
+// the element cannot take a label or already has one
+export function addLabel(containerId: number, content: string): number | null {
+    const api = get(editorApi);
+    if (!api) return null;
+
+    const newId = api.add_label(BigInt(containerId), content);
+    if (newId === undefined) return null;
+
+    const updatedTexts = Array.from(api.get_texts() as Text[]);
+    texts.set(updatedTexts);
+
+    const newText = updatedTexts.find((t: Text) => t.id === Number(newId));
+    if (newText) {
+        selectedTexts.set([newText]);
+    }
+
+    sendOperation({
+        op: 'AddLabel',
+        id: Number(newId),
+        container_id: containerId,
+        content
+    });
+
+    return Number(newId);
+}
+
 export function moveText(id: number, x: number, y: number, saveHistory: boolean = true): void {
     const api = get(editorApi);
     if (!api) return;
@@ -89,17 +117,95 @@ export function resizeText(id: number, width: number, height: number, saveHistor
     });
 }
 
+// edits are sent as the changed range only, so collaborators keep their own styling and
+// concurrent edits elsewhere in the text are not overwritten
 export function setTextContent(id: number, content: string, saveHistory: boolean = true): void {
     const api = get(editorApi);
     if (!api) return;
 
-    api.set_text_content(BigInt(id), content, saveHistory);
+    const existing = get(texts).find((t: Text) => t.id === id);
+    if (!existing) {
+        api.set_text_content(BigInt(id), content, saveHistory);
+        updateTexts();
+        sendOperation({
+            op: 'UpdateText',
+            id,
+            content
+        });
+        return;
+    }
+
+    // the editor counts offsets in code points, so compare code points rather than UTF-16 units
+    const before = Array.from(existing.content);
+    const after = Array.from(content);
+    let prefix = 0;
+    while (prefix < before.length && prefix < after.length && before[prefix] === after[prefix]) {
+        prefix++;
+    }
+    let suffix = 0;
+    while (
+        suffix < before.length - prefix &&
+        suffix < after.length - prefix &&
+        before[before.length - 1 - suffix] === after[after.length - 1 - suffix]
+    ) {
+        suffix++;
+    }
+    const end = before.length - suffix;
+    const inserted = after.slice(prefix, after.length - suffix).join('');
+
+    // a replacement is a deletion followed by an insertion, undone together
+    api.begin_transaction('Edit text');
+    if (end > prefix) {
+        api.delete_text_range(BigInt(id), prefix, end);
+        sendOperation({
+            op: 'DeleteTextRange',
+            id,
+            start: prefix,
+            end
+        });
+    }
+    if (inserted.length > 0) {
+        api.insert_text(BigInt(id), prefix, inserted, null);
+        sendOperation({
+            op: 'InsertText',
+            id,
+            offset: prefix,
+            text: inserted
+        });
+    }
+    api.commit_transaction();
     updateTexts();
-    
+}
+
+export function setTextStyleRange(id: number, start: number, end: number, patch: TextStylePatch): void {
+    const api = get(editorApi);
+    if (!api) return;
+
+    api.set_text_style_range(BigInt(id), start, end, patch);
+    updateTexts();
+
     sendOperation({
-        op: 'UpdateText',
+        op: 'SetTextStyleRange',
         id,
-        content
+        start,
+        end,
+        patch
+    });
+}
+
+export function setTextList(id: number, start: number, end: number, list: ListKind): void {
+    const api = get(editorApi);
+    if (!api) return;
+
+    api.set_text_list(BigInt(id), start, end, list);
+    updateTexts();
+
+    sendOperation({
+        op: 'SetTextList',
+        id,
+        start,
+        end,
+        list
     });
 }
 
@@ -115,6 +221,7 @@ export function setTextFontFamily(id: number, fontFamily: string, saveHistory: b
     const api = get(editorApi);
     if (!api) return;
 
+    ensureFontMetrics(api, fontFamily);
     api.set_text_font_family(BigInt(id), fontFamily, saveHistory);
     updateTexts();
 }
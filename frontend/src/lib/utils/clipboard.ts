@@ -1,4 +1,5 @@
-import type { Rectangle, Ellipse, Line, Arrow, Diamond, Image, Text, Path } from '$lib/stores/editor';
+import { get } from 'svelte/store';
+import { texts as allTexts, type Rectangle, type Ellipse, type Line, type Arrow, type Diamond, type Image, type Text, type Path } from '$lib/stores/editor';
 
 export interface ClipboardData {
 	rectangles: Rectangle[];
@@ -23,6 +24,10 @@ let clipboard: ClipboardData = {
 };
 
 export function copyToClipboard(rectangles: Rectangle[], ellipses: Ellipse[], lines: Line[], arrows: Arrow[], diamonds: Diamond[], images: Image[], texts: Text[] = [], paths: Path[] = []): void {
+	// labels travel with their shape or connector even when only the container is selected
+	const containerIds = new Set([...rectangles, ...ellipses, ...lines, ...arrows, ...diamonds].map(element => element.id));
+	const copiedTextIds = new Set(texts.map(t => t.id));
+	const labels = get(allTexts).filter(t => t.container_id !== undefined && containerIds.has(t.container_id) && !copiedTextIds.has(t.id));
 	clipboard = {
 		rectangles: rectangles.map(r => ({ ...r })),
 		ellipses: ellipses.map(e => ({ ...e })),
@@ -30,7 +35,7 @@ export function copyToClipboard(rectangles: Rectangle[], ellipses: Ellipse[], li
 		arrows: arrows.map(a => ({ ...a })),
 		diamonds: diamonds.map(d => ({ ...d })),
 		images: images.map(i => ({ ...i })),
-		texts: texts.map(t => ({ ...t })),
+		texts: [...texts, ...labels].map(t => ({ ...t })),
 		paths: paths.map(p => ({ ...p, points: p.points.map(pt => ({ ...pt })) }))
 	};
 }
@@ -7,6 +7,7 @@ import { defaultStrokeWidth } from '$lib/stores/stroke-width';
 import { defaultStrokeColor } from '$lib/stores/stroke-color';
 import { renderTrigger } from '$lib/stores/editor';
 import type { EditorApi } from '../wasm/pkg/rustboard_wasm';
+import { ensureFontMetrics } from './font-metrics';
 
 const WS_URL = import.meta.env.VITE_WS_URL || 'ws://localhost:3001';
 const API_URL = import.meta.env.VITE_API_URL || 'http://localhost:3001';
@@ -113,14 +114,22 @@ function isAddOperation(operation: Operation): boolean {
 	return operation.op.startsWith('Add') || operation.op === 'GroupElements';
 }
 
+const ELEMENT_LIST_OPERATIONS = new Set(['GroupElements', 'MoveToLayer', 'Align', 'Distribute', 'Transform', 'Flip']);
+
 function remapOperationId(operation: Operation, resolveId: (id: number) => number): Operation {
 	const mapped = cloneOperation(operation);
 	if (operationHasId(mapped)) {
 		mapped.id = resolveId(mapped.id);
 	}
-	if (mapped.op === 'GroupElements' && Array.isArray(mapped.element_ids)) {
+	if (ELEMENT_LIST_OPERATIONS.has(mapped.op) && Array.isArray(mapped.element_ids)) {
 		mapped.element_ids = mapped.element_ids.map((id: number) => resolveId(id));
 	}
+	if (mapped.op === 'BindConnector' && mapped.binding) {
+		mapped.binding.element_id = resolveId(mapped.binding.element_id);
+	}
+	if (mapped.op === 'AddLabel' && typeof mapped.container_id === 'number') {
+		mapped.container_id = resolveId(mapped.container_id);
+	}
 	return mapped;
 }
 
@@ -564,11 +573,13 @@ async function handleServerMessage(
 async function applyOperation(operation: Operation, editorApi: EditorApi): Promise<number | null> {
 	const op = operation.op;
 	
+	// one history entry per remote operation, as on the server
+	editorApi.begin_transaction('remote');
 	try {
 		let createdId: number | null = null;
 		switch (op) {
 			case 'AddRectangle':
-				createdId = Number(editorApi.add_rectangle_without_snapshot(
+				createdId = Number(editorApi.add_rectangle(
 					operation.position.x,
 					operation.position.y,
 					operation.width,
@@ -576,16 +587,16 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 				));
 				break;
 			case 'MoveRectangle':
-				editorApi.move_rectangle(BigInt(operation.id), operation.position.x, operation.position.y, false);
+				editorApi.move_rectangle(BigInt(operation.id), operation.position.x, operation.position.y, true);
 				break;
 			case 'ResizeRectangle':
-				editorApi.resize_rectangle(BigInt(operation.id), operation.width, operation.height, false);
+				editorApi.resize_rectangle(BigInt(operation.id), operation.width, operation.height, true);
 				break;
 			case 'DeleteRectangle':
-				editorApi.delete_rectangle_without_snapshot(BigInt(operation.id));
+				editorApi.delete_rectangle(BigInt(operation.id));
 				break;
 			case 'AddEllipse':
-				createdId = Number(editorApi.add_ellipse_without_snapshot(
+				createdId = Number(editorApi.add_ellipse(
 					operation.position.x,
 					operation.position.y,
 					operation.radius_x,
@@ -593,16 +604,16 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 				));
 				break;
 			case 'MoveEllipse':
-				editorApi.move_ellipse(BigInt(operation.id), operation.position.x, operation.position.y, false);
+				editorApi.move_ellipse(BigInt(operation.id), operation.position.x, operation.position.y, true);
 				break;
 			case 'ResizeEllipse':
-				editorApi.resize_ellipse(BigInt(operation.id), operation.radius_x, operation.radius_y, false);
+				editorApi.resize_ellipse(BigInt(operation.id), operation.radius_x, operation.radius_y, true);
 				break;
 			case 'DeleteEllipse':
-				editorApi.delete_ellipse_without_snapshot(BigInt(operation.id));
+				editorApi.delete_ellipse(BigInt(operation.id));
 				break;
 			case 'AddDiamond':
-				createdId = Number(editorApi.add_diamond_without_snapshot(
+				createdId = Number(editorApi.add_diamond(
 					operation.position.x,
 					operation.position.y,
 					operation.width,
@@ -610,16 +621,99 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 				));
 				break;
 			case 'MoveDiamond':
-				editorApi.move_diamond(BigInt(operation.id), operation.position.x, operation.position.y, false);
+				editorApi.move_diamond(BigInt(operation.id), operation.position.x, operation.position.y, true);
 				break;
 			case 'ResizeDiamond':
-				editorApi.resize_diamond(BigInt(operation.id), operation.width, operation.height, false);
+				editorApi.resize_diamond(BigInt(operation.id), operation.width, operation.height, true);
 				break;
 			case 'DeleteDiamond':
-				editorApi.delete_diamond_without_snapshot(BigInt(operation.id));
+				editorApi.delete_diamond(BigInt(operation.id));
+				break;
+			case 'AddPolygon':
+				createdId = Number(editorApi.add_polygon(operation.points));
+				break;
+			case 'MovePolygon':
+				editorApi.move_polygon(BigInt(operation.id), operation.offset_x, operation.offset_y);
+				break;
+			case 'SetPolygonPoints':
+				editorApi.set_polygon_points(BigInt(operation.id), operation.points);
+				break;
+			case 'DeletePolygon':
+				editorApi.delete_polygon(BigInt(operation.id));
+				break;
+			case 'AddRegularPolygon':
+				createdId = Number(editorApi.add_regular_polygon(
+					operation.position.x,
+					operation.position.y,
+					operation.width,
+					operation.height,
+					operation.sides
+				));
+				break;
+			case 'MoveRegularPolygon':
+				editorApi.move_regular_polygon(BigInt(operation.id), operation.position.x, operation.position.y);
+				break;
+			case 'ResizeRegularPolygon':
+				editorApi.resize_regular_polygon(BigInt(operation.id), operation.width, operation.height);
+				break;
+			case 'DeleteRegularPolygon':
+				editorApi.delete_regular_polygon(BigInt(operation.id));
+				break;
+			case 'AddStar':
+				createdId = Number(editorApi.add_star(
+					operation.position.x,
+					operation.position.y,
+					operation.width,
+					operation.height,
+					operation.sides
+				));
+				break;
+			case 'MoveStar':
+				editorApi.move_star(BigInt(operation.id), operation.position.x, operation.position.y);
+				break;
+			case 'ResizeStar':
+				editorApi.resize_star(BigInt(operation.id), operation.width, operation.height);
+				break;
+			case 'DeleteStar':
+				editorApi.delete_star(BigInt(operation.id));
+				break;
+			case 'AddFrame':
+				createdId = Number(editorApi.add_frame(
+					operation.position.x,
+					operation.position.y,
+					operation.width,
+					operation.height,
+					operation.name
+				));
+				break;
+			case 'MoveFrame':
+				editorApi.move_frame(BigInt(operation.id), operation.position.x, operation.position.y);
+				break;
+			case 'ResizeFrame':
+				editorApi.resize_frame(BigInt(operation.id), operation.width, operation.height);
+				break;
+			case 'DeleteFrame':
+				editorApi.delete_frame(BigInt(operation.id));
+				break;
+			case 'AddLayer':
+				createdId = Number(editorApi.add_layer(operation.name));
+				break;
+			case 'SetLayerStyle': {
+				const { op: _op, id, ...patch } = operation;
+				editorApi.update_element(BigInt(id), patch);
+				break;
+			}
+			case 'ReorderLayer':
+				editorApi.reorder_layer(BigInt(operation.id), operation.index);
+				break;
+			case 'MoveToLayer':
+				editorApi.move_to_layer(operation.element_ids, BigInt(operation.id));
+				break;
+			case 'DeleteLayer':
+				editorApi.delete_layer(BigInt(operation.id));
 				break;
 			case 'AddLine':
-				createdId = Number(editorApi.add_line_without_snapshot(
+				createdId = Number(editorApi.add_line(
 					operation.start.x,
 					operation.start.y,
 					operation.end.x,
@@ -633,14 +727,14 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 					operation.start.y,
 					operation.end.x,
 					operation.end.y,
-					false
+					true
 				);
 				break;
 			case 'DeleteLine':
-				editorApi.delete_line_without_snapshot(BigInt(operation.id));
+				editorApi.delete_line(BigInt(operation.id));
 				break;
 			case 'AddArrow':
-				createdId = Number(editorApi.add_arrow_without_snapshot(
+				createdId = Number(editorApi.add_arrow(
 					operation.start.x,
 					operation.start.y,
 					operation.end.x,
@@ -654,42 +748,48 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 					operation.start.y,
 					operation.end.x,
 					operation.end.y,
-					false
+					true
 				);
 				break;
 			case 'DeleteArrow':
-				editorApi.delete_arrow_without_snapshot(BigInt(operation.id));
+				editorApi.delete_arrow(BigInt(operation.id));
 				break;
 			case 'AddPath':
-				const pathId = editorApi.add_path_without_snapshot(operation.points);
+				const pathId = editorApi.add_path(operation.points);
 				const strokeWidth = getStore(defaultStrokeWidth);
 				const strokeColor = getStore(defaultStrokeColor);
-				editorApi.set_path_line_width(BigInt(pathId), strokeWidth, false);
-				editorApi.set_path_stroke_color(BigInt(pathId), strokeColor, false);
+				editorApi.set_path_line_width(BigInt(pathId), strokeWidth, true);
+				editorApi.set_path_stroke_color(BigInt(pathId), strokeColor, true);
 				createdId = Number(pathId);
 				break;
 			case 'MovePath':
-				editorApi.move_path(BigInt(operation.id), operation.offset_x, operation.offset_y, false);
+				editorApi.move_path(BigInt(operation.id), operation.offset_x, operation.offset_y, true);
 				break;
 			case 'SetPathPoints':
 				const allPaths = editorApi.get_paths() as any[];
 				const pathExists = allPaths.some((p: any) => p.id === operation.id);
 				if (!pathExists) {
 					console.warn('SetPathPoints: Path with id', operation.id, 'does not exist yet. Creating it.');
-					const newPathId = editorApi.add_path_without_snapshot(operation.points);
+					const newPathId = editorApi.add_path(operation.points);
 					const strokeWidth = getStore(defaultStrokeWidth);
 					const strokeColor = getStore(defaultStrokeColor);
-					editorApi.set_path_line_width(BigInt(newPathId), strokeWidth, false);
-					editorApi.set_path_stroke_color(BigInt(newPathId), strokeColor, false);
+					editorApi.set_path_line_width(BigInt(newPathId), strokeWidth, true);
+					editorApi.set_path_stroke_color(BigInt(newPathId), strokeColor, true);
+					if (operation.pressures) {
+						editorApi.set_path_pressures(BigInt(newPathId), operation.pressures);
+					}
 				} else {
-					editorApi.set_path_points(BigInt(operation.id), operation.points, false);
+					editorApi.set_path_points(BigInt(operation.id), operation.points, true);
+					if (operation.pressures) {
+						editorApi.set_path_pressures(BigInt(operation.id), operation.pressures);
+					}
 				}
 				break;
 			case 'DeletePath':
-				editorApi.delete_path_without_snapshot(BigInt(operation.id));
+				editorApi.delete_path(BigInt(operation.id));
 				break;
 			case 'AddImage':
-				createdId = Number(editorApi.add_image_without_snapshot(
+				createdId = Number(editorApi.add_image(
 					operation.position.x,
 					operation.position.y,
 					operation.width,
@@ -698,16 +798,16 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 				));
 				break;
 			case 'MoveImage':
-				editorApi.move_image(BigInt(operation.id), operation.position.x, operation.position.y, false);
+				editorApi.move_image(BigInt(operation.id), operation.position.x, operation.position.y, true);
 				break;
 			case 'ResizeImage':
-				editorApi.resize_image(BigInt(operation.id), operation.width, operation.height, false);
+				editorApi.resize_image(BigInt(operation.id), operation.width, operation.height, true);
 				break;
 			case 'DeleteImage':
-				editorApi.delete_image_without_snapshot(BigInt(operation.id));
+				editorApi.delete_image(BigInt(operation.id));
 				break;
 			case 'AddText':
-				createdId = Number(editorApi.add_text_without_snapshot(
+				createdId = Number(editorApi.add_text(
 					operation.position.x,
 					operation.position.y,
 					operation.width,
@@ -715,137 +815,255 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 					operation.content
 				));
 				break;
+			case 'AddLabel': {
+				const labelId = editorApi.add_label(BigInt(operation.container_id), operation.content);
+				if (labelId !== undefined) {
+					createdId = Number(labelId);
+				}
+				break;
+			}
 			case 'MoveText':
-				editorApi.move_text(BigInt(operation.id), operation.position.x, operation.position.y, false);
+				editorApi.move_text(BigInt(operation.id), operation.position.x, operation.position.y, true);
 				break;
 			case 'ResizeText':
-				editorApi.resize_text(BigInt(operation.id), operation.width, operation.height, false);
+				editorApi.resize_text(BigInt(operation.id), operation.width, operation.height, true);
 				break;
 			case 'UpdateText':
-				editorApi.set_text_content(BigInt(operation.id), operation.content, false);
+				editorApi.set_text_content(BigInt(operation.id), operation.content, true);
+				break;
+			case 'InsertText':
+				editorApi.insert_text(BigInt(operation.id), operation.offset, operation.text, operation.style ?? null);
+				break;
+			case 'DeleteTextRange':
+				editorApi.delete_text_range(BigInt(operation.id), operation.start, operation.end);
+				break;
+			case 'SetTextStyleRange':
+				editorApi.set_text_style_range(BigInt(operation.id), operation.start, operation.end, operation.patch);
+				break;
+			case 'SetTextList':
+				editorApi.set_text_list(BigInt(operation.id), operation.start, operation.end, operation.list);
 				break;
 			case 'DeleteText':
-				editorApi.delete_text_without_snapshot(BigInt(operation.id));
+				editorApi.delete_text(BigInt(operation.id));
 				break;
 			case 'SetRectangleStyle':
 				if (operation.stroke_color !== undefined) {
-					editorApi.set_rectangle_stroke_color(BigInt(operation.id), operation.stroke_color, false);
+					editorApi.set_rectangle_stroke_color(BigInt(operation.id), operation.stroke_color, true);
 				}
 				if (operation.fill_color !== undefined) {
-					editorApi.set_rectangle_fill_color(BigInt(operation.id), operation.fill_color, false);
+					editorApi.set_element_fill(BigInt(operation.id), operation.fill_color);
 				}
 				if (operation.line_width !== undefined) {
-					editorApi.set_rectangle_line_width(BigInt(operation.id), operation.line_width, false);
+					editorApi.set_rectangle_line_width(BigInt(operation.id), operation.line_width, true);
 				}
 				if (operation.dash_pattern !== undefined) {
-					editorApi.set_rectangle_dash_pattern(BigInt(operation.id), operation.dash_pattern, false);
+					editorApi.set_rectangle_dash_pattern(BigInt(operation.id), String(operation.dash_pattern), true);
 				}
 				if (operation.border_radius !== undefined) {
-					editorApi.set_rectangle_border_radius(BigInt(operation.id), operation.border_radius, false);
+					editorApi.set_rectangle_border_radius(BigInt(operation.id), operation.border_radius, true);
 				}
 				if (operation.rotation_angle !== undefined) {
-					editorApi.set_rectangle_rotation(BigInt(operation.id), operation.rotation_angle, false);
+					editorApi.set_rectangle_rotation(BigInt(operation.id), operation.rotation_angle, true);
+				}
+				if (operation.opacity !== undefined) {
+					editorApi.set_element_opacity(BigInt(operation.id), operation.opacity);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
+				}
+				if (operation.seed !== undefined) {
+					editorApi.set_element_seed(BigInt(operation.id), operation.seed);
+				}
+				if (operation.roughness !== undefined) {
+					editorApi.set_element_roughness(BigInt(operation.id), operation.roughness);
 				}
 				break;
 			case 'SetEllipseStyle':
 				if (operation.stroke_color !== undefined) {
-					editorApi.set_ellipse_stroke_color(BigInt(operation.id), operation.stroke_color, false);
+					editorApi.set_ellipse_stroke_color(BigInt(operation.id), operation.stroke_color, true);
 				}
 				if (operation.fill_color !== undefined) {
-					editorApi.set_ellipse_fill_color(BigInt(operation.id), operation.fill_color, false);
+					editorApi.set_element_fill(BigInt(operation.id), operation.fill_color);
 				}
 				if (operation.line_width !== undefined) {
-					editorApi.set_ellipse_line_width(BigInt(operation.id), operation.line_width, false);
+					editorApi.set_ellipse_line_width(BigInt(operation.id), operation.line_width, true);
 				}
 				if (operation.dash_pattern !== undefined) {
-					editorApi.set_ellipse_dash_pattern(BigInt(operation.id), operation.dash_pattern, false);
+					editorApi.set_ellipse_dash_pattern(BigInt(operation.id), String(operation.dash_pattern), true);
 				}
 				if (operation.rotation_angle !== undefined) {
-					editorApi.set_ellipse_rotation(BigInt(operation.id), operation.rotation_angle, false);
+					editorApi.set_ellipse_rotation(BigInt(operation.id), operation.rotation_angle, true);
+				}
+				if (operation.opacity !== undefined) {
+					editorApi.set_element_opacity(BigInt(operation.id), operation.opacity);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
+				}
+				if (operation.seed !== undefined) {
+					editorApi.set_element_seed(BigInt(operation.id), operation.seed);
+				}
+				if (operation.roughness !== undefined) {
+					editorApi.set_element_roughness(BigInt(operation.id), operation.roughness);
 				}
 				break;
 			case 'SetDiamondStyle':
 				if (operation.stroke_color !== undefined) {
-					editorApi.set_diamond_stroke_color(BigInt(operation.id), operation.stroke_color, false);
+					editorApi.set_diamond_stroke_color(BigInt(operation.id), operation.stroke_color, true);
 				}
 				if (operation.fill_color !== undefined) {
-					editorApi.set_diamond_fill_color(BigInt(operation.id), operation.fill_color, false);
+					editorApi.set_element_fill(BigInt(operation.id), operation.fill_color);
 				}
 				if (operation.line_width !== undefined) {
-					editorApi.set_diamond_line_width(BigInt(operation.id), operation.line_width, false);
+					editorApi.set_diamond_line_width(BigInt(operation.id), operation.line_width, true);
 				}
 				if (operation.dash_pattern !== undefined) {
-					editorApi.set_diamond_dash_pattern(BigInt(operation.id), operation.dash_pattern, false);
+					editorApi.set_diamond_dash_pattern(BigInt(operation.id), String(operation.dash_pattern), true);
 				}
 				if (operation.border_radius !== undefined) {
-					editorApi.set_diamond_border_radius(BigInt(operation.id), operation.border_radius, false);
+					editorApi.set_diamond_border_radius(BigInt(operation.id), operation.border_radius, true);
 				}
 				if (operation.rotation_angle !== undefined) {
-					editorApi.set_diamond_rotation(BigInt(operation.id), operation.rotation_angle, false);
+					editorApi.set_diamond_rotation(BigInt(operation.id), operation.rotation_angle, true);
 				}
+				if (operation.opacity !== undefined) {
+					editorApi.set_element_opacity(BigInt(operation.id), operation.opacity);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
+				}
+				if (operation.seed !== undefined) {
+					editorApi.set_element_seed(BigInt(operation.id), operation.seed);
+				}
+				if (operation.roughness !== undefined) {
+					editorApi.set_element_roughness(BigInt(operation.id), operation.roughness);
+				}
+				break;
+			case 'SetPolygonStyle':
+			case 'SetRegularPolygonStyle':
+			case 'SetStarStyle':
+			case 'SetFrameStyle': {
+				const { op: _op, id, ...patch } = operation;
+				editorApi.update_element(BigInt(id), patch);
 				break;
+			}
 			case 'SetLineStyle':
 				if (operation.stroke_color !== undefined) {
-					editorApi.set_line_stroke_color(BigInt(operation.id), operation.stroke_color, false);
+					editorApi.set_line_stroke_color(BigInt(operation.id), operation.stroke_color, true);
 				}
 				if (operation.line_width !== undefined) {
-					editorApi.set_line_line_width(BigInt(operation.id), operation.line_width, false);
+					editorApi.set_line_line_width(BigInt(operation.id), operation.line_width, true);
 				}
 				if (operation.dash_pattern !== undefined) {
-					editorApi.set_line_dash_pattern(BigInt(operation.id), operation.dash_pattern, false);
+					editorApi.set_line_dash_pattern(BigInt(operation.id), String(operation.dash_pattern), true);
+				}
+				if (operation.routing) {
+					editorApi.set_connector_routing(BigInt(operation.id), operation.routing);
+				}
+				if (operation.opacity !== undefined) {
+					editorApi.set_element_opacity(BigInt(operation.id), operation.opacity);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
+				}
+				if (operation.seed !== undefined) {
+					editorApi.set_element_seed(BigInt(operation.id), operation.seed);
+				}
+				if (operation.roughness !== undefined) {
+					editorApi.set_element_roughness(BigInt(operation.id), operation.roughness);
 				}
 				break;
 			case 'SetArrowStyle':
 				if (operation.stroke_color !== undefined) {
-					editorApi.set_arrow_stroke_color(BigInt(operation.id), operation.stroke_color, false);
+					editorApi.set_arrow_stroke_color(BigInt(operation.id), operation.stroke_color, true);
 				}
 				if (operation.line_width !== undefined) {
-					editorApi.set_arrow_line_width(BigInt(operation.id), operation.line_width, false);
+					editorApi.set_arrow_line_width(BigInt(operation.id), operation.line_width, true);
 				}
 				if (operation.dash_pattern !== undefined) {
-					editorApi.set_arrow_dash_pattern(BigInt(operation.id), operation.dash_pattern, false);
+					editorApi.set_arrow_dash_pattern(BigInt(operation.id), String(operation.dash_pattern), true);
+				}
+				if (operation.routing) {
+					editorApi.set_connector_routing(BigInt(operation.id), operation.routing);
+				}
+				if (operation.opacity !== undefined) {
+					editorApi.set_element_opacity(BigInt(operation.id), operation.opacity);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
+				}
+				if (operation.seed !== undefined) {
+					editorApi.set_element_seed(BigInt(operation.id), operation.seed);
+				}
+				if (operation.roughness !== undefined) {
+					editorApi.set_element_roughness(BigInt(operation.id), operation.roughness);
 				}
 				break;
 			case 'SetPathStyle':
 				if (operation.stroke_color !== undefined) {
-					editorApi.set_path_stroke_color(BigInt(operation.id), operation.stroke_color, false);
+					editorApi.set_path_stroke_color(BigInt(operation.id), operation.stroke_color, true);
 				}
 				if (operation.line_width !== undefined) {
-					editorApi.set_path_line_width(BigInt(operation.id), operation.line_width, false);
+					editorApi.set_path_line_width(BigInt(operation.id), operation.line_width, true);
 				}
 				if (operation.dash_pattern !== undefined) {
-					editorApi.set_path_dash_pattern(BigInt(operation.id), operation.dash_pattern, false);
+					editorApi.set_path_dash_pattern(BigInt(operation.id), String(operation.dash_pattern), true);
 				}
 				if (operation.rotation_angle !== undefined) {
-					editorApi.set_path_rotation(BigInt(operation.id), operation.rotation_angle, false);
+					editorApi.set_path_rotation(BigInt(operation.id), operation.rotation_angle, true);
+				}
+				if (operation.smoothing) {
+					editorApi.update_element(BigInt(operation.id), { smoothing: operation.smoothing });
+				}
+				if (operation.opacity !== undefined) {
+					editorApi.set_element_opacity(BigInt(operation.id), operation.opacity);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
 				}
 				break;
 			case 'SetImageStyle':
 				if (operation.rotation_angle !== undefined) {
-					editorApi.set_image_rotation(BigInt(operation.id), operation.rotation_angle, false);
+					editorApi.set_image_rotation(BigInt(operation.id), operation.rotation_angle, true);
+				}
+				if (operation.opacity !== undefined) {
+					editorApi.set_element_opacity(BigInt(operation.id), operation.opacity);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
 				}
 				break;
 			case 'SetTextStyle':
 				if (operation.color !== undefined) {
-					editorApi.set_text_color(BigInt(operation.id), operation.color, false);
+					editorApi.set_text_color(BigInt(operation.id), operation.color, true);
 				}
 				if (operation.opacity !== undefined) {
-					editorApi.set_text_opacity(BigInt(operation.id), operation.opacity, false);
+					editorApi.set_text_opacity(BigInt(operation.id), operation.opacity, true);
 				}
 				if (operation.font_size !== undefined) {
-					editorApi.set_text_font_size(BigInt(operation.id), operation.font_size, false);
+					editorApi.set_text_font_size(BigInt(operation.id), operation.font_size, true);
 				}
 				if (operation.font_family !== undefined) {
-					editorApi.set_text_font_family(BigInt(operation.id), operation.font_family, false);
+					ensureFontMetrics(editorApi, operation.font_family);
+					editorApi.set_text_font_family(BigInt(operation.id), operation.font_family, true);
 				}
 				if (operation.font_weight !== undefined) {
-					editorApi.set_text_font_weight(BigInt(operation.id), operation.font_weight, false);
+					editorApi.set_text_font_weight(BigInt(operation.id), operation.font_weight, true);
 				}
 				if (operation.text_align !== undefined) {
-					editorApi.set_text_text_align(BigInt(operation.id), operation.text_align, false);
+					editorApi.set_text_text_align(BigInt(operation.id), operation.text_align, true);
+				}
+				if (operation.sizing !== undefined) {
+					editorApi.set_text_sizing(BigInt(operation.id), operation.sizing);
+				}
+				if (operation.vertical_align !== undefined) {
+					editorApi.set_text_vertical_align(BigInt(operation.id), operation.vertical_align);
 				}
 				if (operation.rotation_angle !== undefined) {
-					editorApi.set_text_rotation(BigInt(operation.id), operation.rotation_angle, false);
+					editorApi.set_text_rotation(BigInt(operation.id), operation.rotation_angle, true);
+				}
+				if (operation.blend_mode !== undefined) {
+					editorApi.set_element_blend_mode(BigInt(operation.id), operation.blend_mode);
 				}
 				break;
 			case 'BringToFront':
@@ -861,7 +1079,7 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 				editorApi.send_shape_to_back(BigInt(operation.id));
 				break;
 			case 'SetElementLock':
-				editorApi.set_element_locked(BigInt(operation.id), operation.locked, false);
+				editorApi.set_element_locked(BigInt(operation.id), operation.locked, true);
 				break;
 			case 'GroupElements':
 				createdId = Number(editorApi.group_elements(operation.element_ids));
@@ -869,6 +1087,42 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 			case 'UngroupElements':
 				editorApi.ungroup_elements(BigInt(operation.id));
 				break;
+			case 'MoveGroup':
+				editorApi.move_group(BigInt(operation.id), operation.offset_x, operation.offset_y);
+				break;
+			case 'RotateGroup':
+				editorApi.rotate_group(BigInt(operation.id), operation.angle);
+				break;
+			case 'ResizeGroup':
+				editorApi.resize_group(
+					BigInt(operation.id),
+					operation.x,
+					operation.y,
+					operation.width,
+					operation.height
+				);
+				break;
+			case 'DeleteGroup':
+				editorApi.delete_element(BigInt(operation.id));
+				break;
+			case 'Align':
+				editorApi.align(operation.element_ids, operation.alignment);
+				break;
+			case 'Distribute':
+				editorApi.distribute(operation.element_ids, operation.axis, operation.spacing);
+				break;
+			case 'Transform':
+				editorApi.transform(operation.element_ids, operation.transform, operation.origin.x, operation.origin.y);
+				break;
+			case 'Flip':
+				editorApi.flip(operation.element_ids, operation.axis);
+				break;
+			case 'BindConnector':
+				editorApi.bind_connector(BigInt(operation.id), operation.end, operation.binding ?? null);
+				break;
+			case 'SetControlPoints':
+				editorApi.set_control_points(BigInt(operation.id), operation.control_points);
+				break;
 			case 'FullSync':
 				if (operation.data) {
 					editorApi.deserialize(operation.data);
@@ -877,12 +1131,16 @@ async function applyOperation(operation: Operation, editorApi: EditorApi): Promi
 			default:
 				console.warn('Unknown operation:', op);
 		}
+		editorApi.commit_transaction();
 
 		updateStores();
 		await tick();
 		renderTrigger.update(n => n + 1);
 		return createdId;
 	} catch (error) {
+		if (editorApi.in_transaction()) {
+			editorApi.rollback_transaction();
+		}
 		console.error('Error applying operation:', error, operation);
 		return null;
 	}
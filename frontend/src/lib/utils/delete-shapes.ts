@@ -19,49 +19,49 @@ export function deleteShapes(
     const hasAnySelection = rectangleIds.length > 0 || ellipseIds.length > 0 || lineIds.length > 0 || arrowIds.length > 0 || diamondIds.length > 0 || textIds.length > 0 || pathIds.length > 0 || imageIds.length > 0;
     if (!hasAnySelection) return;
 
-    api.save_snapshot();
+    api.begin_transaction('Delete');
 
     rectangleIds.forEach(id => {
-        api.delete_rectangle_without_snapshot(BigInt(id));
+        api.delete_rectangle(BigInt(id));
         sendOperation({ op: 'DeleteRectangle', id });
     });
 
     ellipseIds.forEach(id => {
-        api.delete_ellipse_without_snapshot(BigInt(id));
+        api.delete_ellipse(BigInt(id));
         sendOperation({ op: 'DeleteEllipse', id });
     });
 
     lineIds.forEach(id => {
-        api.delete_line_without_snapshot(BigInt(id));
+        api.delete_line(BigInt(id));
         sendOperation({ op: 'DeleteLine', id });
     });
 
     arrowIds.forEach(id => {
-        api.delete_arrow_without_snapshot(BigInt(id));
+        api.delete_arrow(BigInt(id));
         sendOperation({ op: 'DeleteArrow', id });
     });
 
     diamondIds.forEach(id => {
-        api.delete_diamond_without_snapshot(BigInt(id));
+        api.delete_diamond(BigInt(id));
         sendOperation({ op: 'DeleteDiamond', id });
     });
 
     pathIds.forEach(id => {
-        api.delete_path_without_snapshot(BigInt(id));
+        api.delete_path(BigInt(id));
         sendOperation({ op: 'DeletePath', id });
     });
 
     imageIds.forEach(id => {
-        api.delete_image_without_snapshot(BigInt(id));
+        api.delete_image(BigInt(id));
         sendOperation({ op: 'DeleteImage', id });
     });
 
     textIds.forEach(id => {
-        api.delete_text_without_snapshot(BigInt(id));
+        api.delete_text(BigInt(id));
         sendOperation({ op: 'DeleteText', id });
     });
 
-    api.save_snapshot();
+    api.commit_transaction();
 
     const updatedRectangles = Array.from(api.get_rectangles() as Rectangle[]);
     const updatedEllipses = Array.from(api.get_ellipses() as Ellipse[]);
@@ -0,0 +1,45 @@
+import type { EditorApi } from '../wasm/pkg/rustboard_wasm';
+
+// the editor lays text out in Rust; these tables give it the widths the canvas will really
+// draw with, so wrapping matches what is on screen
+const MEASURE_SIZE = 100;
+const registered = new Set<string>();
+
+let measureCtx: CanvasRenderingContext2D | null = null;
+
+function measureWidths(family: string, bold: boolean, italic: boolean): number[] | null {
+    if (!measureCtx) {
+        measureCtx = document.createElement('canvas').getContext('2d');
+    }
+    if (!measureCtx) return null;
+    measureCtx.font = `${italic ? 'italic ' : ''}${bold ? 'bold' : 'normal'} ${MEASURE_SIZE}px ${family}`;
+    const widths: number[] = [];
+    for (let code = 32; code <= 126; code++) {
+        widths.push(measureCtx.measureText(String.fromCharCode(code)).width / MEASURE_SIZE);
+    }
+    return widths;
+}
+
+export function ensureFontMetrics(api: EditorApi, family: string): void {
+    if (registered.has(family)) return;
+    registered.add(family);
+    for (const bold of [false, true]) {
+        for (const italic of [false, true]) {
+            const widths = measureWidths(family, bold, italic);
+            if (widths) {
+                api.set_font_widths(family, bold, italic, new Float64Array(widths));
+            }
+        }
+    }
+}
+
+// web fonts may finish loading after the first measurement, so the returned promise measures
+// again once they have; callers refresh their text stores when it settles
+export async function registerFontMetrics(api: EditorApi, families: string[]): Promise<void> {
+    families.forEach((family) => ensureFontMetrics(api, family));
+    if (!document.fonts) return;
+    await document.fonts.ready;
+    const known = Array.from(registered);
+    registered.clear();
+    known.forEach((family) => ensureFontMetrics(api, family));
+}
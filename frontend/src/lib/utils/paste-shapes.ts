@@ -73,7 +73,7 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
 
     const { minX, minY } = calculateBoundingBox(clipboard, offsetX, offsetY);
 
-    api.save_snapshot();
+    api.begin_transaction('Paste');
 
     const pastedIds = {
         rectangles: [] as number[],
@@ -85,22 +85,24 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
         texts: [] as number[],
         paths: [] as number[]
     };
+    // copied containers by their new id, so pasted labels can be bound again
+    const pastedContainers = new Map<number, number>();
 
     clipboard.rectangles.forEach(rect => {
         const newX = rect.position.x - minX + offsetX;
         const newY = rect.position.y - minY + offsetY;
-        const newId = api.add_rectangle_without_snapshot(newX, newY, rect.width, rect.height);
+        const newId = api.add_rectangle(newX, newY, rect.width, rect.height);
         if (rect.stroke_color) {
             api.set_rectangle_stroke_color(BigInt(newId), rect.stroke_color, false);
         }
         if (rect.fill_color !== undefined && rect.fill_color !== null) {
-            api.set_rectangle_fill_color(BigInt(newId), rect.fill_color, false);
+            api.set_element_fill(BigInt(newId), rect.fill_color);
         }
         if (rect.line_width) {
             api.set_rectangle_line_width(BigInt(newId), rect.line_width, false);
         }
         if (rect.dash_pattern) {
-            api.set_rectangle_dash_pattern(BigInt(newId), rect.dash_pattern, false);
+            api.set_rectangle_dash_pattern(BigInt(newId), String(rect.dash_pattern), false);
         }
         if (rect.border_radius !== undefined) {
             api.set_rectangle_border_radius(BigInt(newId), rect.border_radius, false);
@@ -108,46 +110,54 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
         if (rect.rotation_angle !== undefined) {
             api.set_rectangle_rotation(BigInt(newId), rect.rotation_angle, false);
         }
+        if (rect.roughness) {
+            api.set_element_roughness(BigInt(newId), rect.roughness);
+        }
+        pastedContainers.set(rect.id, Number(newId));
         pastedIds.rectangles.push(Number(newId));
     });
 
     clipboard.ellipses.forEach(ellipse => {
         const newX = ellipse.position.x - minX + offsetX;
         const newY = ellipse.position.y - minY + offsetY;
-        const newId = api.add_ellipse_without_snapshot(newX, newY, ellipse.radius_x, ellipse.radius_y);
+        const newId = api.add_ellipse(newX, newY, ellipse.radius_x, ellipse.radius_y);
         if (ellipse.stroke_color) {
             api.set_ellipse_stroke_color(BigInt(newId), ellipse.stroke_color, false);
         }
         if (ellipse.fill_color !== undefined && ellipse.fill_color !== null) {
-            api.set_ellipse_fill_color(BigInt(newId), ellipse.fill_color, false);
+            api.set_element_fill(BigInt(newId), ellipse.fill_color);
         }
         if (ellipse.line_width) {
             api.set_ellipse_line_width(BigInt(newId), ellipse.line_width, false);
         }
         if (ellipse.dash_pattern) {
-            api.set_ellipse_dash_pattern(BigInt(newId), ellipse.dash_pattern, false);
+            api.set_ellipse_dash_pattern(BigInt(newId), String(ellipse.dash_pattern), false);
         }
         if (ellipse.rotation_angle !== undefined) {
             api.set_ellipse_rotation(BigInt(newId), ellipse.rotation_angle, false);
         }
+        if (ellipse.roughness) {
+            api.set_element_roughness(BigInt(newId), ellipse.roughness);
+        }
+        pastedContainers.set(ellipse.id, Number(newId));
         pastedIds.ellipses.push(Number(newId));
     });
 
     clipboard.diamonds.forEach(diamond => {
         const newX = diamond.position.x - minX + offsetX;
         const newY = diamond.position.y - minY + offsetY;
-        const newId = api.add_diamond_without_snapshot(newX, newY, diamond.width, diamond.height);
+        const newId = api.add_diamond(newX, newY, diamond.width, diamond.height);
         if (diamond.stroke_color) {
             api.set_diamond_stroke_color(BigInt(newId), diamond.stroke_color, false);
         }
         if (diamond.fill_color !== undefined && diamond.fill_color !== null) {
-            api.set_diamond_fill_color(BigInt(newId), diamond.fill_color, false);
+            api.set_element_fill(BigInt(newId), diamond.fill_color);
         }
         if (diamond.line_width) {
             api.set_diamond_line_width(BigInt(newId), diamond.line_width, false);
         }
         if (diamond.dash_pattern) {
-            api.set_diamond_dash_pattern(BigInt(newId), diamond.dash_pattern, false);
+            api.set_diamond_dash_pattern(BigInt(newId), String(diamond.dash_pattern), false);
         }
         if (diamond.border_radius !== undefined) {
             api.set_diamond_border_radius(BigInt(newId), diamond.border_radius, false);
@@ -155,6 +165,10 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
         if (diamond.rotation_angle !== undefined) {
             api.set_diamond_rotation(BigInt(newId), diamond.rotation_angle, false);
         }
+        if (diamond.roughness) {
+            api.set_element_roughness(BigInt(newId), diamond.roughness);
+        }
+        pastedContainers.set(diamond.id, Number(newId));
         pastedIds.diamonds.push(Number(newId));
     });
 
@@ -163,7 +177,7 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
         const startY = line.start.y - minY + offsetY;
         const endX = line.end.x - minX + offsetX;
         const endY = line.end.y - minY + offsetY;
-        const newId = api.add_line_without_snapshot(startX, startY, endX, endY);
+        const newId = api.add_line(startX, startY, endX, endY);
         if (line.stroke_color) {
             api.set_line_stroke_color(BigInt(newId), line.stroke_color, false);
         }
@@ -171,11 +185,15 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
             api.set_line_line_width(BigInt(newId), line.line_width, false);
         }
         if (line.dash_pattern) {
-            api.set_line_dash_pattern(BigInt(newId), line.dash_pattern, false);
+            api.set_line_dash_pattern(BigInt(newId), String(line.dash_pattern), false);
         }
         if (line.rotation_angle !== undefined) {
             api.set_line_rotation(BigInt(newId), line.rotation_angle, false);
         }
+        if (line.roughness) {
+            api.set_element_roughness(BigInt(newId), line.roughness);
+        }
+        pastedContainers.set(line.id, Number(newId));
         pastedIds.lines.push(Number(newId));
     });
 
@@ -184,7 +202,7 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
         const startY = arrow.start.y - minY + offsetY;
         const endX = arrow.end.x - minX + offsetX;
         const endY = arrow.end.y - minY + offsetY;
-        const newId = api.add_arrow_without_snapshot(startX, startY, endX, endY);
+        const newId = api.add_arrow(startX, startY, endX, endY);
         if (arrow.stroke_color) {
             api.set_arrow_stroke_color(BigInt(newId), arrow.stroke_color, false);
         }
@@ -192,18 +210,22 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
             api.set_arrow_line_width(BigInt(newId), arrow.line_width, false);
         }
         if (arrow.dash_pattern) {
-            api.set_arrow_dash_pattern(BigInt(newId), arrow.dash_pattern, false);
+            api.set_arrow_dash_pattern(BigInt(newId), String(arrow.dash_pattern), false);
         }
         if (arrow.rotation_angle !== undefined) {
             api.set_arrow_rotation(BigInt(newId), arrow.rotation_angle, false);
         }
+        if (arrow.roughness) {
+            api.set_element_roughness(BigInt(newId), arrow.roughness);
+        }
+        pastedContainers.set(arrow.id, Number(newId));
         pastedIds.arrows.push(Number(newId));
     });
 
     clipboard.images.forEach(image => {
         const newX = image.position.x - minX + offsetX;
         const newY = image.position.y - minY + offsetY;
-        const newId = api.add_image_without_snapshot(newX, newY, image.width, image.height, image.image_data);
+        const newId = api.add_image(newX, newY, image.width, image.height, image.image_data);
         if (image.rotation_angle !== undefined) {
             api.set_image_rotation(BigInt(newId), image.rotation_angle, false);
         }
@@ -214,7 +236,9 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
     clipboard.texts.forEach((text, index) => {
         const newX = text.position.x - minX + offsetX;
         const newY = text.position.y - minY + offsetY;
-        const newId = api.add_text_without_snapshot(newX, newY, text.width, text.height, text.content);
+        const containerId = text.container_id !== undefined ? pastedContainers.get(text.container_id) : undefined;
+        const labelId = containerId !== undefined ? api.add_label(BigInt(containerId), text.content) : undefined;
+        const newId = labelId ?? api.add_text(newX, newY, text.width, text.height, text.content);
         if (text.font_family !== undefined && text.font_family !== 'Arial') {
             api.set_text_font_family(BigInt(newId), text.font_family, false);
         }
@@ -233,6 +257,30 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
         if (text.rotation_angle !== undefined && text.rotation_angle !== 0.0) {
             api.set_text_rotation(BigInt(newId), text.rotation_angle, false);
         }
+        // a wrapping text needs its mode before its width, or the width is fitted to the content
+        // labels take their box from the container instead
+        if (labelId === undefined && text.sizing !== undefined && text.sizing !== 'auto_width') {
+            api.set_text_sizing(BigInt(newId), text.sizing);
+            api.resize_text(BigInt(newId), text.width, text.height, false);
+        }
+        if (text.vertical_align !== undefined && text.vertical_align !== 'top') {
+            api.set_text_vertical_align(BigInt(newId), text.vertical_align);
+        }
+        // rich text is replayed run by run, with offsets in code points
+        let runStart = 0;
+        (text.runs ?? []).forEach(({ text: runText, ...style }) => {
+            const runEnd = runStart + Array.from(runText).length;
+            api.set_text_style_range(BigInt(newId), runStart, runEnd, style);
+            runStart = runEnd;
+        });
+        let lineStart = 0;
+        text.content.split('\n').forEach((line, index) => {
+            const list = text.paragraphs?.[index]?.list;
+            if (list && list !== 'none') {
+                api.set_text_list(BigInt(newId), lineStart, lineStart, list);
+            }
+            lineStart += Array.from(line).length + 1;
+        });
         const opacity = (text as any).opacity;
         if (opacity !== undefined) {
             textOpacityMap.set(Number(newId), opacity);
@@ -246,7 +294,7 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
                 x: pt.x - minX + offsetX,
                 y: pt.y - minY + offsetY
             }));
-            const newId = api.add_path_without_snapshot(offsetPoints);
+            const newId = api.add_path(offsetPoints);
             if (path.stroke_color) {
                 api.set_path_stroke_color(BigInt(newId), path.stroke_color, false);
             }
@@ -254,7 +302,7 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
                 api.set_path_line_width(BigInt(newId), path.line_width, false);
             }
             if (path.dash_pattern) {
-                api.set_path_dash_pattern(BigInt(newId), path.dash_pattern, false);
+                api.set_path_dash_pattern(BigInt(newId), String(path.dash_pattern), false);
             }
             if (path.rotation_angle !== undefined) {
                 api.set_path_rotation(BigInt(newId), path.rotation_angle, false);
@@ -263,7 +311,7 @@ export function pasteShapes(clipboard: ClipboardData, offsetX: number, offsetY:
         }
     });
 
-    api.save_snapshot();
+    api.commit_transaction();
 
     const updatedRectangles = Array.from(api.get_rectangles() as Rectangle[]);
     const updatedEllipses = Array.from(api.get_ellipses() as Ellipse[]);
@@ -1,11 +1,19 @@
-use rustboard_editor::{Document, Point};
+use rustboard_editor::{
+    Affine, Alignment, Axis, Binding, BlendMode, ConnectorEnd, Document, Element, ElementPatch, Marker, PathOptions,
+    Color, DashPattern, Fill, FontWeight, ListKind, MeasuredMetrics, Point, Rect, Routing, SelectionMode, SnapHandle,
+    SnapOptions, Spacing, StyleError, TextAlign, TextSizing, TextStyle, TextStylePatch, VerticalAlign,
+};
+use rustboard_editor::stroke::stroke_outline;
 use serde_wasm_bindgen::to_value;
 use std::cell::RefCell;
+use std::str::FromStr;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 pub struct EditorApi {
     document: RefCell<Document>,
+    // widths measured by the browser, handed to the document's text layout
+    metrics: RefCell<MeasuredMetrics>,
 }
 
 #[wasm_bindgen]
@@ -14,6 +22,7 @@ impl EditorApi {
     pub fn new() -> EditorApi {
         EditorApi {
             document: RefCell::new(Document::new()),
+            metrics: RefCell::new(MeasuredMetrics::default()),
         }
     }
 
@@ -38,6 +47,196 @@ impl EditorApi {
         self.document.borrow().can_redo()
     }
 
+    #[wasm_bindgen]
+    pub fn undo_label(&self) -> Option<String> {
+        self.document.borrow().undo_label().map(str::to_string)
+    }
+
+    #[wasm_bindgen]
+    pub fn redo_label(&self) -> Option<String> {
+        self.document.borrow().redo_label().map(str::to_string)
+    }
+
+    // transaction
+    #[wasm_bindgen]
+    pub fn begin_transaction(&self, label: String) {
+        self.document.borrow_mut().begin_transaction(&label);
+    }
+
+    #[wasm_bindgen]
+    pub fn commit_transaction(&self) -> bool {
+        self.document.borrow_mut().commit_transaction()
+    }
+
+    #[wasm_bindgen]
+    pub fn rollback_transaction(&self) -> bool {
+        self.document.borrow_mut().rollback_transaction()
+    }
+
+    #[wasm_bindgen]
+    pub fn in_transaction(&self) -> bool {
+        self.document.borrow().in_transaction()
+    }
+
+    // element
+    #[wasm_bindgen]
+    pub fn get_element(&self, id: u64) -> JsValue {
+        let element = self.document.borrow().get(id).cloned();
+        to_value(&element).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_elements(&self) -> JsValue {
+        let elements: Vec<Element> = self.document.borrow().elements().cloned().collect();
+        to_value(&elements).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn update_element(&self, id: u64, patch: JsValue) -> Result<bool, JsValue> {
+        let patch: ElementPatch =
+            serde_wasm_bindgen::from_value(patch).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().update(id, &patch))
+    }
+
+    #[wasm_bindgen]
+    pub fn translate_element(&self, id: u64, delta_x: f64, delta_y: f64) -> bool {
+        self.document
+            .borrow_mut()
+            .translate(id, delta_x, delta_y)
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_element(&self, id: u64) -> bool {
+        self.document.borrow_mut().delete(id)
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_element_without_snapshot(&self, id: u64) -> bool {
+        self.document.borrow_mut().delete_without_snapshot(id)
+    }
+
+    // hit testing
+    #[wasm_bindgen]
+    pub fn elements_at_point(&self, x: f64, y: f64, tolerance: f64) -> Vec<u64> {
+        self.document
+            .borrow()
+            .elements_at_point(Point::new(x, y), tolerance)
+    }
+
+    #[wasm_bindgen]
+    pub fn elements_in_rect(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        mode: JsValue,
+    ) -> Result<Vec<u64>, JsValue> {
+        let mode: SelectionMode =
+            serde_wasm_bindgen::from_value(mode).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow().elements_in_rect(Rect::new(x, y, width, height), mode))
+    }
+
+    // snapping
+    #[wasm_bindgen]
+    pub fn snap(
+        &self,
+        bounds: JsValue,
+        handle: JsValue,
+        exclude: JsValue,
+        options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let bounds: Rect =
+            serde_wasm_bindgen::from_value(bounds).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let handle: SnapHandle =
+            serde_wasm_bindgen::from_value(handle).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let exclude: Vec<u64> =
+            serde_wasm_bindgen::from_value(exclude).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let options: SnapOptions =
+            serde_wasm_bindgen::from_value(options).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let snap = self.document.borrow().snap(bounds, handle, &exclude, &options);
+        to_value(&snap).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub fn element_contains_point(&self, id: u64, x: f64, y: f64, tolerance: f64) -> bool {
+        self.document
+            .borrow()
+            .get(id)
+            .is_some_and(|element| element.contains_point(Point::new(x, y), tolerance))
+    }
+
+    #[wasm_bindgen]
+    pub fn element_intersects_rect(&self, id: u64, x: f64, y: f64, width: f64, height: f64) -> bool {
+        self.document
+            .borrow()
+            .get(id)
+            .is_some_and(|element| element.intersects_rect(&Rect::new(x, y, width, height)))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_element_bounds(&self, id: u64) -> JsValue {
+        let bounds = self.document.borrow().element_bounds(id);
+        to_value(&bounds).unwrap()
+    }
+
+    // { stroke, hachure } path data in the element's unrotated frame, or null when drawn clean
+    #[wasm_bindgen]
+    pub fn get_element_sketch(&self, id: u64) -> JsValue {
+        let sketch = self.document.borrow().sketch(id);
+        to_value(&sketch).unwrap()
+    }
+
+    // connector
+    #[wasm_bindgen]
+    pub fn bind_connector(&self, id: u64, end: JsValue, binding: JsValue) -> Result<bool, JsValue> {
+        let end: ConnectorEnd =
+            serde_wasm_bindgen::from_value(end).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let binding: Option<Binding> =
+            serde_wasm_bindgen::from_value(binding).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().bind_connector(id, end, binding))
+    }
+
+    #[wasm_bindgen]
+    pub fn set_connector_routing(&self, id: u64, routing: JsValue) -> Result<bool, JsValue> {
+        let routing: Routing =
+            serde_wasm_bindgen::from_value(routing).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().set_connector_routing(id, routing))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_bound_connectors(&self, id: u64) -> Vec<u64> {
+        self.document.borrow().get_bound_connectors(id)
+    }
+
+    #[wasm_bindgen]
+    pub fn set_control_points(&self, id: u64, control_points: JsValue) -> Result<bool, JsValue> {
+        let control_points: Vec<Point> =
+            serde_wasm_bindgen::from_value(control_points).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().set_control_points(id, control_points))
+    }
+
+    #[wasm_bindgen]
+    pub fn add_control_point(&self, id: u64, x: f64, y: f64) -> bool {
+        self.document
+            .borrow_mut()
+            .add_control_point(id, Point::new(x, y))
+    }
+
+    #[wasm_bindgen]
+    pub fn move_control_point(&self, id: u64, index: usize, x: f64, y: f64) -> bool {
+        self.document
+            .borrow_mut()
+            .move_control_point(id, index, Point::new(x, y))
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_control_point(&self, id: u64, index: usize) -> bool {
+        self.document
+            .borrow_mut()
+            .remove_control_point(id, index)
+    }
+
     // rectangle
     #[wasm_bindgen]
     pub fn add_rectangle(&self, x: f64, y: f64, width: f64, height: f64) -> u64 {
@@ -55,7 +254,7 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_rectangles(&self) -> JsValue {
-        let rectangles = self.document.borrow().get_rectangles().to_vec();
+        let rectangles = self.document.borrow().get_rectangles();
         to_value(&rectangles).unwrap()
     }
 
@@ -107,7 +306,7 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_diamonds(&self) -> JsValue {
-        let diamonds = self.document.borrow().get_diamonds().to_vec();
+        let diamonds = self.document.borrow().get_diamonds();
         to_value(&diamonds).unwrap()
     }
 
@@ -137,6 +336,275 @@ impl EditorApi {
             .delete_diamond_without_snapshot(id)
     }
 
+    // polygon
+    #[wasm_bindgen]
+    pub fn add_polygon(&self, points: JsValue) -> Result<u64, JsValue> {
+        let points: Vec<Point> =
+            serde_wasm_bindgen::from_value(points).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().add_polygon(points))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_polygons(&self) -> JsValue {
+        let polygons = self.document.borrow().get_polygons();
+        to_value(&polygons).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn move_polygon(&self, id: u64, delta_x: f64, delta_y: f64) {
+        self.document
+            .borrow_mut()
+            .move_polygon(id, delta_x, delta_y);
+    }
+
+    #[wasm_bindgen]
+    pub fn resize_polygon(
+        &self,
+        id: u64,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) {
+        self.document
+            .borrow_mut()
+            .resize_polygon(id, x, y, width, height);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_polygon_points(&self, id: u64, points: JsValue) -> Result<(), JsValue> {
+        let points: Vec<Point> =
+            serde_wasm_bindgen::from_value(points).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_polygon_points(id, points);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_polygon(&self, id: u64) {
+        self.document.borrow_mut().delete_polygon(id);
+    }
+
+    // regular polygon
+    #[wasm_bindgen]
+    pub fn add_regular_polygon(&self, x: f64, y: f64, width: f64, height: f64, sides: u32) -> u64 {
+        self.document
+            .borrow_mut()
+            .add_regular_polygon(Point::new(x, y), width, height, sides)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_regular_polygons(&self) -> JsValue {
+        let regular_polygons = self.document.borrow().get_regular_polygons();
+        to_value(&regular_polygons).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn move_regular_polygon(&self, id: u64, x: f64, y: f64) {
+        self.document
+            .borrow_mut()
+            .move_regular_polygon(id, Point::new(x, y));
+    }
+
+    #[wasm_bindgen]
+    pub fn resize_regular_polygon(&self, id: u64, width: f64, height: f64) {
+        self.document
+            .borrow_mut()
+            .resize_regular_polygon(id, width, height);
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_regular_polygon(&self, id: u64) {
+        self.document.borrow_mut().delete_regular_polygon(id);
+    }
+
+    // star
+    #[wasm_bindgen]
+    pub fn add_star(&self, x: f64, y: f64, width: f64, height: f64, sides: u32) -> u64 {
+        self.document
+            .borrow_mut()
+            .add_star(Point::new(x, y), width, height, sides)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_stars(&self) -> JsValue {
+        let stars = self.document.borrow().get_stars();
+        to_value(&stars).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn move_star(&self, id: u64, x: f64, y: f64) {
+        self.document
+            .borrow_mut()
+            .move_star(id, Point::new(x, y));
+    }
+
+    #[wasm_bindgen]
+    pub fn resize_star(&self, id: u64, width: f64, height: f64) {
+        self.document
+            .borrow_mut()
+            .resize_star(id, width, height);
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_star(&self, id: u64) {
+        self.document.borrow_mut().delete_star(id);
+    }
+
+    // frame
+    #[wasm_bindgen]
+    pub fn add_frame(&self, x: f64, y: f64, width: f64, height: f64, name: String) -> u64 {
+        self.document
+            .borrow_mut()
+            .add_frame(Point::new(x, y), width, height, name)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_frames(&self) -> JsValue {
+        let frames = self.document.borrow().get_frames();
+        to_value(&frames).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_frame_children(&self, id: u64) -> JsValue {
+        let ids = self.document.borrow().frame_children(id);
+        to_value(&ids).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_frame_of(&self, id: u64) -> JsValue {
+        let frame = self.document.borrow().frame_of(id);
+        to_value(&frame).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn move_frame(&self, id: u64, x: f64, y: f64) {
+        self.document
+            .borrow_mut()
+            .move_frame(id, Point::new(x, y));
+    }
+
+    #[wasm_bindgen]
+    pub fn resize_frame(&self, id: u64, width: f64, height: f64) {
+        self.document
+            .borrow_mut()
+            .resize_frame(id, width, height);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_frame_name(&self, id: u64, name: String) {
+        self.document
+            .borrow_mut()
+            .set_frame_name(id, name);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_frame_stroke_color(&self, id: u64, color: String) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
+        self.document
+            .borrow_mut()
+            .set_frame_stroke_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_frame_fill_color(&self, id: u64, color: Option<String>) -> Result<(), JsValue> {
+        let color = color.map(|color| parse_style::<Color>(&color)).transpose()?;
+        self.document
+            .borrow_mut()
+            .set_frame_fill_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_frame_clip(&self, id: u64, clip: bool) {
+        self.document
+            .borrow_mut()
+            .set_frame_clip(id, clip);
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_frame(&self, id: u64) {
+        self.document.borrow_mut().delete_frame(id);
+    }
+
+    // layer
+    #[wasm_bindgen]
+    pub fn add_layer(&self, name: String) -> u64 {
+        self.document.borrow_mut().add_layer(name)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_layers(&self) -> JsValue {
+        let layers = self.document.borrow().get_layers();
+        to_value(&layers).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_layer_of(&self, id: u64) -> JsValue {
+        let layer = self.document.borrow().layer_of(id);
+        to_value(&layer).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn set_layer_name(&self, id: u64, name: String) {
+        self.document
+            .borrow_mut()
+            .set_layer_name(id, name);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_layer_visible(&self, id: u64, visible: bool) {
+        self.document
+            .borrow_mut()
+            .set_layer_visible(id, visible);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_layer_locked(&self, id: u64, locked: bool) {
+        self.document
+            .borrow_mut()
+            .set_layer_locked(id, locked);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_layer_opacity(&self, id: u64, opacity: f64) {
+        self.document
+            .borrow_mut()
+            .set_layer_opacity(id, opacity);
+    }
+
+    #[wasm_bindgen]
+    pub fn reorder_layer(&self, id: u64, index: usize) -> bool {
+        self.document
+            .borrow_mut()
+            .reorder_layer(id, index)
+    }
+
+    #[wasm_bindgen]
+    pub fn move_to_layer(&self, ids: JsValue, layer_id: u64) -> Result<bool, JsValue> {
+        let ids: Vec<u64> =
+            serde_wasm_bindgen::from_value(ids).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().move_to_layer(ids, layer_id))
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_layer(&self, id: u64) {
+        self.document.borrow_mut().delete_layer(id);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_render_order(&self) -> JsValue {
+        let ids = self.document.borrow().render_order();
+        to_value(&ids).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_layer_opacity(&self, id: u64) -> f64 {
+        self.document.borrow().element_layer_opacity(id)
+    }
+
     #[wasm_bindgen]
     pub fn add_ellipse(&self, x: f64, y: f64, radius_x: f64, radius_y: f64) -> u64 {
         self.document
@@ -161,7 +629,7 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_ellipses(&self) -> JsValue {
-        let ellipses = self.document.borrow().get_ellipses().to_vec();
+        let ellipses = self.document.borrow().get_ellipses();
         to_value(&ellipses).unwrap()
     }
 
@@ -214,7 +682,7 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_lines(&self) -> JsValue {
-        let lines = self.document.borrow().get_lines().to_vec();
+        let lines = self.document.borrow().get_lines();
         to_value(&lines).unwrap()
     }
 
@@ -269,7 +737,7 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_arrows(&self) -> JsValue {
-        let arrows = self.document.borrow().get_arrows().to_vec();
+        let arrows = self.document.borrow().get_arrows();
         to_value(&arrows).unwrap()
     }
 
@@ -315,7 +783,7 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_paths(&self) -> JsValue {
-        let paths = self.document.borrow().get_paths().to_vec();
+        let paths = self.document.borrow().get_paths();
         to_value(&paths).unwrap()
     }
 
@@ -330,10 +798,12 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_path_stroke_color(&self, id: u64, color: String, save_history: bool) {
+    pub fn set_path_stroke_color(&self, id: u64, color: String, save_history: bool) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
         self.document
             .borrow_mut()
             .set_path_stroke_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -344,10 +814,12 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_path_dash_pattern(&self, id: u64, pattern: String, save_history: bool) {
+    pub fn set_path_dash_pattern(&self, id: u64, pattern: String, save_history: bool) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
         self.document
             .borrow_mut()
             .set_path_dash_pattern(id, pattern, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -365,6 +837,25 @@ impl EditorApi {
             .set_path_points(id, points, save_history);
     }
 
+    #[wasm_bindgen]
+    pub fn set_path_pressures(&self, id: u64, pressures: JsValue) -> Result<(), JsValue> {
+        let pressures: Vec<f64> =
+            serde_wasm_bindgen::from_value(pressures).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_path_pressures(id, pressures);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn stroke_outline(&self, points: JsValue, pressures: JsValue, line_width: f64) -> Result<JsValue, JsValue> {
+        let points: Vec<Point> =
+            serde_wasm_bindgen::from_value(points).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let pressures: Vec<f64> = serde_wasm_bindgen::from_value(pressures).unwrap_or_default();
+        to_value(&stroke_outline(&points, &pressures, line_width))
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
     #[wasm_bindgen]
     pub fn move_path(&self, id: u64, delta_x: f64, delta_y: f64, save_history: bool) {
         self.document
@@ -372,6 +863,13 @@ impl EditorApi {
             .move_path(id, delta_x, delta_y, save_history);
     }
 
+    #[wasm_bindgen]
+    pub fn process_path(&self, id: u64, options: JsValue) -> Result<bool, JsValue> {
+        let options: PathOptions =
+            serde_wasm_bindgen::from_value(options).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().process_path(id, &options))
+    }
+
     #[wasm_bindgen]
     pub fn resize_path(
         &self,
@@ -413,7 +911,7 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_images(&self) -> JsValue {
-        let images = self.document.borrow().get_images().to_vec();
+        let images = self.document.borrow().get_images();
         to_value(&images).unwrap()
     }
 
@@ -475,10 +973,21 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_texts(&self) -> JsValue {
-        let texts = self.document.borrow().get_texts().to_vec();
+        let texts = self.document.borrow().get_texts();
         to_value(&texts).unwrap()
     }
 
+    // returns None when the element cannot take a label or already has one
+    #[wasm_bindgen]
+    pub fn add_label(&self, container_id: u64, content: String) -> Option<u64> {
+        self.document.borrow_mut().add_label(container_id, content)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_label(&self, container_id: u64) -> Option<u64> {
+        self.document.borrow().get_label(container_id)
+    }
+
     #[wasm_bindgen]
     pub fn move_text(&self, id: u64, x: f64, y: f64, save_history: bool) {
         self.document
@@ -525,24 +1034,30 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_text_font_weight(&self, id: u64, font_weight: String, save_history: bool) {
+    pub fn set_text_font_weight(&self, id: u64, font_weight: String, save_history: bool) -> Result<(), JsValue> {
+        let font_weight: FontWeight = parse_style(&font_weight)?;
         self.document
             .borrow_mut()
             .set_text_font_weight(id, font_weight, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
-    pub fn set_text_text_align(&self, id: u64, text_align: String, save_history: bool) {
+    pub fn set_text_text_align(&self, id: u64, text_align: String, save_history: bool) -> Result<(), JsValue> {
+        let text_align: TextAlign = parse_style(&text_align)?;
         self.document
             .borrow_mut()
             .set_text_text_align(id, text_align, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
-    pub fn set_text_color(&self, id: u64, color: String, save_history: bool) {
+    pub fn set_text_color(&self, id: u64, color: String, save_history: bool) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
         self.document
             .borrow_mut()
             .set_text_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -559,18 +1074,108 @@ impl EditorApi {
             .set_text_rotation(id, angle, save_history);
     }
 
+    // lines and fragments in document space, the same layout the SVG export uses
+    #[wasm_bindgen]
+    pub fn get_text_layout(&self, id: u64) -> JsValue {
+        let layout = self.document.borrow().text_layout(id);
+        to_value(&layout).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn text_offset_at(&self, id: u64, x: f64, y: f64) -> Option<usize> {
+        self.document.borrow().text_offset_at(id, Point::new(x, y))
+    }
+
+    // takes "auto_width", "auto_height" or "fixed"
+    #[wasm_bindgen]
+    pub fn set_text_sizing(&self, id: u64, sizing: JsValue) -> Result<(), JsValue> {
+        let sizing: TextSizing =
+            serde_wasm_bindgen::from_value(sizing).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_text_sizing(id, sizing);
+        Ok(())
+    }
+
+    // takes "top", "middle" or "bottom"
+    #[wasm_bindgen]
+    pub fn set_text_vertical_align(&self, id: u64, vertical_align: JsValue) -> Result<(), JsValue> {
+        let vertical_align: VerticalAlign =
+            serde_wasm_bindgen::from_value(vertical_align).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_text_vertical_align(id, vertical_align);
+        Ok(())
+    }
+
+    // widths in ems of the characters from space to tilde, as measured by the canvas; every
+    // text is laid out again with them
+    #[wasm_bindgen]
+    pub fn set_font_widths(&self, family: String, bold: bool, italic: bool, widths: Vec<f64>) -> bool {
+        if !self.metrics.borrow_mut().insert(&family, bold, italic, widths) {
+            return false;
+        }
+        let metrics = self.metrics.borrow().clone();
+        self.document.borrow_mut().set_font_metrics(Box::new(metrics));
+        true
+    }
+
+    // offsets count characters (code points), not UTF-16 units; a null style continues the
+    // style of the character before the offset
+    #[wasm_bindgen]
+    pub fn insert_text(&self, id: u64, offset: usize, text: String, style: JsValue) -> Result<(), JsValue> {
+        let style: Option<TextStyle> =
+            serde_wasm_bindgen::from_value(style).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .insert_text(id, offset, &text, style);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_text_range(&self, id: u64, start: usize, end: usize) {
+        self.document
+            .borrow_mut()
+            .delete_text_range(id, start, end);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_text_style_range(&self, id: u64, start: usize, end: usize, patch: JsValue) -> Result<(), JsValue> {
+        let patch: TextStylePatch =
+            serde_wasm_bindgen::from_value(patch).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_text_style_range(id, start, end, &patch);
+        Ok(())
+    }
+
+    // takes "none", "bullet" or "numbered"
+    #[wasm_bindgen]
+    pub fn set_text_list(&self, id: u64, start: usize, end: usize, list: JsValue) -> Result<(), JsValue> {
+        let list: ListKind =
+            serde_wasm_bindgen::from_value(list).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_text_list(id, start, end, list);
+        Ok(())
+    }
+
     #[wasm_bindgen]
-    pub fn set_rectangle_stroke_color(&self, id: u64, color: String, save_history: bool) {
+    pub fn set_rectangle_stroke_color(&self, id: u64, color: String, save_history: bool) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
         self.document
             .borrow_mut()
             .set_rectangle_stroke_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
-    pub fn set_rectangle_fill_color(&self, id: u64, color: Option<String>, save_history: bool) {
+    pub fn set_rectangle_fill_color(&self, id: u64, color: Option<String>, save_history: bool) -> Result<(), JsValue> {
+        let color = color.map(|color| parse_style::<Color>(&color)).transpose()?;
         self.document
             .borrow_mut()
             .set_rectangle_fill_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -581,10 +1186,12 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_rectangle_dash_pattern(&self, id: u64, pattern: String, save_history: bool) {
+    pub fn set_rectangle_dash_pattern(&self, id: u64, pattern: String, save_history: bool) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
         self.document
             .borrow_mut()
             .set_rectangle_dash_pattern(id, pattern, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -602,17 +1209,21 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_ellipse_stroke_color(&self, id: u64, color: String, save_history: bool) {
+    pub fn set_ellipse_stroke_color(&self, id: u64, color: String, save_history: bool) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
         self.document
             .borrow_mut()
             .set_ellipse_stroke_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
-    pub fn set_ellipse_fill_color(&self, id: u64, color: Option<String>, save_history: bool) {
+    pub fn set_ellipse_fill_color(&self, id: u64, color: Option<String>, save_history: bool) -> Result<(), JsValue> {
+        let color = color.map(|color| parse_style::<Color>(&color)).transpose()?;
         self.document
             .borrow_mut()
             .set_ellipse_fill_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -623,10 +1234,12 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_ellipse_dash_pattern(&self, id: u64, pattern: String, save_history: bool) {
+    pub fn set_ellipse_dash_pattern(&self, id: u64, pattern: String, save_history: bool) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
         self.document
             .borrow_mut()
             .set_ellipse_dash_pattern(id, pattern, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -637,17 +1250,21 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_diamond_stroke_color(&self, id: u64, color: String, save_history: bool) {
+    pub fn set_diamond_stroke_color(&self, id: u64, color: String, save_history: bool) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
         self.document
             .borrow_mut()
             .set_diamond_stroke_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
-    pub fn set_diamond_fill_color(&self, id: u64, color: Option<String>, save_history: bool) {
+    pub fn set_diamond_fill_color(&self, id: u64, color: Option<String>, save_history: bool) -> Result<(), JsValue> {
+        let color = color.map(|color| parse_style::<Color>(&color)).transpose()?;
         self.document
             .borrow_mut()
             .set_diamond_fill_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -658,10 +1275,12 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_diamond_dash_pattern(&self, id: u64, pattern: String, save_history: bool) {
+    pub fn set_diamond_dash_pattern(&self, id: u64, pattern: String, save_history: bool) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
         self.document
             .borrow_mut()
             .set_diamond_dash_pattern(id, pattern, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -679,10 +1298,177 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_line_stroke_color(&self, id: u64, color: String, save_history: bool) {
+    pub fn set_polygon_stroke_color(&self, id: u64, color: String) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
+        self.document
+            .borrow_mut()
+            .set_polygon_stroke_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_polygon_fill_color(&self, id: u64, color: Option<String>) -> Result<(), JsValue> {
+        let color = color.map(|color| parse_style::<Color>(&color)).transpose()?;
+        self.document
+            .borrow_mut()
+            .set_polygon_fill_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_polygon_line_width(&self, id: u64, width: f64) {
+        self.document
+            .borrow_mut()
+            .set_polygon_line_width(id, width);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_polygon_dash_pattern(&self, id: u64, pattern: String) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
+        self.document
+            .borrow_mut()
+            .set_polygon_dash_pattern(id, pattern);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_polygon_rotation(&self, id: u64, angle: f64) {
+        self.document
+            .borrow_mut()
+            .set_polygon_rotation(id, angle);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_polygon_border_radius(&self, id: u64, radius: f64) {
+        self.document
+            .borrow_mut()
+            .set_polygon_border_radius(id, radius);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_regular_polygon_stroke_color(&self, id: u64, color: String) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
+        self.document
+            .borrow_mut()
+            .set_regular_polygon_stroke_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_regular_polygon_fill_color(&self, id: u64, color: Option<String>) -> Result<(), JsValue> {
+        let color = color.map(|color| parse_style::<Color>(&color)).transpose()?;
+        self.document
+            .borrow_mut()
+            .set_regular_polygon_fill_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_regular_polygon_line_width(&self, id: u64, width: f64) {
+        self.document
+            .borrow_mut()
+            .set_regular_polygon_line_width(id, width);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_regular_polygon_dash_pattern(&self, id: u64, pattern: String) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
+        self.document
+            .borrow_mut()
+            .set_regular_polygon_dash_pattern(id, pattern);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_regular_polygon_rotation(&self, id: u64, angle: f64) {
+        self.document
+            .borrow_mut()
+            .set_regular_polygon_rotation(id, angle);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_regular_polygon_border_radius(&self, id: u64, radius: f64) {
+        self.document
+            .borrow_mut()
+            .set_regular_polygon_border_radius(id, radius);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_regular_polygon_sides(&self, id: u64, sides: u32) {
+        self.document
+            .borrow_mut()
+            .set_regular_polygon_sides(id, sides);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_stroke_color(&self, id: u64, color: String) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
+        self.document
+            .borrow_mut()
+            .set_star_stroke_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_fill_color(&self, id: u64, color: Option<String>) -> Result<(), JsValue> {
+        let color = color.map(|color| parse_style::<Color>(&color)).transpose()?;
+        self.document
+            .borrow_mut()
+            .set_star_fill_color(id, color);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_line_width(&self, id: u64, width: f64) {
+        self.document
+            .borrow_mut()
+            .set_star_line_width(id, width);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_dash_pattern(&self, id: u64, pattern: String) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
+        self.document
+            .borrow_mut()
+            .set_star_dash_pattern(id, pattern);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_rotation(&self, id: u64, angle: f64) {
+        self.document
+            .borrow_mut()
+            .set_star_rotation(id, angle);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_border_radius(&self, id: u64, radius: f64) {
+        self.document
+            .borrow_mut()
+            .set_star_border_radius(id, radius);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_sides(&self, id: u64, sides: u32) {
+        self.document
+            .borrow_mut()
+            .set_star_sides(id, sides);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_star_inner_radius(&self, id: u64, ratio: f64) {
+        self.document
+            .borrow_mut()
+            .set_star_inner_radius(id, ratio);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_line_stroke_color(&self, id: u64, color: String, save_history: bool) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
         self.document
             .borrow_mut()
             .set_line_stroke_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -700,17 +1486,48 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_line_dash_pattern(&self, id: u64, pattern: String, save_history: bool) {
+    pub fn set_line_dash_pattern(&self, id: u64, pattern: String, save_history: bool) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
         self.document
             .borrow_mut()
             .set_line_dash_pattern(id, pattern, save_history);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_line_start_marker(&self, id: u64, marker: JsValue) -> Result<(), JsValue> {
+        let marker: Marker =
+            serde_wasm_bindgen::from_value(marker).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_line_start_marker(id, marker);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_line_end_marker(&self, id: u64, marker: JsValue) -> Result<(), JsValue> {
+        let marker: Marker =
+            serde_wasm_bindgen::from_value(marker).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_line_end_marker(id, marker);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_line_marker_size(&self, id: u64, size: f64) {
+        self.document
+            .borrow_mut()
+            .set_line_marker_size(id, size);
     }
 
     #[wasm_bindgen]
-    pub fn set_arrow_stroke_color(&self, id: u64, color: String, save_history: bool) {
+    pub fn set_arrow_stroke_color(&self, id: u64, color: String, save_history: bool) -> Result<(), JsValue> {
+        let color: Color = parse_style(&color)?;
         self.document
             .borrow_mut()
             .set_arrow_stroke_color(id, color, save_history);
+        Ok(())
     }
 
     #[wasm_bindgen]
@@ -728,10 +1545,77 @@ impl EditorApi {
     }
 
     #[wasm_bindgen]
-    pub fn set_arrow_dash_pattern(&self, id: u64, pattern: String, save_history: bool) {
+    pub fn set_arrow_dash_pattern(&self, id: u64, pattern: String, save_history: bool) -> Result<(), JsValue> {
+        let pattern: DashPattern = parse_style(&pattern)?;
         self.document
             .borrow_mut()
             .set_arrow_dash_pattern(id, pattern, save_history);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_arrow_start_marker(&self, id: u64, marker: JsValue) -> Result<(), JsValue> {
+        let marker: Marker =
+            serde_wasm_bindgen::from_value(marker).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_arrow_start_marker(id, marker);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_arrow_end_marker(&self, id: u64, marker: JsValue) -> Result<(), JsValue> {
+        let marker: Marker =
+            serde_wasm_bindgen::from_value(marker).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_arrow_end_marker(id, marker);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_arrow_marker_size(&self, id: u64, size: f64) {
+        self.document
+            .borrow_mut()
+            .set_arrow_marker_size(id, size);
+    }
+
+    #[wasm_bindgen]
+    pub fn align(&self, ids: JsValue, alignment: JsValue) -> Result<bool, JsValue> {
+        let ids: Vec<u64> =
+            serde_wasm_bindgen::from_value(ids).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let alignment: Alignment =
+            serde_wasm_bindgen::from_value(alignment).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().align(&ids, alignment))
+    }
+
+    #[wasm_bindgen]
+    pub fn distribute(&self, ids: JsValue, axis: JsValue, spacing: JsValue) -> Result<bool, JsValue> {
+        let ids: Vec<u64> =
+            serde_wasm_bindgen::from_value(ids).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let axis: Axis =
+            serde_wasm_bindgen::from_value(axis).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let spacing: Spacing =
+            serde_wasm_bindgen::from_value(spacing).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().distribute(&ids, axis, spacing))
+    }
+
+    #[wasm_bindgen]
+    pub fn transform(&self, ids: JsValue, transform: JsValue, origin_x: f64, origin_y: f64) -> Result<bool, JsValue> {
+        let ids: Vec<u64> =
+            serde_wasm_bindgen::from_value(ids).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let transform: Affine =
+            serde_wasm_bindgen::from_value(transform).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().transform(&ids, &transform, Point::new(origin_x, origin_y)))
+    }
+
+    #[wasm_bindgen]
+    pub fn flip(&self, ids: JsValue, axis: JsValue) -> Result<bool, JsValue> {
+        let ids: Vec<u64> =
+            serde_wasm_bindgen::from_value(ids).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let axis: Axis =
+            serde_wasm_bindgen::from_value(axis).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(self.document.borrow_mut().flip(&ids, axis))
     }
 
     #[wasm_bindgen]
@@ -748,10 +1632,41 @@ impl EditorApi {
 
     #[wasm_bindgen]
     pub fn get_groups(&self) -> JsValue {
-        let groups = self.document.borrow().get_groups().to_vec();
+        let groups = self.document.borrow().get_groups();
         to_value(&groups).unwrap()
     }
 
+    #[wasm_bindgen]
+    pub fn get_group_descendants(&self, id: u64) -> JsValue {
+        let ids = self.document.borrow().group_descendants(id);
+        to_value(&ids).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_parent_group(&self, id: u64) -> JsValue {
+        let parent = self.document.borrow().parent_group(id);
+        to_value(&parent).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn move_group(&self, id: u64, delta_x: f64, delta_y: f64) -> bool {
+        self.document
+            .borrow_mut()
+            .move_group(id, delta_x, delta_y)
+    }
+
+    #[wasm_bindgen]
+    pub fn rotate_group(&self, id: u64, angle: f64) -> bool {
+        self.document.borrow_mut().rotate_group(id, angle)
+    }
+
+    #[wasm_bindgen]
+    pub fn resize_group(&self, id: u64, x: f64, y: f64, width: f64, height: f64) -> bool {
+        self.document
+            .borrow_mut()
+            .resize_group(id, x, y, width, height)
+    }
+
     #[wasm_bindgen]
     pub fn bring_shape_to_front(&self, id: u64) {
         self.document.borrow_mut().bring_shape_to_front(id);
@@ -782,6 +1697,16 @@ impl EditorApi {
         self.document.borrow_mut().deserialize(data)
     }
 
+    #[wasm_bindgen]
+    pub fn export_svg(&self) -> String {
+        self.document.borrow().export_svg()
+    }
+
+    #[wasm_bindgen]
+    pub fn export_frame_svg(&self, id: u64) -> Option<String> {
+        self.document.borrow().export_frame_svg(id)
+    }
+
     #[wasm_bindgen]
     pub fn save_snapshot(&self) {
         self.document.borrow_mut().save_snapshot();
@@ -798,4 +1723,53 @@ impl EditorApi {
             .borrow_mut()
             .set_element_locked(id, locked, save_history);
     }
+
+    #[wasm_bindgen]
+    pub fn set_element_opacity(&self, id: u64, opacity: f64) {
+        self.document
+            .borrow_mut()
+            .set_element_opacity(id, opacity);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_element_blend_mode(&self, id: u64, blend_mode: JsValue) -> Result<(), JsValue> {
+        let blend_mode: BlendMode =
+            serde_wasm_bindgen::from_value(blend_mode).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document
+            .borrow_mut()
+            .set_element_blend_mode(id, blend_mode);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_element_roughness(&self, id: u64, roughness: f64) {
+        self.document
+            .borrow_mut()
+            .set_element_roughness(id, roughness);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_element_seed(&self, id: u64, seed: u32) {
+        self.document.borrow_mut().set_element_seed(id, seed);
+    }
+
+    // takes a color string, a fill object or null
+    #[wasm_bindgen]
+    pub fn set_element_fill(&self, id: u64, fill: JsValue) -> Result<(), JsValue> {
+        let fill: Option<Fill> =
+            serde_wasm_bindgen::from_value(fill).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.document.borrow_mut().set_element_fill(id, fill);
+        Ok(())
+    }
+}
+
+// style values arrive as text; bad ones are thrown back to JS with the reason
+fn parse_style<T: FromStr<Err = StyleError>>(value: &str) -> Result<T, JsValue> {
+    value.parse().map_err(|err: StyleError| JsValue::from_str(&err.to_string()))
+}
+
+impl Default for EditorApi {
+    fn default() -> Self {
+        Self::new()
+    }
 }
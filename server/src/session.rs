@@ -111,6 +111,7 @@ impl Session {
         session
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new_with_timestamps(
         id: String,
         document: Document,
@@ -357,7 +358,9 @@ impl Session {
 
     fn from_persisted(snapshot: PersistedSession) -> Self {
         let mut document = Document::new();
-        let _ = document.deserialize(&snapshot.document);
+        if !snapshot.document.is_empty() && !document.deserialize(&snapshot.document) {
+            tracing::warn!("Failed to load the stored document of session {}", snapshot.id);
+        }
 
         let has_token_secret = snapshot.token_secret.is_some();
         let token_secret = snapshot
@@ -2,6 +2,10 @@ use crate::session::{ClientInfo, ClientRole, Session};
 use axum::extract::ws::{Message, WebSocket};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use rustboard_editor::{
+    Affine, Alignment, Axis, Binding, BlendMode, Color, ConnectorEnd, DashPattern, Document, Fill, FontWeight,
+    ListKind, Marker, Routing, Smoothing, Spacing, TextAlign, TextSizing, TextStyle, TextStylePatch, VerticalAlign,
+};
 use std::sync::{Arc, Mutex};
 use tracing::{error, info, warn};
 
@@ -81,6 +85,101 @@ pub enum Operation {
     DeleteDiamond {
         id: u64,
     },
+    AddPolygon {
+        id: u64,
+        points: Vec<Point>,
+    },
+    MovePolygon {
+        id: u64,
+        offset_x: f64,
+        offset_y: f64,
+    },
+    SetPolygonPoints {
+        id: u64,
+        points: Vec<Point>,
+    },
+    DeletePolygon {
+        id: u64,
+    },
+    AddRegularPolygon {
+        id: u64,
+        position: Point,
+        width: f64,
+        height: f64,
+        sides: u32,
+    },
+    MoveRegularPolygon {
+        id: u64,
+        position: Point,
+    },
+    ResizeRegularPolygon {
+        id: u64,
+        width: f64,
+        height: f64,
+    },
+    DeleteRegularPolygon {
+        id: u64,
+    },
+    AddStar {
+        id: u64,
+        position: Point,
+        width: f64,
+        height: f64,
+        sides: u32,
+    },
+    MoveStar {
+        id: u64,
+        position: Point,
+    },
+    ResizeStar {
+        id: u64,
+        width: f64,
+        height: f64,
+    },
+    DeleteStar {
+        id: u64,
+    },
+    AddFrame {
+        id: u64,
+        position: Point,
+        width: f64,
+        height: f64,
+        name: String,
+    },
+    MoveFrame {
+        id: u64,
+        position: Point,
+    },
+    ResizeFrame {
+        id: u64,
+        width: f64,
+        height: f64,
+    },
+    DeleteFrame {
+        id: u64,
+    },
+    AddLayer {
+        id: u64,
+        name: String,
+    },
+    SetLayerStyle {
+        id: u64,
+        name: Option<String>,
+        visible: Option<bool>,
+        locked: Option<bool>,
+        opacity: Option<f64>,
+    },
+    ReorderLayer {
+        id: u64,
+        index: usize,
+    },
+    MoveToLayer {
+        id: u64,
+        element_ids: Vec<u64>,
+    },
+    DeleteLayer {
+        id: u64,
+    },
     AddLine {
         id: u64,
         start: Point,
@@ -119,6 +218,7 @@ pub enum Operation {
     SetPathPoints {
         id: u64,
         points: Vec<Point>,
+        pressures: Option<Vec<f64>>,
     },
     DeletePath {
         id: u64,
@@ -149,6 +249,11 @@ pub enum Operation {
         height: f64,
         content: String,
     },
+    AddLabel {
+        id: u64,
+        container_id: u64,
+        content: String,
+    },
     MoveText {
         id: u64,
         position: Point,
@@ -162,67 +267,171 @@ pub enum Operation {
         id: u64,
         content: String,
     },
+    // offsets count characters, matching the editor's rich text APIs
+    InsertText {
+        id: u64,
+        offset: usize,
+        text: String,
+        style: Option<TextStyle>,
+    },
+    DeleteTextRange {
+        id: u64,
+        start: usize,
+        end: usize,
+    },
+    SetTextStyleRange {
+        id: u64,
+        start: usize,
+        end: usize,
+        patch: TextStylePatch,
+    },
+    SetTextList {
+        id: u64,
+        start: usize,
+        end: usize,
+        list: ListKind,
+    },
     DeleteText {
         id: u64,
     },
     SetRectangleStyle {
         id: u64,
-        stroke_color: Option<String>,
-        fill_color: Option<Option<String>>,
+        stroke_color: Option<Color>,
+        fill_color: Option<Option<Fill>>,
         line_width: Option<f64>,
-        dash_pattern: Option<String>,
+        dash_pattern: Option<DashPattern>,
         border_radius: Option<f64>,
         rotation_angle: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+        roughness: Option<f64>,
+        seed: Option<u32>,
     },
     SetEllipseStyle {
         id: u64,
-        stroke_color: Option<String>,
-        fill_color: Option<Option<String>>,
+        stroke_color: Option<Color>,
+        fill_color: Option<Option<Fill>>,
         line_width: Option<f64>,
-        dash_pattern: Option<String>,
+        dash_pattern: Option<DashPattern>,
         rotation_angle: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+        roughness: Option<f64>,
+        seed: Option<u32>,
     },
     SetDiamondStyle {
         id: u64,
-        stroke_color: Option<String>,
-        fill_color: Option<Option<String>>,
+        stroke_color: Option<Color>,
+        fill_color: Option<Option<Fill>>,
+        line_width: Option<f64>,
+        dash_pattern: Option<DashPattern>,
+        border_radius: Option<f64>,
+        rotation_angle: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+        roughness: Option<f64>,
+        seed: Option<u32>,
+    },
+    SetPolygonStyle {
+        id: u64,
+        stroke_color: Option<Color>,
+        fill_color: Option<Option<Fill>>,
         line_width: Option<f64>,
-        dash_pattern: Option<String>,
+        dash_pattern: Option<DashPattern>,
         border_radius: Option<f64>,
         rotation_angle: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+    },
+    SetRegularPolygonStyle {
+        id: u64,
+        stroke_color: Option<Color>,
+        fill_color: Option<Option<Fill>>,
+        line_width: Option<f64>,
+        dash_pattern: Option<DashPattern>,
+        border_radius: Option<f64>,
+        rotation_angle: Option<f64>,
+        sides: Option<u32>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+    },
+    SetStarStyle {
+        id: u64,
+        stroke_color: Option<Color>,
+        fill_color: Option<Option<Fill>>,
+        line_width: Option<f64>,
+        dash_pattern: Option<DashPattern>,
+        border_radius: Option<f64>,
+        rotation_angle: Option<f64>,
+        sides: Option<u32>,
+        inner_radius: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+    },
+    SetFrameStyle {
+        id: u64,
+        name: Option<String>,
+        stroke_color: Option<Color>,
+        fill_color: Option<Option<Color>>,
+        line_width: Option<f64>,
+        clip: Option<bool>,
     },
     SetLineStyle {
         id: u64,
-        stroke_color: Option<String>,
+        stroke_color: Option<Color>,
         line_width: Option<f64>,
-        dash_pattern: Option<String>,
+        dash_pattern: Option<DashPattern>,
+        routing: Option<Routing>,
+        start_marker: Option<Marker>,
+        end_marker: Option<Marker>,
+        marker_size: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+        roughness: Option<f64>,
+        seed: Option<u32>,
     },
     SetArrowStyle {
         id: u64,
-        stroke_color: Option<String>,
+        stroke_color: Option<Color>,
         line_width: Option<f64>,
-        dash_pattern: Option<String>,
+        dash_pattern: Option<DashPattern>,
+        routing: Option<Routing>,
+        start_marker: Option<Marker>,
+        end_marker: Option<Marker>,
+        marker_size: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
+        roughness: Option<f64>,
+        seed: Option<u32>,
     },
     SetPathStyle {
         id: u64,
-        stroke_color: Option<String>,
+        stroke_color: Option<Color>,
         line_width: Option<f64>,
-        dash_pattern: Option<String>,
+        dash_pattern: Option<DashPattern>,
         rotation_angle: Option<f64>,
+        smoothing: Option<Smoothing>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
     },
     SetImageStyle {
         id: u64,
         rotation_angle: Option<f64>,
+        opacity: Option<f64>,
+        blend_mode: Option<BlendMode>,
     },
     SetTextStyle {
         id: u64,
-        color: Option<String>,
+        color: Option<Color>,
         opacity: Option<f64>,
         font_size: Option<f64>,
         font_family: Option<String>,
-        font_weight: Option<String>,
-        text_align: Option<String>,
+        font_weight: Option<FontWeight>,
+        text_align: Option<TextAlign>,
+        sizing: Option<TextSizing>,
+        vertical_align: Option<VerticalAlign>,
         rotation_angle: Option<f64>,
+        blend_mode: Option<BlendMode>,
     },
     BringToFront {
         id: u64,
@@ -247,6 +456,52 @@ pub enum Operation {
     UngroupElements {
         id: u64,
     },
+    MoveGroup {
+        id: u64,
+        offset_x: f64,
+        offset_y: f64,
+    },
+    RotateGroup {
+        id: u64,
+        angle: f64,
+    },
+    ResizeGroup {
+        id: u64,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    DeleteGroup {
+        id: u64,
+    },
+    Align {
+        element_ids: Vec<u64>,
+        alignment: Alignment,
+    },
+    Distribute {
+        element_ids: Vec<u64>,
+        axis: Axis,
+        spacing: Spacing,
+    },
+    Transform {
+        element_ids: Vec<u64>,
+        transform: Affine,
+        origin: Point,
+    },
+    Flip {
+        element_ids: Vec<u64>,
+        axis: Axis,
+    },
+    BindConnector {
+        id: u64,
+        end: ConnectorEnd,
+        binding: Option<Binding>,
+    },
+    SetControlPoints {
+        id: u64,
+        control_points: Vec<Point>,
+    },
     FullSync {
         data: String,
     },
@@ -305,6 +560,27 @@ impl Operation {
             | Operation::MoveDiamond { id, .. }
             | Operation::ResizeDiamond { id, .. }
             | Operation::DeleteDiamond { id, .. }
+            | Operation::AddPolygon { id, .. }
+            | Operation::MovePolygon { id, .. }
+            | Operation::SetPolygonPoints { id, .. }
+            | Operation::DeletePolygon { id, .. }
+            | Operation::AddRegularPolygon { id, .. }
+            | Operation::MoveRegularPolygon { id, .. }
+            | Operation::ResizeRegularPolygon { id, .. }
+            | Operation::DeleteRegularPolygon { id, .. }
+            | Operation::AddStar { id, .. }
+            | Operation::MoveStar { id, .. }
+            | Operation::ResizeStar { id, .. }
+            | Operation::DeleteStar { id, .. }
+            | Operation::AddFrame { id, .. }
+            | Operation::MoveFrame { id, .. }
+            | Operation::ResizeFrame { id, .. }
+            | Operation::DeleteFrame { id, .. }
+            | Operation::AddLayer { id, .. }
+            | Operation::SetLayerStyle { id, .. }
+            | Operation::ReorderLayer { id, .. }
+            | Operation::MoveToLayer { id, .. }
+            | Operation::DeleteLayer { id, .. }
             | Operation::AddLine { id, .. }
             | Operation::MoveLine { id, .. }
             | Operation::DeleteLine { id, .. }
@@ -320,13 +596,22 @@ impl Operation {
             | Operation::ResizeImage { id, .. }
             | Operation::DeleteImage { id, .. }
             | Operation::AddText { id, .. }
+            | Operation::AddLabel { id, .. }
             | Operation::MoveText { id, .. }
             | Operation::ResizeText { id, .. }
             | Operation::UpdateText { id, .. }
+            | Operation::InsertText { id, .. }
+            | Operation::DeleteTextRange { id, .. }
+            | Operation::SetTextStyleRange { id, .. }
+            | Operation::SetTextList { id, .. }
             | Operation::DeleteText { id, .. }
             | Operation::SetRectangleStyle { id, .. }
             | Operation::SetEllipseStyle { id, .. }
             | Operation::SetDiamondStyle { id, .. }
+            | Operation::SetPolygonStyle { id, .. }
+            | Operation::SetRegularPolygonStyle { id, .. }
+            | Operation::SetStarStyle { id, .. }
+            | Operation::SetFrameStyle { id, .. }
             | Operation::SetLineStyle { id, .. }
             | Operation::SetArrowStyle { id, .. }
             | Operation::SetPathStyle { id, .. }
@@ -338,8 +623,18 @@ impl Operation {
             | Operation::SendToBack { id, .. }
             | Operation::SetElementLock { id, .. }
             | Operation::GroupElements { id, .. }
-            | Operation::UngroupElements { id, .. } => Some(*id),
-            Operation::FullSync { .. } => None,
+            | Operation::UngroupElements { id, .. }
+            | Operation::MoveGroup { id, .. }
+            | Operation::RotateGroup { id, .. }
+            | Operation::ResizeGroup { id, .. }
+            | Operation::DeleteGroup { id, .. }
+            | Operation::BindConnector { id, .. }
+            | Operation::SetControlPoints { id, .. } => Some(*id),
+            Operation::Align { .. }
+            | Operation::Distribute { .. }
+            | Operation::Transform { .. }
+            | Operation::Flip { .. }
+            | Operation::FullSync { .. } => None,
         }
     }
 
@@ -357,6 +652,27 @@ impl Operation {
             | Operation::MoveDiamond { id, .. }
             | Operation::ResizeDiamond { id, .. }
             | Operation::DeleteDiamond { id, .. }
+            | Operation::AddPolygon { id, .. }
+            | Operation::MovePolygon { id, .. }
+            | Operation::SetPolygonPoints { id, .. }
+            | Operation::DeletePolygon { id, .. }
+            | Operation::AddRegularPolygon { id, .. }
+            | Operation::MoveRegularPolygon { id, .. }
+            | Operation::ResizeRegularPolygon { id, .. }
+            | Operation::DeleteRegularPolygon { id, .. }
+            | Operation::AddStar { id, .. }
+            | Operation::MoveStar { id, .. }
+            | Operation::ResizeStar { id, .. }
+            | Operation::DeleteStar { id, .. }
+            | Operation::AddFrame { id, .. }
+            | Operation::MoveFrame { id, .. }
+            | Operation::ResizeFrame { id, .. }
+            | Operation::DeleteFrame { id, .. }
+            | Operation::AddLayer { id, .. }
+            | Operation::SetLayerStyle { id, .. }
+            | Operation::ReorderLayer { id, .. }
+            | Operation::MoveToLayer { id, .. }
+            | Operation::DeleteLayer { id, .. }
             | Operation::AddLine { id, .. }
             | Operation::MoveLine { id, .. }
             | Operation::DeleteLine { id, .. }
@@ -372,13 +688,22 @@ impl Operation {
             | Operation::ResizeImage { id, .. }
             | Operation::DeleteImage { id, .. }
             | Operation::AddText { id, .. }
+            | Operation::AddLabel { id, .. }
             | Operation::MoveText { id, .. }
             | Operation::ResizeText { id, .. }
             | Operation::UpdateText { id, .. }
+            | Operation::InsertText { id, .. }
+            | Operation::DeleteTextRange { id, .. }
+            | Operation::SetTextStyleRange { id, .. }
+            | Operation::SetTextList { id, .. }
             | Operation::DeleteText { id, .. }
             | Operation::SetRectangleStyle { id, .. }
             | Operation::SetEllipseStyle { id, .. }
             | Operation::SetDiamondStyle { id, .. }
+            | Operation::SetPolygonStyle { id, .. }
+            | Operation::SetRegularPolygonStyle { id, .. }
+            | Operation::SetStarStyle { id, .. }
+            | Operation::SetFrameStyle { id, .. }
             | Operation::SetLineStyle { id, .. }
             | Operation::SetArrowStyle { id, .. }
             | Operation::SetPathStyle { id, .. }
@@ -390,8 +715,18 @@ impl Operation {
             | Operation::SendToBack { id, .. }
             | Operation::SetElementLock { id, .. }
             | Operation::GroupElements { id, .. }
-            | Operation::UngroupElements { id, .. } => Some(id),
-            Operation::FullSync { .. } => None,
+            | Operation::UngroupElements { id, .. }
+            | Operation::MoveGroup { id, .. }
+            | Operation::RotateGroup { id, .. }
+            | Operation::ResizeGroup { id, .. }
+            | Operation::DeleteGroup { id, .. }
+            | Operation::BindConnector { id, .. }
+            | Operation::SetControlPoints { id, .. } => Some(id),
+            Operation::Align { .. }
+            | Operation::Distribute { .. }
+            | Operation::Transform { .. }
+            | Operation::Flip { .. }
+            | Operation::FullSync { .. } => None,
         }
     }
 
@@ -401,11 +736,17 @@ impl Operation {
             Operation::AddRectangle { .. }
                 | Operation::AddEllipse { .. }
                 | Operation::AddDiamond { .. }
+                | Operation::AddPolygon { .. }
+                | Operation::AddRegularPolygon { .. }
+                | Operation::AddStar { .. }
+                | Operation::AddFrame { .. }
+                | Operation::AddLayer { .. }
                 | Operation::AddLine { .. }
                 | Operation::AddArrow { .. }
                 | Operation::AddPath { .. }
                 | Operation::AddImage { .. }
                 | Operation::AddText { .. }
+                | Operation::AddLabel { .. }
                 | Operation::GroupElements { .. }
         )
     }
@@ -415,10 +756,27 @@ fn remap_operation_references<F>(operation: &mut Operation, mut resolve_id: F)
 where
     F: FnMut(u64) -> u64,
 {
-    if let Operation::GroupElements { element_ids, .. } = operation {
-        for id in element_ids.iter_mut() {
-            *id = resolve_id(*id);
+    match operation {
+        Operation::GroupElements { element_ids, .. }
+        | Operation::MoveToLayer { element_ids, .. }
+        | Operation::Align { element_ids, .. }
+        | Operation::Distribute { element_ids, .. }
+        | Operation::Transform { element_ids, .. }
+        | Operation::Flip { element_ids, .. } => {
+            for id in element_ids.iter_mut() {
+                *id = resolve_id(*id);
+            }
+        }
+        Operation::BindConnector {
+            binding: Some(binding),
+            ..
+        } => {
+            binding.element_id = resolve_id(binding.element_id);
+        }
+        Operation::AddLabel { container_id, .. } => {
+            *container_id = resolve_id(*container_id);
         }
+        _ => {}
     }
 }
 
@@ -864,242 +1222,409 @@ pub async fn handle_websocket(
 
 fn apply_operation(operation: &Operation, session: &Session) -> Option<u64> {
     let mut doc = session.document.write().unwrap();
+    doc.begin_transaction("remote");
+    let result = apply_operation_to_document(operation, &mut doc);
+    doc.commit_transaction();
+    result
+}
+
+fn apply_operation_to_document(operation: &Operation, doc: &mut Document) -> Option<u64> {
     use rustboard_editor::geometry::Point as EditorPoint;
+    use rustboard_editor::ElementPatch;
 
     match operation {
         Operation::AddRectangle { position, width, height, .. } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            return Some(doc.add_rectangle_without_snapshot(point, *width, *height));
+            return Some(doc.add_rectangle(point, *width, *height));
         }
         Operation::MoveRectangle { id, position } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            doc.move_rectangle(*id, point, false);
+            doc.move_rectangle(*id, point, true);
         }
         Operation::ResizeRectangle { id, width, height } => {
-            doc.resize_rectangle(*id, *width, *height, false);
+            doc.resize_rectangle(*id, *width, *height, true);
         }
         Operation::DeleteRectangle { id } => {
-            doc.delete_rectangle_without_snapshot(*id);
+            doc.delete_rectangle(*id);
         }
         Operation::AddEllipse { position, radius_x, radius_y, .. } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            return Some(doc.add_ellipse_without_snapshot(point, *radius_x, *radius_y));
+            return Some(doc.add_ellipse(point, *radius_x, *radius_y));
         }
         Operation::MoveEllipse { id, position } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            doc.move_ellipse(*id, point, false);
+            doc.move_ellipse(*id, point, true);
         }
         Operation::ResizeEllipse { id, radius_x, radius_y } => {
-            doc.resize_ellipse(*id, *radius_x, *radius_y, false);
+            doc.resize_ellipse(*id, *radius_x, *radius_y, true);
         }
         Operation::DeleteEllipse { id } => {
-            doc.delete_ellipse_without_snapshot(*id);
+            doc.delete_ellipse(*id);
         }
         Operation::AddDiamond { position, width, height, .. } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            return Some(doc.add_diamond_without_snapshot(point, *width, *height));
+            return Some(doc.add_diamond(point, *width, *height));
         }
         Operation::MoveDiamond { id, position } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            doc.move_diamond(*id, point, false);
+            doc.move_diamond(*id, point, true);
         }
         Operation::ResizeDiamond { id, width, height } => {
-            doc.resize_diamond(*id, *width, *height, false);
+            doc.resize_diamond(*id, *width, *height, true);
         }
         Operation::DeleteDiamond { id } => {
-            doc.delete_diamond_without_snapshot(*id);
+            doc.delete_diamond(*id);
+        }
+        Operation::AddPolygon { points, .. } => {
+            let editor_points: Vec<EditorPoint> = points.iter().map(|p| EditorPoint { x: p.x, y: p.y }).collect();
+            return Some(doc.add_polygon(editor_points));
+        }
+        Operation::MovePolygon { id, offset_x, offset_y } => {
+            doc.move_polygon(*id, *offset_x, *offset_y);
+        }
+        Operation::SetPolygonPoints { id, points } => {
+            let editor_points: Vec<EditorPoint> = points.iter().map(|p| EditorPoint { x: p.x, y: p.y }).collect();
+            doc.set_polygon_points(*id, editor_points);
+        }
+        Operation::DeletePolygon { id } => {
+            doc.delete_polygon(*id);
+        }
+        Operation::AddRegularPolygon { position, width, height, sides, .. } => {
+            let point = EditorPoint { x: position.x, y: position.y };
+            return Some(doc.add_regular_polygon(point, *width, *height, *sides));
+        }
+        Operation::MoveRegularPolygon { id, position } => {
+            let point = EditorPoint { x: position.x, y: position.y };
+            doc.move_regular_polygon(*id, point);
+        }
+        Operation::ResizeRegularPolygon { id, width, height } => {
+            doc.resize_regular_polygon(*id, *width, *height);
+        }
+        Operation::DeleteRegularPolygon { id } => {
+            doc.delete_regular_polygon(*id);
+        }
+        Operation::AddStar { position, width, height, sides, .. } => {
+            let point = EditorPoint { x: position.x, y: position.y };
+            return Some(doc.add_star(point, *width, *height, *sides));
+        }
+        Operation::MoveStar { id, position } => {
+            let point = EditorPoint { x: position.x, y: position.y };
+            doc.move_star(*id, point);
+        }
+        Operation::ResizeStar { id, width, height } => {
+            doc.resize_star(*id, *width, *height);
+        }
+        Operation::DeleteStar { id } => {
+            doc.delete_star(*id);
+        }
+        Operation::AddFrame { position, width, height, name, .. } => {
+            let point = EditorPoint { x: position.x, y: position.y };
+            return Some(doc.add_frame(point, *width, *height, name.clone()));
+        }
+        Operation::MoveFrame { id, position } => {
+            let point = EditorPoint { x: position.x, y: position.y };
+            doc.move_frame(*id, point);
+        }
+        Operation::ResizeFrame { id, width, height } => {
+            doc.resize_frame(*id, *width, *height);
+        }
+        Operation::DeleteFrame { id } => {
+            doc.delete_frame(*id);
+        }
+        Operation::AddLayer { name, .. } => {
+            return Some(doc.add_layer(name.clone()));
+        }
+        Operation::SetLayerStyle { id, name, visible, locked, opacity } => {
+            let patch = ElementPatch {
+                name: name.clone(),
+                visible: *visible,
+                locked: *locked,
+                opacity: *opacity,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
+        }
+        Operation::ReorderLayer { id, index } => {
+            doc.reorder_layer(*id, *index);
+        }
+        Operation::MoveToLayer { id, element_ids } => {
+            doc.move_to_layer(element_ids.clone(), *id);
+        }
+        Operation::DeleteLayer { id } => {
+            doc.delete_layer(*id);
         }
         Operation::AddLine { start, end, .. } => {
             let start_point = EditorPoint { x: start.x, y: start.y };
             let end_point = EditorPoint { x: end.x, y: end.y };
-            return Some(doc.add_line_without_snapshot(start_point, end_point));
+            return Some(doc.add_line(start_point, end_point));
         }
         Operation::MoveLine { id, start, end } => {
             let start_point = EditorPoint { x: start.x, y: start.y };
             let end_point = EditorPoint { x: end.x, y: end.y };
-            doc.move_line(*id, start_point, end_point, false);
+            doc.move_line(*id, start_point, end_point, true);
         }
         Operation::DeleteLine { id } => {
-            doc.delete_line_without_snapshot(*id);
+            doc.delete_line(*id);
         }
         Operation::AddArrow { start, end, .. } => {
             let start_point = EditorPoint { x: start.x, y: start.y };
             let end_point = EditorPoint { x: end.x, y: end.y };
-            return Some(doc.add_arrow_without_snapshot(start_point, end_point));
+            return Some(doc.add_arrow(start_point, end_point));
         }
         Operation::MoveArrow { id, start, end } => {
             let start_point = EditorPoint { x: start.x, y: start.y };
             let end_point = EditorPoint { x: end.x, y: end.y };
-            doc.move_arrow(*id, start_point, end_point, false);
+            doc.move_arrow(*id, start_point, end_point, true);
         }
         Operation::DeleteArrow { id } => {
-            doc.delete_arrow_without_snapshot(*id);
+            doc.delete_arrow(*id);
         }
         Operation::AddPath { points, .. } => {
             let editor_points: Vec<EditorPoint> = points.iter().map(|p| EditorPoint { x: p.x, y: p.y }).collect();
-            return Some(doc.add_path_without_snapshot(editor_points));
+            return Some(doc.add_path(editor_points));
         }
         Operation::MovePath { id, offset_x, offset_y } => {
-            doc.move_path(*id, *offset_x, *offset_y, false);
+            doc.move_path(*id, *offset_x, *offset_y, true);
         }
-        Operation::SetPathPoints { id, points } => {
+        Operation::SetPathPoints { id, points, pressures } => {
             let editor_points: Vec<EditorPoint> = points.iter().map(|p| EditorPoint { x: p.x, y: p.y }).collect();
-            doc.set_path_points(*id, editor_points, false);
+            let patch = ElementPatch {
+                points: Some(editor_points),
+                pressures: pressures.clone(),
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
         Operation::DeletePath { id } => {
-            doc.delete_path_without_snapshot(*id);
+            doc.delete_path(*id);
         }
         Operation::AddImage { position, width, height, image_data, .. } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            return Some(doc.add_image_without_snapshot(point, *width, *height, image_data.clone()));
+            return Some(doc.add_image(point, *width, *height, image_data.clone()));
         }
         Operation::MoveImage { id, position } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            doc.move_image(*id, point, false);
+            doc.move_image(*id, point, true);
         }
         Operation::ResizeImage { id, width, height } => {
-            doc.resize_image(*id, *width, *height, false);
+            doc.resize_image(*id, *width, *height, true);
         }
         Operation::DeleteImage { id } => {
-            doc.delete_image_without_snapshot(*id);
+            doc.delete_image(*id);
         }
         Operation::AddText { position, width, height, content, .. } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            return Some(doc.add_text_without_snapshot(point, *width, *height, content.clone()));
+            return Some(doc.add_text(point, *width, *height, content.clone()));
+        }
+        Operation::AddLabel { container_id, content, .. } => {
+            return doc.add_label(*container_id, content.clone());
         }
         Operation::MoveText { id, position } => {
             let point = EditorPoint { x: position.x, y: position.y };
-            doc.move_text(*id, point, false);
+            doc.move_text(*id, point, true);
         }
         Operation::ResizeText { id, width, height } => {
-            doc.resize_text(*id, *width, *height, false);
+            doc.resize_text(*id, *width, *height, true);
         }
         Operation::UpdateText { id, content } => {
-            doc.set_text_content(*id, content.clone(), false);
+            doc.set_text_content(*id, content.clone(), true);
+        }
+        Operation::InsertText { id, offset, text, style } => {
+            doc.insert_text(*id, *offset, text, style.clone());
+        }
+        Operation::DeleteTextRange { id, start, end } => {
+            doc.delete_text_range(*id, *start, *end);
+        }
+        Operation::SetTextStyleRange { id, start, end, patch } => {
+            doc.set_text_style_range(*id, *start, *end, patch);
+        }
+        Operation::SetTextList { id, start, end, list } => {
+            doc.set_text_list(*id, *start, *end, *list);
         }
         Operation::DeleteText { id } => {
-            doc.delete_text_without_snapshot(*id);
+            doc.delete_text(*id);
         }
-        Operation::SetRectangleStyle { id, stroke_color, fill_color, line_width, dash_pattern, border_radius, rotation_angle } => {
-            if let Some(color) = stroke_color {
-                doc.set_rectangle_stroke_color(*id, color.clone(), false);
-            }
-            if let Some(color) = fill_color {
-                doc.set_rectangle_fill_color(*id, color.clone(), false);
-            }
-            if let Some(width) = line_width {
-                doc.set_rectangle_line_width(*id, *width, false);
-            }
-            if let Some(pattern) = dash_pattern {
-                doc.set_rectangle_dash_pattern(*id, pattern.clone(), false);
-            }
-            if let Some(radius) = border_radius {
-                doc.set_rectangle_border_radius(*id, *radius, false);
-            }
-            if let Some(angle) = rotation_angle {
-                doc.set_rectangle_rotation(*id, *angle, false);
-            }
+        Operation::SetRectangleStyle { id, stroke_color, fill_color, line_width, dash_pattern, border_radius, rotation_angle, opacity, blend_mode, roughness, seed }
+        | Operation::SetDiamondStyle { id, stroke_color, fill_color, line_width, dash_pattern, border_radius, rotation_angle, opacity, blend_mode, roughness, seed } => {
+            let patch = ElementPatch {
+                stroke_color: *stroke_color,
+                fill: fill_color.clone(),
+                line_width: *line_width,
+                dash_pattern: dash_pattern.clone(),
+                border_radius: *border_radius,
+                rotation_angle: *rotation_angle,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                roughness: *roughness,
+                seed: *seed,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
-        Operation::SetEllipseStyle { id, stroke_color, fill_color, line_width, dash_pattern, rotation_angle } => {
-            if let Some(color) = stroke_color {
-                doc.set_ellipse_stroke_color(*id, color.clone(), false);
-            }
-            if let Some(color) = fill_color {
-                doc.set_ellipse_fill_color(*id, color.clone(), false);
-            }
-            if let Some(width) = line_width {
-                doc.set_ellipse_line_width(*id, *width, false);
-            }
-            if let Some(pattern) = dash_pattern {
-                doc.set_ellipse_dash_pattern(*id, pattern.clone(), false);
-            }
-            if let Some(angle) = rotation_angle {
-                doc.set_ellipse_rotation(*id, *angle, false);
-            }
+        Operation::SetPolygonStyle { id, stroke_color, fill_color, line_width, dash_pattern, border_radius, rotation_angle, opacity, blend_mode } => {
+            let patch = ElementPatch {
+                stroke_color: *stroke_color,
+                fill: fill_color.clone(),
+                line_width: *line_width,
+                dash_pattern: dash_pattern.clone(),
+                border_radius: *border_radius,
+                rotation_angle: *rotation_angle,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
-        Operation::SetDiamondStyle { id, stroke_color, fill_color, line_width, dash_pattern, border_radius, rotation_angle } => {
-            if let Some(color) = stroke_color {
-                doc.set_diamond_stroke_color(*id, color.clone(), false);
-            }
-            if let Some(color) = fill_color {
-                doc.set_diamond_fill_color(*id, color.clone(), false);
-            }
-            if let Some(width) = line_width {
-                doc.set_diamond_line_width(*id, *width, false);
-            }
-            if let Some(pattern) = dash_pattern {
-                doc.set_diamond_dash_pattern(*id, pattern.clone(), false);
-            }
-            if let Some(radius) = border_radius {
-                doc.set_diamond_border_radius(*id, *radius, false);
-            }
-            if let Some(angle) = rotation_angle {
-                doc.set_diamond_rotation(*id, *angle, false);
-            }
+        Operation::SetRegularPolygonStyle { id, stroke_color, fill_color, line_width, dash_pattern, border_radius, rotation_angle, sides, opacity, blend_mode } => {
+            let patch = ElementPatch {
+                stroke_color: *stroke_color,
+                fill: fill_color.clone(),
+                line_width: *line_width,
+                dash_pattern: dash_pattern.clone(),
+                border_radius: *border_radius,
+                rotation_angle: *rotation_angle,
+                sides: *sides,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
-        Operation::SetLineStyle { id, stroke_color, line_width, dash_pattern } => {
-            if let Some(color) = stroke_color {
-                doc.set_line_stroke_color(*id, color.clone(), false);
-            }
-            if let Some(width) = line_width {
-                doc.set_line_line_width(*id, *width, false);
-            }
-            if let Some(pattern) = dash_pattern {
-                doc.set_line_dash_pattern(*id, pattern.clone(), false);
-            }
+        Operation::SetStarStyle {
+            id,
+            stroke_color,
+            fill_color,
+            line_width,
+            dash_pattern,
+            border_radius,
+            rotation_angle,
+            sides,
+            inner_radius,
+            opacity,
+            blend_mode,
+        } => {
+            let patch = ElementPatch {
+                stroke_color: *stroke_color,
+                fill: fill_color.clone(),
+                line_width: *line_width,
+                dash_pattern: dash_pattern.clone(),
+                border_radius: *border_radius,
+                rotation_angle: *rotation_angle,
+                sides: *sides,
+                inner_radius: *inner_radius,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
-        Operation::SetArrowStyle { id, stroke_color, line_width, dash_pattern } => {
-            if let Some(color) = stroke_color {
-                doc.set_arrow_stroke_color(*id, color.clone(), false);
-            }
-            if let Some(width) = line_width {
-                doc.set_arrow_line_width(*id, *width, false);
-            }
-            if let Some(pattern) = dash_pattern {
-                doc.set_arrow_dash_pattern(*id, pattern.clone(), false);
-            }
+        Operation::SetFrameStyle { id, name, stroke_color, fill_color, line_width, clip } => {
+            let patch = ElementPatch {
+                name: name.clone(),
+                stroke_color: *stroke_color,
+                fill: fill_color.map(|color| color.map(Fill::Solid)),
+                line_width: *line_width,
+                clip: *clip,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
-        Operation::SetPathStyle { id, stroke_color, line_width, dash_pattern, rotation_angle } => {
-            if let Some(color) = stroke_color {
-                doc.set_path_stroke_color(*id, color.clone(), false);
-            }
-            if let Some(width) = line_width {
-                doc.set_path_line_width(*id, *width, false);
-            }
-            if let Some(pattern) = dash_pattern {
-                doc.set_path_dash_pattern(*id, pattern.clone(), false);
-            }
-            if let Some(angle) = rotation_angle {
-                doc.set_path_rotation(*id, *angle, false);
-            }
+        Operation::SetEllipseStyle { id, stroke_color, fill_color, line_width, dash_pattern, rotation_angle, opacity, blend_mode, roughness, seed } => {
+            let patch = ElementPatch {
+                stroke_color: *stroke_color,
+                fill: fill_color.clone(),
+                line_width: *line_width,
+                dash_pattern: dash_pattern.clone(),
+                rotation_angle: *rotation_angle,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                roughness: *roughness,
+                seed: *seed,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
-        Operation::SetImageStyle { id, rotation_angle } => {
-            if let Some(angle) = rotation_angle {
-                doc.set_image_rotation(*id, *angle, false);
-            }
+        Operation::SetLineStyle {
+            id,
+            stroke_color,
+            line_width,
+            dash_pattern,
+            routing,
+            start_marker,
+            end_marker,
+            marker_size,
+            opacity,
+            blend_mode,
+            roughness,
+            seed,
         }
-        Operation::SetTextStyle { id, color, opacity, font_size, font_family, font_weight, text_align, rotation_angle } => {
-            if let Some(c) = color {
-                doc.set_text_color(*id, c.clone(), false);
-            }
-            if let Some(value) = opacity {
-                doc.set_text_opacity(*id, *value, false);
-            }
-            if let Some(size) = font_size {
-                doc.set_text_font_size(*id, *size, false);
-            }
-            if let Some(family) = font_family {
-                doc.set_text_font_family(*id, family.clone(), false);
-            }
-            if let Some(weight) = font_weight {
-                doc.set_text_font_weight(*id, weight.clone(), false);
-            }
-            if let Some(align) = text_align {
-                doc.set_text_text_align(*id, align.clone(), false);
-            }
-            if let Some(angle) = rotation_angle {
-                doc.set_text_rotation(*id, *angle, false);
-            }
+        | Operation::SetArrowStyle {
+            id,
+            stroke_color,
+            line_width,
+            dash_pattern,
+            routing,
+            start_marker,
+            end_marker,
+            marker_size,
+            opacity,
+            blend_mode,
+            roughness,
+            seed,
+        } => {
+            let patch = ElementPatch {
+                stroke_color: *stroke_color,
+                line_width: *line_width,
+                dash_pattern: dash_pattern.clone(),
+                routing: *routing,
+                start_marker: *start_marker,
+                end_marker: *end_marker,
+                marker_size: *marker_size,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                roughness: *roughness,
+                seed: *seed,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
+        }
+        Operation::SetPathStyle { id, stroke_color, line_width, dash_pattern, rotation_angle, smoothing, opacity, blend_mode } => {
+            let patch = ElementPatch {
+                stroke_color: *stroke_color,
+                line_width: *line_width,
+                dash_pattern: dash_pattern.clone(),
+                rotation_angle: *rotation_angle,
+                smoothing: *smoothing,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
+        }
+        Operation::SetImageStyle { id, rotation_angle, opacity, blend_mode } => {
+            let patch = ElementPatch {
+                rotation_angle: *rotation_angle,
+                opacity: *opacity,
+                blend_mode: *blend_mode,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
+        }
+        Operation::SetTextStyle { id, color, opacity, font_size, font_family, font_weight, text_align, sizing, vertical_align, rotation_angle, blend_mode } => {
+            let patch = ElementPatch {
+                color: *color,
+                opacity: *opacity,
+                font_size: *font_size,
+                font_family: font_family.clone(),
+                font_weight: *font_weight,
+                text_align: *text_align,
+                sizing: *sizing,
+                vertical_align: *vertical_align,
+                rotation_angle: *rotation_angle,
+                blend_mode: *blend_mode,
+                ..Default::default()
+            };
+            doc.update(*id, &patch);
         }
         Operation::BringToFront { id } => {
             doc.bring_shape_to_front(*id);
@@ -1114,7 +1639,7 @@ fn apply_operation(operation: &Operation, session: &Session) -> Option<u64> {
             doc.send_shape_to_back(*id);
         }
         Operation::SetElementLock { id, locked } => {
-            doc.set_element_locked(*id, *locked, false);
+            doc.set_element_locked(*id, *locked, true);
         }
         Operation::GroupElements { element_ids, .. } => {
             return Some(doc.group_elements(element_ids.clone()));
@@ -1122,6 +1647,38 @@ fn apply_operation(operation: &Operation, session: &Session) -> Option<u64> {
         Operation::UngroupElements { id } => {
             doc.ungroup_elements(*id);
         }
+        Operation::MoveGroup { id, offset_x, offset_y } => {
+            doc.move_group(*id, *offset_x, *offset_y);
+        }
+        Operation::RotateGroup { id, angle } => {
+            doc.rotate_group(*id, *angle);
+        }
+        Operation::ResizeGroup { id, x, y, width, height } => {
+            doc.resize_group(*id, *x, *y, *width, *height);
+        }
+        Operation::DeleteGroup { id } => {
+            doc.delete_group(*id);
+        }
+        Operation::Align { element_ids, alignment } => {
+            doc.align(element_ids, *alignment);
+        }
+        Operation::Distribute { element_ids, axis, spacing } => {
+            doc.distribute(element_ids, *axis, *spacing);
+        }
+        Operation::Transform { element_ids, transform, origin } => {
+            doc.transform(element_ids, transform, EditorPoint::new(origin.x, origin.y));
+        }
+        Operation::Flip { element_ids, axis } => {
+            doc.flip(element_ids, *axis);
+        }
+        Operation::BindConnector { id, end, binding } => {
+            doc.bind_connector(*id, *end, *binding);
+        }
+        Operation::SetControlPoints { id, control_points } => {
+            let editor_points: Vec<EditorPoint> =
+                control_points.iter().map(|p| EditorPoint { x: p.x, y: p.y }).collect();
+            doc.set_control_points(*id, editor_points);
+        }
         Operation::FullSync { data } => {
             doc.deserialize(data);
         }